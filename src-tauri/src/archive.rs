@@ -0,0 +1,506 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::encode::EncodeSettings;
+use crate::fs::unique_media_path;
+use crate::jobs::{start_job, Job};
+use crate::media::probe_media_info;
+use crate::project::{read_project, write_migrated_project, write_project, LoadedProject, ProjectFile, PROJECT_FILE};
+use crate::relink::find_offline_media;
+use crate::sandbox::{activate_project_dir, CommandError, PathPolicy};
+use crate::trim::{plan_trim_for_file, run_trim, SegmentMethod, TrimMode};
+
+// Extra source kept on each side of the used range when trimming
+const DEFAULT_HANDLE_SEC: f64 = 1.0;
+
+// Slack allowed between a clip's trim window and the kept range, in seconds
+const RANGE_EPSILON: f64 = 0.001;
+
+// Containers that can hold the libx264/aac output of a re-encoded trim
+const TRIM_CONTAINERS: &[&str] = &["mp4", "mov", "m4v", "mkv"];
+
+/// How `consolidate_project` packages the project
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsolidateOptions {
+    /// Write a `.zip` archive instead of a folder
+    #[serde(default)]
+    pub zip: bool,
+    /// Copy only the used range of each source plus handles
+    #[serde(default)]
+    pub trim: bool,
+    /// Seconds kept before and after the used range (default 1)
+    pub handle_sec: Option<f64>,
+    /// How trimmed sources are cut (default re-encode, which is frame accurate)
+    pub trim_mode: Option<TrimMode>,
+}
+
+/// One source as packaged
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsolidatedMedia {
+    pub source: String,
+    /// Path inside the package, relative to the project folder
+    pub path: String,
+    /// Range of the source that was kept, when it was trimmed
+    pub trimmed_range: Option<(f64, f64)>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsolidateResult {
+    /// The folder or `.zip` that was written
+    pub path: String,
+    pub media: Vec<ConsolidatedMedia>,
+}
+
+/// Union of the source ranges the clips use, widened by `handle` and clamped
+/// to the source duration when it is known
+fn used_range(project: &ProjectFile, source: &str, handle: f64, duration: Option<f64>) -> Option<(f64, f64)> {
+    let (start, end) = project
+        .main_track
+        .iter()
+        .chain(&project.overlay_track)
+        .filter(|item| item.path == source)
+        .map(|item| (item.source_in(), item.source_out()))
+        .reduce(|(a0, a1), (b0, b1)| (a0.min(b0), a1.max(b1)))?;
+
+    let start = (start - handle).max(0.0);
+    let end = match duration {
+        Some(duration) => (end + handle).min(duration),
+        None => end + handle,
+    };
+    (end > start).then_some((start, end))
+}
+
+/// Shift the trim windows of clips using `old` after their source was cut to
+/// `range`, and point them at `new`. Fails, leaving the project untouched, if
+/// a clip uses footage outside the kept range.
+fn rebase_clips(project: &mut ProjectFile, old: &str, new: &str, range: (f64, f64)) -> Result<(), String> {
+    let (start, end) = range;
+    let outside = project
+        .main_track
+        .iter()
+        .chain(&project.overlay_track)
+        .filter(|item| item.path == old)
+        .find(|item| item.source_in() < start - RANGE_EPSILON || item.source_out() > end + RANGE_EPSILON);
+    if let Some(item) = outside {
+        return Err(format!(
+            "Clip {} uses {}..{}s of {}, outside the kept range {}..{}s",
+            item.id, item.source_in(), item.source_out(), old, start, end
+        ));
+    }
+
+    for item in project.main_track.iter_mut().chain(project.overlay_track.iter_mut()) {
+        if item.path == old {
+            let (source_in, source_out) = (item.source_in(), item.source_out());
+            item.in_sec = Some((source_in - start).max(0.0));
+            item.out_sec = Some((source_out - start).clamp(0.0, end - start));
+            item.path = new.to_string();
+        }
+    }
+    Ok(())
+}
+
+/// Copy or trim one source into `media_dir`, updating the project's clips
+async fn package_source(
+    job: &Job,
+    project: &mut ProjectFile,
+    source: &str,
+    media_dir: &Path,
+    options: &ConsolidateOptions,
+) -> Result<ConsolidatedMedia, String> {
+    let source_path = Path::new(source);
+    let file_name = source_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid media path: {}", source))?;
+
+    let range = if options.trim {
+        let path = source.to_string();
+        let duration = tauri::async_runtime::spawn_blocking(move || probe_media_info(&path))
+            .await
+            .map_err(|e| format!("Failed to probe {}: {}", source, e))?
+            .ok()
+            .and_then(|info| info.duration);
+        let handle = options.handle_sec.unwrap_or(DEFAULT_HANDLE_SEC).max(0.0);
+        // Not worth cutting when the whole source is used anyway
+        used_range(project, source, handle, duration)
+            .filter(|(start, end)| duration.is_some_and(|d| *start > 0.0 || *end < d))
+    } else {
+        None
+    };
+
+    let Some((start, end)) = range else {
+        let destination = unique_media_path(media_dir, &file_name);
+        tokio::fs::copy(source_path, &destination).await
+            .map_err(|e| format!("Failed to copy {}: {}", source, e))?;
+        project.replace_path(source, &destination.display().to_string());
+        return Ok(ConsolidatedMedia {
+            source: source.to_string(),
+            path: format!("media/{}", destination.file_name().unwrap_or_default().to_string_lossy()),
+            trimmed_range: None,
+        });
+    };
+
+    let mode = options.trim_mode.unwrap_or_default();
+    let path = source.to_string();
+    let (plan, info) = tauri::async_runtime::spawn_blocking(move || plan_trim_for_file(&path, start, end, mode))
+        .await
+        .map_err(|e| format!("Failed to plan trim: {}", e))??;
+
+    // Re-encoded parts are H.264/AAC, which not every source container can hold
+    let extension = source_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let encodes = plan.segments.iter().any(|s| s.method == SegmentMethod::Encode);
+    let file_name = if encodes && !TRIM_CONTAINERS.contains(&extension.as_str()) {
+        Path::new(&file_name).with_extension("mp4").display().to_string()
+    } else {
+        file_name
+    };
+
+    let destination = unique_media_path(media_dir, &file_name);
+    let output = destination.display().to_string();
+    run_trim(job, source, &output, &plan, info.as_ref(), &EncodeSettings::default()).await?;
+
+    rebase_clips(project, source, &output, (plan.start, plan.end))?;
+    Ok(ConsolidatedMedia {
+        source: source.to_string(),
+        path: format!("media/{}", destination.file_name().unwrap_or_default().to_string_lossy()),
+        trimmed_range: Some((plan.start, plan.end)),
+    })
+}
+
+/// Fail unless `dir` is missing or an empty directory
+fn ensure_empty_destination(dir: &Path) -> Result<(), String> {
+    match std::fs::read_dir(dir) {
+        Ok(mut entries) => match entries.next() {
+            Some(_) => Err(format!("Destination is not empty: {}", dir.display())),
+            None => Ok(()),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to read destination {}: {}", dir.display(), e)),
+    }
+}
+
+/// Remove a partially written destination, keeping the folder itself if it
+/// existed (empty) beforehand
+fn discard_destination(dir: &Path, existed: bool) {
+    let _ = std::fs::remove_dir_all(dir);
+    if existed {
+        let _ = std::fs::create_dir(dir);
+    }
+}
+
+/// Build a self-contained project folder at `package_dir`
+async fn build_package(
+    job: &Job,
+    project_dir: &Path,
+    package_dir: &Path,
+    options: &ConsolidateOptions,
+) -> Result<Vec<ConsolidatedMedia>, String> {
+    let dir = project_dir.to_path_buf();
+    let LoadedProject { mut project, .. } = tauri::async_runtime::spawn_blocking(move || read_project(&dir))
+        .await
+        .map_err(|e| format!("Failed to read project: {}", e))??;
+
+    let check = find_offline_media(&project);
+    if !check.offline.is_empty() {
+        let missing: Vec<&str> = check.offline.iter().map(|m| m.path.as_str()).collect();
+        return Err(format!("Cannot consolidate with offline media: {}", missing.join(", ")));
+    }
+
+    let media_dir = package_dir.join("media");
+    for dir in [&media_dir, &package_dir.join("exports")] {
+        tokio::fs::create_dir_all(dir).await
+            .map_err(|e| format!("Failed to create directory {}: {}", dir.display(), e))?;
+    }
+
+    // Only fingerprints of packaged media are meaningful in the copy
+    project.media.clear();
+
+    let mut media = Vec::new();
+    for source in project.source_paths() {
+        if job.is_cancelled() {
+            return Err(job.cancelled_error());
+        }
+        media.push(package_source(job, &mut project, &source, &media_dir, options).await?);
+    }
+
+    let dir = package_dir.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || write_project(&dir, project))
+        .await
+        .map_err(|e| format!("Failed to save project: {}", e))??;
+    Ok(media)
+}
+
+/// Zip a package folder (project file plus `media/`) into `archive`
+fn write_archive(package_dir: &Path, archive: &Path) -> Result<(), String> {
+    let temp_path = archive.with_extension("zip.part");
+    let result = (|| -> Result<(), String> {
+        let file = File::create(&temp_path)
+            .map_err(|e| format!("Failed to create archive: {}", e))?;
+        let mut writer = ZipWriter::new(file);
+
+        let mut entries = BTreeMap::new();
+        entries.insert(PROJECT_FILE.to_string(), package_dir.join(PROJECT_FILE));
+        let media = std::fs::read_dir(package_dir.join("media"))
+            .map_err(|e| format!("Failed to read media directory: {}", e))?;
+        for entry in media.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            entries.insert(format!("media/{}", name), entry.path());
+        }
+
+        for (name, path) in entries {
+            let size = std::fs::metadata(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+                .len();
+            // Media is already compressed; only the project file is deflated
+            let method = if name == PROJECT_FILE {
+                CompressionMethod::Deflated
+            } else {
+                CompressionMethod::Stored
+            };
+            let options = SimpleFileOptions::default()
+                .compression_method(method)
+                .large_file(size >= u32::MAX as u64);
+
+            writer
+                .start_file(name.as_str(), options)
+                .map_err(|e| format!("Failed to add {} to archive: {}", name, e))?;
+            let mut input = File::open(&path)
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            std::io::copy(&mut input, &mut writer)
+                .map_err(|e| format!("Failed to add {} to archive: {}", name, e))?;
+        }
+
+        let file = writer
+            .finish()
+            .map_err(|e| format!("Failed to write archive: {}", e))?;
+        file.sync_all()
+            .map_err(|e| format!("Failed to write archive: {}", e))?;
+        std::fs::rename(&temp_path, archive)
+            .map_err(|e| format!("Failed to write archive: {}", e))
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+async fn consolidate_internal(
+    job: &Job,
+    project_dir: &Path,
+    destination: &Path,
+    options: &ConsolidateOptions,
+) -> Result<ConsolidateResult, String> {
+    if options.zip {
+        if destination.exists() {
+            return Err(format!("Destination already exists: {}", destination.display()));
+        }
+        // Staged next to the archive, where writes are already allowed
+        let file_name = destination.file_name().unwrap_or_default().to_string_lossy();
+        let staging = destination.with_file_name(format!(".{}.{}.staging", file_name, job.id()));
+        let result = async {
+            let media = build_package(job, project_dir, &staging, options).await?;
+            let (package, archive) = (staging.clone(), destination.to_path_buf());
+            tauri::async_runtime::spawn_blocking(move || write_archive(&package, &archive))
+                .await
+                .map_err(|e| format!("Failed to write archive: {}", e))??;
+            Ok(media)
+        }
+        .await;
+        let _ = std::fs::remove_dir_all(&staging);
+        return result.map(|media| ConsolidateResult {
+            path: destination.display().to_string(),
+            media,
+        });
+    }
+
+    ensure_empty_destination(destination)?;
+    let existed = destination.exists();
+    let result = build_package(job, project_dir, destination, options).await;
+    if result.is_err() {
+        discard_destination(destination, existed);
+    }
+    result.map(|media| ConsolidateResult {
+        path: destination.display().to_string(),
+        media,
+    })
+}
+
+/// Package a saved project with only the media its timeline uses, so it can
+/// be handed to another editor.
+///
+/// Writes a project folder at `destination` (which must be missing or empty),
+/// or a `.zip` of one when `options.zip` is set. Sources can be trimmed to the
+/// used ranges plus handles; clip trim points are rewritten to match.
+#[tauri::command]
+pub async fn consolidate_project(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    project_dir: String,
+    destination: String,
+    options: Option<ConsolidateOptions>,
+    job_id: Option<String>,
+) -> Result<ConsolidateResult, CommandError> {
+    policy.check_write(Path::new(&destination))?;
+
+    let options = options.unwrap_or_default();
+    let job = start_job(&app, "consolidate", job_id)?;
+    let result = consolidate_internal(&job, Path::new(&project_dir), Path::new(&destination), &options).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+/// Extract an archive into `destination`, rejecting entries that would land
+/// outside it
+fn extract_archive(archive: &Path, destination: &Path) -> Result<(), String> {
+    let file = File::open(archive)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive: {}", e))?;
+        let relative: PathBuf = entry
+            .enclosed_name()
+            .ok_or_else(|| format!("Archive entry has an unsafe path: {}", entry.name()))?;
+        let path = destination.join(relative);
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)
+                .map_err(|e| format!("Failed to create directory {}: {}", path.display(), e))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
+        let mut output = File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        std::io::copy(&mut entry, &mut output)
+            .map_err(|e| format!("Failed to extract {}: {}", entry.name(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Unpack a consolidated project archive into `destination` (which must be
+/// missing or empty) and load it. The destination becomes the active project
+/// directory.
+#[tauri::command]
+pub async fn import_project_archive(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    archive_path: String,
+    destination: String,
+) -> Result<LoadedProject, CommandError> {
+    policy.check_write(Path::new(&destination))?;
+
+    let dir = destination.clone();
+    let loaded = tauri::async_runtime::spawn_blocking(move || {
+        let destination = Path::new(&dir);
+        ensure_empty_destination(destination)?;
+        let existed = destination.exists();
+
+        let result = extract_archive(Path::new(&archive_path), destination).and_then(|_| {
+            if !destination.join(PROJECT_FILE).exists() {
+                return Err(format!("Archive does not contain {}", PROJECT_FILE));
+            }
+            let mut loaded = read_project(destination)?;
+            write_migrated_project(destination, &mut loaded)?;
+            Ok(loaded)
+        });
+        if result.is_err() {
+            discard_destination(destination, existed);
+        }
+        result
+    })
+    .await
+    .map_err(|e| format!("Failed to import project archive: {}", e))??;
+
+    activate_project_dir(&app, Path::new(&destination))?;
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn clip(id: &str, path: &str, in_sec: f64, out_sec: f64) -> serde_json::Value {
+        json!({ "id": id, "path": path, "startTime": 0.0, "durationSec": out_sec - in_sec, "inSec": in_sec, "outSec": out_sec })
+    }
+
+    fn project(main: serde_json::Value, overlay: serde_json::Value) -> ProjectFile {
+        serde_json::from_value(json!({
+            "version": 1,
+            "id": "project-1",
+            "name": "Interview",
+            "createdAt": "2026-01-01T00:00:00Z",
+            "updatedAt": "2026-01-01T00:00:00Z",
+            "settings": { "resolution": { "width": 1920, "height": 1080 }, "frameRate": 30, "outputFormat": "mp4" },
+            "mainTrack": main,
+            "overlayTrack": overlay
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn used_range_is_the_union_of_both_tracks() {
+        let project = project(
+            json!([clip("a", "/m/a.mp4", 10.0, 12.0), clip("b", "/m/b.mp4", 0.0, 50.0)]),
+            json!([clip("c", "/m/a.mp4", 4.0, 6.0), clip("d", "/m/a.mp4", 20.0, 25.0)]),
+        );
+        assert_eq!(used_range(&project, "/m/a.mp4", 0.0, None), Some((4.0, 25.0)));
+        assert_eq!(used_range(&project, "/m/missing.mp4", 1.0, None), None);
+    }
+
+    #[test]
+    fn used_range_handles_are_clamped_to_the_source() {
+        let project = project(json!([clip("a", "/m/a.mp4", 0.5, 9.5)]), json!([]));
+        assert_eq!(used_range(&project, "/m/a.mp4", 1.0, Some(10.0)), Some((0.0, 10.0)));
+        assert_eq!(used_range(&project, "/m/a.mp4", 1.0, None), Some((0.0, 10.5)));
+        assert_eq!(used_range(&project, "/m/a.mp4", 0.25, Some(60.0)), Some((0.25, 9.75)));
+    }
+
+    #[test]
+    fn rebase_shifts_clips_into_the_kept_range() {
+        let mut project = project(
+            json!([clip("a", "/m/a.mp4", 10.0, 12.0), clip("b", "/m/b.mp4", 10.0, 12.0)]),
+            json!([clip("c", "/m/a.mp4", 20.0, 25.0)]),
+        );
+        rebase_clips(&mut project, "/m/a.mp4", "/p/media/a.mp4", (9.0, 26.0)).unwrap();
+
+        let a = &project.main_track[0];
+        assert_eq!((a.path.as_str(), a.in_sec, a.out_sec), ("/p/media/a.mp4", Some(1.0), Some(3.0)));
+        let c = &project.overlay_track[0];
+        assert_eq!((c.path.as_str(), c.in_sec, c.out_sec), ("/p/media/a.mp4", Some(11.0), Some(16.0)));
+        // Other sources are left alone
+        let b = &project.main_track[1];
+        assert_eq!((b.path.as_str(), b.in_sec, b.out_sec), ("/m/b.mp4", Some(10.0), Some(12.0)));
+    }
+
+    #[test]
+    fn rebase_rejects_clips_outside_the_kept_range() {
+        let mut project = project(
+            json!([clip("a", "/m/a.mp4", 10.0, 12.0), clip("b", "/m/a.mp4", 20.0, 30.0)]),
+            json!([]),
+        );
+        let err = rebase_clips(&mut project, "/m/a.mp4", "/p/media/a.mp4", (9.0, 26.0)).unwrap_err();
+        assert!(err.contains("Clip b"), "{}", err);
+        // Nothing was rewritten
+        assert!(project.main_track.iter().all(|item| item.path == "/m/a.mp4"));
+        assert_eq!(project.main_track[0].in_sec, Some(10.0));
+    }
+}
@@ -0,0 +1,334 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::fs::write_file_atomic;
+use crate::project::{parse_project_json, stored_project_json, ProjectFile};
+use crate::sandbox::{CommandError, PathPolicy};
+
+// How often pending snapshots are written
+const AUTOSAVE_INTERVAL_SECS: u64 = 30;
+
+// Recovery points kept per project; older ones are deleted
+const MAX_RECOVERY_POINTS: usize = 10;
+
+// Present while an autosave session is running; left behind by a crash
+const SESSION_LOCK_FILE: &str = "session.lock";
+
+const RECOVERY_PREFIX: &str = "recovery-";
+
+/// Autosave folder inside the project's `.temp` directory
+fn autosave_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(".temp").join("autosave")
+}
+
+struct AutosaveSession {
+    id: u64,
+    project_dir: PathBuf,
+    pending: Option<ProjectFile>,
+}
+
+/// Autosave state for the open project, managed as Tauri state
+#[derive(Default)]
+pub struct AutosaveManager {
+    session: Mutex<Option<AutosaveSession>>,
+    next_id: Mutex<u64>,
+}
+
+impl AutosaveManager {
+    /// Take the pending snapshot of session `id`; None once the session has ended
+    fn take_pending(&self, id: u64) -> Option<(PathBuf, Option<ProjectFile>)> {
+        let mut session = self.session.lock().unwrap();
+        let session = session.as_mut().filter(|s| s.id == id)?;
+        Some((session.project_dir.clone(), session.pending.take()))
+    }
+
+    /// End the current session: write any pending snapshot and remove the lock
+    pub fn shutdown(&self) -> Result<(), String> {
+        let Some(session) = self.session.lock().unwrap().take() else {
+            return Ok(());
+        };
+        if let Some(project) = &session.pending {
+            write_recovery_point(&session.project_dir, project)?;
+        }
+        let _ = std::fs::remove_file(autosave_dir(&session.project_dir).join(SESSION_LOCK_FILE));
+        Ok(())
+    }
+}
+
+/// A timeline snapshot that can be restored
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryPoint {
+    pub id: String,
+    pub saved_at: DateTime<Utc>,
+    pub project_name: String,
+    pub clip_count: usize,
+}
+
+/// Result of starting autosave for a project
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutosaveStatus {
+    /// The previous session did not shut down cleanly
+    pub unclean_shutdown: bool,
+    pub recovery_points: Vec<RecoveryPoint>,
+}
+
+/// Write one snapshot and prune the oldest beyond the limit
+fn write_recovery_point(project_dir: &Path, project: &ProjectFile) -> Result<(), String> {
+    let dir = autosave_dir(project_dir);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create autosave directory: {}", e))?;
+
+    let mut project = project.clone();
+    project.updated_at = Utc::now();
+    let id = format!("{}{}", RECOVERY_PREFIX, project.updated_at.format("%Y%m%dT%H%M%S%3f"));
+    let json = stored_project_json(project_dir, &project)?;
+    write_file_atomic(&dir.join(format!("{}.json", id)), json.as_bytes())?;
+    prune_recovery_points(project_dir)
+}
+
+/// Delete readable recovery points beyond the limit, oldest first. Counts the
+/// same snapshots `list_points` offers, so unreadable files never push a
+/// restorable one out.
+fn prune_recovery_points(project_dir: &Path) -> Result<(), String> {
+    let dir = autosave_dir(project_dir);
+    for old in list_points(project_dir)?.iter().skip(MAX_RECOVERY_POINTS) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", old.id)));
+    }
+    Ok(())
+}
+
+/// IDs of stored recovery points, oldest first
+fn recovery_point_ids(project_dir: &Path) -> Result<Vec<String>, String> {
+    let dir = autosave_dir(project_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read autosave directory: {}", e))?;
+
+    let mut ids: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let id = name.strip_suffix(".json")?;
+            id.starts_with(RECOVERY_PREFIX).then(|| id.to_string())
+        })
+        .collect();
+    // Timestamps in the IDs sort chronologically
+    ids.sort();
+    Ok(ids)
+}
+
+/// Read a recovery point, returning clip paths as stored
+fn read_recovery_point(project_dir: &Path, id: &str) -> Result<ProjectFile, String> {
+    let valid = id.starts_with(RECOVERY_PREFIX)
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if !valid {
+        return Err(format!("Invalid recovery point id: {}", id));
+    }
+
+    let path = autosave_dir(project_dir).join(format!("{}.json", id));
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read recovery point: {}", e))?;
+    parse_project_json(&json).map(|(project, _)| project)
+}
+
+/// All readable recovery points, newest first; unreadable ones are skipped
+fn list_points(project_dir: &Path) -> Result<Vec<RecoveryPoint>, String> {
+    let mut points: Vec<RecoveryPoint> = recovery_point_ids(project_dir)?
+        .into_iter()
+        .filter_map(|id| {
+            let project = read_recovery_point(project_dir, &id).ok()?;
+            Some(RecoveryPoint {
+                id,
+                saved_at: project.updated_at,
+                project_name: project.name,
+                clip_count: project.main_track.len() + project.overlay_track.len(),
+            })
+        })
+        .collect();
+    points.reverse();
+    Ok(points)
+}
+
+/// Start autosaving a project. Any previous session is ended first.
+///
+/// Reports whether the last session for this project crashed, so the
+/// frontend can offer its recovery points.
+#[tauri::command]
+pub async fn start_autosave(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    project_dir: String,
+) -> Result<AutosaveStatus, CommandError> {
+    let project_dir = PathBuf::from(project_dir);
+    let dir = policy.check_write(&autosave_dir(&project_dir))?;
+
+    let handle = app.clone();
+    let (id, status) = tauri::async_runtime::spawn_blocking(move || {
+        let autosave = handle.state::<AutosaveManager>();
+        autosave.shutdown()?;
+
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create autosave directory: {}", e))?;
+
+        let lock = dir.join(SESSION_LOCK_FILE);
+        let unclean_shutdown = lock.exists();
+        write_file_atomic(&lock, Utc::now().to_rfc3339().as_bytes())?;
+
+        let id = {
+            let mut next_id = autosave.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        *autosave.session.lock().unwrap() = Some(AutosaveSession {
+            id,
+            project_dir: project_dir.clone(),
+            pending: None,
+        });
+
+        let status = AutosaveStatus {
+            unclean_shutdown,
+            recovery_points: list_points(&project_dir)?,
+        };
+        Ok::<_, String>((id, status))
+    })
+    .await
+    .map_err(|e| format!("Failed to start autosave: {}", e))??;
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(AUTOSAVE_INTERVAL_SECS));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let manager = app.state::<AutosaveManager>();
+            let Some((project_dir, pending)) = manager.take_pending(id) else {
+                break;
+            };
+            let Some(project) = pending else {
+                continue;
+            };
+            let written = tauri::async_runtime::spawn_blocking(move || {
+                write_recovery_point(&project_dir, &project)
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+            if let Err(e) = written {
+                eprintln!("Autosave failed: {}", e);
+            }
+        }
+    });
+
+    Ok(status)
+}
+
+/// Hand the latest project state to the autosave service; it is written on
+/// the next autosave tick
+#[tauri::command]
+pub async fn update_autosave(
+    autosave: tauri::State<'_, AutosaveManager>,
+    project: ProjectFile,
+) -> Result<(), String> {
+    let mut session = autosave.session.lock().unwrap();
+    let session = session.as_mut().ok_or("Autosave is not running")?;
+    session.pending = Some(project);
+    Ok(())
+}
+
+/// Stop autosaving (e.g. when the project is closed), flushing pending changes
+#[tauri::command]
+pub async fn stop_autosave(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<AutosaveManager>().shutdown())
+        .await
+        .map_err(|e| format!("Failed to stop autosave: {}", e))?
+}
+
+/// List a project's recovery points, newest first
+#[tauri::command]
+pub async fn list_recovery_points(project_dir: String) -> Result<Vec<RecoveryPoint>, String> {
+    tauri::async_runtime::spawn_blocking(move || list_points(Path::new(&project_dir)))
+        .await
+        .map_err(|e| format!("Failed to list recovery points: {}", e))?
+}
+
+/// Load a recovery point. The project file itself is not modified; save the
+/// returned project to keep it.
+#[tauri::command]
+pub async fn restore_recovery_point(project_dir: String, id: String) -> Result<ProjectFile, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let project_dir = Path::new(&project_dir);
+        let mut project = read_recovery_point(project_dir, &id)?;
+        project.resolve_paths(project_dir);
+        Ok(project)
+    })
+    .await
+    .map_err(|e| format!("Failed to restore recovery point: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A project directory with an autosave folder, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("trimbot_autosave_{}_{}", name, uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(autosave_dir(&dir)).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_point(project_dir: &Path, index: usize) {
+        // An empty document migrates to a default project
+        let (project, _) = parse_project_json("{}").unwrap();
+        let json = stored_project_json(project_dir, &project).unwrap();
+        let path = autosave_dir(project_dir)
+            .join(format!("{}20260101T0000{:02}000.json", RECOVERY_PREFIX, index));
+        std::fs::write(path, json).unwrap();
+    }
+
+    #[test]
+    fn prune_keeps_newest_readable_points() {
+        let project = TempDir::new("prune");
+        let dir = &project.0;
+        for index in 0..MAX_RECOVERY_POINTS + 2 {
+            write_point(dir, index);
+        }
+        prune_recovery_points(dir).unwrap();
+
+        let ids: Vec<String> = list_points(dir).unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(ids.len(), MAX_RECOVERY_POINTS);
+        assert_eq!(ids.first().unwrap(), "recovery-20260101T000011000");
+        assert_eq!(ids.last().unwrap(), "recovery-20260101T000002000");
+    }
+
+    #[test]
+    fn unreadable_points_do_not_count_towards_the_limit() {
+        let project = TempDir::new("unreadable");
+        let dir = &project.0;
+        for index in 0..MAX_RECOVERY_POINTS {
+            write_point(dir, index);
+        }
+        let corrupt = autosave_dir(dir).join(format!("{}20260101T000059000.json", RECOVERY_PREFIX));
+        std::fs::write(&corrupt, "{ not json").unwrap();
+        prune_recovery_points(dir).unwrap();
+
+        assert_eq!(list_points(dir).unwrap().len(), MAX_RECOVERY_POINTS);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::media::MediaInfo;
+use crate::render::{conform_filter, fit_filter, num, Canvas, FilterGraph, RenderPlan, AUDIO_SYNC_FILTER};
+use crate::timeline::FitMode;
+use crate::transitions::{AudioCurve, Transition, TransitionSpec};
+
+// Audio format every input is normalized to before crossfading
+const AUDIO_SAMPLE_RATE: u32 = 48000;
+const AUDIO_LAYOUT: &str = "stereo";
+
+// A transition must leave at least this much of each clip untouched
+const MIN_CLIP_REMAINDER: f64 = 0.05;
+
+// Transition length used when the requested default is not positive
+const DEFAULT_TRANSITION_SECS: f64 = 1.0;
+
+/// Resolved transition for one boundary
+#[derive(Clone, Debug)]
+struct Boundary {
+    duration: f64,
+    transition: Transition,
+    audio_curve: AudioCurve,
+}
+
+/// Resolve per-boundary settings, clamping the transitions so that the two
+/// on either side of a clip together leave at least `MIN_CLIP_REMAINDER` of it.
+/// A non-positive `default_duration` falls back to `DEFAULT_TRANSITION_SECS`.
+fn resolve_boundaries(
+    durations: &[f64],
+    default_duration: f64,
+    specs: &[TransitionSpec],
+) -> Vec<Boundary> {
+    let default_duration = if default_duration > 0.0 { default_duration } else { DEFAULT_TRANSITION_SECS };
+    let mut boundaries: Vec<Boundary> = (0..durations.len().saturating_sub(1))
+        .map(|i| {
+            let spec = specs.get(i).cloned().unwrap_or_default();
+            let requested = match spec.transition {
+                Transition::Cut => 0.0,
+                _ => spec.duration.unwrap_or(default_duration),
+            };
+            Boundary {
+                duration: if requested.is_finite() { requested.max(0.0) } else { 0.0 },
+                transition: spec.transition,
+                audio_curve: spec.audio_curve,
+            }
+        })
+        .collect();
+
+    // Shrinking a boundary only loosens the other clip it touches, so one
+    // pass scaling both sides of each over-committed clip is enough
+    for (i, duration) in durations.iter().enumerate() {
+        let available = (duration - MIN_CLIP_REMAINDER).max(0.0);
+        let left = if i > 0 { boundaries[i - 1].duration } else { 0.0 };
+        let right = boundaries.get(i).map_or(0.0, |b| b.duration);
+        if left + right > available {
+            let scale = available / (left + right);
+            if i > 0 {
+                boundaries[i - 1].duration *= scale;
+            }
+            if let Some(boundary) = boundaries.get_mut(i) {
+                boundary.duration *= scale;
+            }
+        }
+    }
+
+    boundaries
+}
+
+/// Build one filter graph that crossfades every adjacent pair of inputs.
+///
+/// Every input is normalized to the canvas first, since xfade requires
+/// matching size, frame rate and pixel format on both sides. Inputs are also
+/// padded or cut to exactly their probed duration so the xfade offsets, which
+/// are computed from those durations, line up with the actual streams.
+pub fn compile_crossfades(
+    inputs: &[String],
+    media: &[MediaInfo],
+    canvas: &Canvas,
+    fit: FitMode,
+    default_duration: f64,
+    specs: &[TransitionSpec],
+) -> Result<RenderPlan, String> {
+    if inputs.is_empty() {
+        return Err("No input files provided".to_string());
+    }
+
+    let durations = media
+        .iter()
+        .map(|info| info.duration_seconds())
+        .collect::<Result<Vec<f64>, String>>()?;
+    let boundaries = resolve_boundaries(&durations, default_duration, specs);
+
+    let mut graph = FilterGraph::default();
+    let mut clips = Vec::new();
+
+    for ((path, info), duration) in inputs.iter().zip(media).zip(&durations) {
+        let input = graph.add_input(Vec::new(), path);
+
+        let video = graph.label("v");
+        if info.has_video() {
+            graph.push(format!(
+                "[{}:v:0]{},setsar=1,{},format=yuv420p,setpts=PTS-STARTPTS,tpad=stop_mode=clone:stop=-1,trim=duration={},settb=AVTB{}",
+                input, fit_filter(canvas, fit), conform_filter(canvas), num(*duration), video
+            ));
+        } else {
+            graph.push(format!(
+                "color=c=black:s={}:r={}:d={},format=yuv420p,settb=AVTB{}",
+                canvas.size(), num(canvas.frame_rate), num(*duration), video
+            ));
+        }
+
+        let audio = graph.label("a");
+        if info.has_audio() {
+            let sync = if info.is_variable_frame_rate() {
+                format!("{},", AUDIO_SYNC_FILTER)
+            } else {
+                String::new()
+            };
+            graph.push(format!(
+                "[{}:a:0]{}aformat=sample_fmts=fltp:sample_rates={}:channel_layouts={},asetpts=PTS-STARTPTS,apad=whole_dur={},atrim=duration={}{}",
+                input, sync, AUDIO_SAMPLE_RATE, AUDIO_LAYOUT, num(*duration), num(*duration), audio
+            ));
+        } else {
+            graph.push(format!(
+                "anullsrc=r={}:cl={},atrim=duration={}{}",
+                AUDIO_SAMPLE_RATE, AUDIO_LAYOUT, num(*duration), audio
+            ));
+        }
+
+        clips.push((video, audio));
+    }
+
+    let mut clips = clips.into_iter();
+    let (mut video_acc, mut audio_acc) = clips.next().unwrap();
+    let mut length = durations[0];
+
+    for ((video, audio), (boundary, duration)) in clips.zip(boundaries.iter().zip(&durations[1..])) {
+        let next_video = graph.label("xv");
+        let next_audio = graph.label("xa");
+
+        match boundary.transition.xfade_name() {
+            Some(xfade) if boundary.duration > 0.0 => {
+                let offset = length - boundary.duration;
+                let curve = boundary.audio_curve.ffmpeg_name();
+                graph.push(format!(
+                    "{}{}xfade=transition={}:duration={}:offset={}{}",
+                    video_acc, video, xfade, num(boundary.duration), num(offset), next_video
+                ));
+                graph.push(format!(
+                    "{}{}acrossfade=d={}:c1={}:c2={}{}",
+                    audio_acc, audio, num(boundary.duration), curve, curve, next_audio
+                ));
+            }
+            _ => {
+                // Hard cut
+                graph.push(format!("{}{}concat=n=2:v=1:a=0{}", video_acc, video, next_video));
+                graph.push(format!("{}{}concat=n=2:v=0:a=1{}", audio_acc, audio, next_audio));
+            }
+        }
+
+        length += duration - boundary.duration;
+        video_acc = next_video;
+        audio_acc = next_audio;
+    }
+
+    Ok(RenderPlan {
+        args: graph.into_args(),
+        video_out: video_acc,
+        audio_out: audio_acc,
+        duration: length,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::FrameRateConversion;
+
+    fn spec(transition: Transition, duration: Option<f64>) -> TransitionSpec {
+        TransitionSpec { transition, duration, audio_curve: AudioCurve::Linear }
+    }
+
+    fn silent_clip(duration: f64) -> MediaInfo {
+        MediaInfo {
+            path: String::new(),
+            container: None,
+            duration: Some(duration),
+            start_time: None,
+            size: None,
+            bit_rate: None,
+            timecode: None,
+            streams: Vec::new(),
+        }
+    }
+
+    fn canvas() -> Canvas {
+        Canvas { width: 1280, height: 720, frame_rate: 30.0, conversion: FrameRateConversion::Duplicate }
+    }
+
+    fn assert_fits(durations: &[f64], boundaries: &[Boundary]) {
+        for (i, duration) in durations.iter().enumerate() {
+            let left = if i > 0 { boundaries[i - 1].duration } else { 0.0 };
+            let right = boundaries.get(i).map_or(0.0, |b| b.duration);
+            assert!(
+                left + right <= duration - MIN_CLIP_REMAINDER + 1e-9,
+                "clip {} ({}s) has {}s + {}s of transitions",
+                i, duration, left, right
+            );
+        }
+    }
+
+    #[test]
+    fn boundaries_keep_requested_durations_that_fit() {
+        let durations = [5.0, 5.0, 5.0];
+        let boundaries = resolve_boundaries(&durations, 1.0, &[spec(Transition::Fade, Some(0.5))]);
+        assert_eq!(boundaries.len(), 2);
+        assert_eq!(boundaries[0].duration, 0.5);
+        assert_eq!(boundaries[1].duration, 1.0);
+        assert_eq!(boundaries[1].transition, Transition::Fade);
+    }
+
+    #[test]
+    fn boundaries_clamp_to_the_shorter_end_clip() {
+        let durations = [0.5, 10.0];
+        let boundaries = resolve_boundaries(&durations, 2.0, &[]);
+        assert!((boundaries[0].duration - 0.45).abs() < 1e-9);
+        assert_fits(&durations, &boundaries);
+    }
+
+    #[test]
+    fn boundaries_share_a_short_middle_clip() {
+        // Each transition fits the middle clip alone, but not both together
+        let durations = [10.0, 1.0, 10.0];
+        let boundaries = resolve_boundaries(&durations, 0.8, &[]);
+        assert!((boundaries[0].duration - 0.475).abs() < 1e-9);
+        assert!((boundaries[1].duration - 0.475).abs() < 1e-9);
+        assert_fits(&durations, &boundaries);
+    }
+
+    #[test]
+    fn boundaries_fit_every_clip_in_a_chain_of_short_clips() {
+        let durations = [0.3, 2.0, 0.2, 1.0, 0.06, 4.0];
+        let boundaries = resolve_boundaries(&durations, 1.5, &[]);
+        assert_fits(&durations, &boundaries);
+        assert!(boundaries.iter().all(|b| b.duration >= 0.0));
+    }
+
+    #[test]
+    fn boundaries_drop_cuts_and_invalid_durations() {
+        let durations = [5.0, 5.0, 5.0, 5.0];
+        let specs = [
+            spec(Transition::Cut, Some(1.0)),
+            spec(Transition::Fade, Some(f64::NAN)),
+            spec(Transition::Fade, Some(-1.0)),
+        ];
+        let boundaries = resolve_boundaries(&durations, 1.0, &specs);
+        assert!(boundaries.iter().all(|b| b.duration == 0.0));
+    }
+
+    #[test]
+    fn boundaries_default_non_positive_durations_to_one_second() {
+        let durations = [5.0, 5.0, 5.0];
+        for default in [0.0, -2.0, f64::NAN] {
+            let boundaries = resolve_boundaries(&durations, default, &[]);
+            assert!(boundaries.iter().all(|b| b.duration == DEFAULT_TRANSITION_SECS));
+        }
+    }
+
+    #[test]
+    fn offsets_follow_the_output_length() {
+        let media = [silent_clip(4.0), silent_clip(3.0), silent_clip(5.0)];
+        let inputs = vec!["a.mp4".to_string(), "b.mp4".to_string(), "c.mp4".to_string()];
+        let specs = [spec(Transition::Fade, Some(1.0)), spec(Transition::WipeLeft, Some(0.5))];
+        let plan = compile_crossfades(&inputs, &media, &canvas(), FitMode::Letterbox, 1.0, &specs).unwrap();
+
+        let graph = plan.args.last().unwrap();
+        // First fade starts 1s before the end of clip A; the second starts
+        // 0.5s before the end of the 4 + 3 - 1 = 6s accumulated output
+        assert!(graph.contains("xfade=transition=fade:duration=1:offset=3["), "{}", graph);
+        assert!(graph.contains("xfade=transition=wipeleft:duration=0.5:offset=5.5["), "{}", graph);
+        assert!(graph.contains("acrossfade=d=1:"), "{}", graph);
+        assert_eq!(plan.duration, 4.0 + 3.0 + 5.0 - 1.0 - 0.5);
+    }
+
+    #[test]
+    fn cuts_concatenate_without_shortening() {
+        let media = [silent_clip(2.0), silent_clip(3.0)];
+        let inputs = vec!["a.mp4".to_string(), "b.mp4".to_string()];
+        let specs = [spec(Transition::Cut, None)];
+        let plan = compile_crossfades(&inputs, &media, &canvas(), FitMode::Letterbox, 1.0, &specs).unwrap();
+
+        let graph = plan.args.last().unwrap();
+        assert!(!graph.contains("xfade"));
+        assert!(graph.contains("concat=n=2:v=1:a=0"));
+        assert_eq!(plan.duration, 5.0);
+    }
+}
@@ -0,0 +1,532 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::fs::write_file_atomic;
+
+// File in the app data directory holding user-defined presets
+const PRESETS_FILE: &str = "encode_presets.json";
+
+// Highest CRF accepted by any of the supported encoders
+const MAX_CRF: u32 = 63;
+
+/// Output container; decides the muxer and whether faststart applies
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Container {
+    Mp4,
+    Mov,
+    Mkv,
+    Webm,
+}
+
+// Encoder families each container can hold. A family also covers hardware
+// variants such as `h264_nvenc` or `aac_at`.
+const MP4_VIDEO: &[&str] = &[
+    "libx264", "libx265", "h264", "hevc", "libaom-av1", "libsvtav1", "librav1e", "av1",
+    "libvpx-vp9", "vp9", "mpeg4", "libxvid",
+];
+const MP4_AUDIO: &[&str] = &["aac", "libfdk_aac", "libmp3lame", "ac3", "eac3", "libopus", "opus", "flac", "alac"];
+const MOV_VIDEO: &[&str] = &[
+    "libx264", "libx265", "h264", "hevc", "mpeg4", "prores", "prores_ks", "prores_aw", "dnxhd", "mjpeg",
+    "png", "qtrle",
+];
+const MOV_AUDIO: &[&str] = &["aac", "libfdk_aac", "libmp3lame", "ac3", "alac", "pcm"];
+const WEBM_VIDEO: &[&str] = &["libvpx", "libvpx-vp9", "vp8", "vp9", "libaom-av1", "libsvtav1", "librav1e", "av1"];
+const WEBM_AUDIO: &[&str] = &["libopus", "opus", "libvorbis", "vorbis"];
+
+impl Container {
+    /// The container a file's extension names, if it is one of these
+    pub fn from_extension(path: &Path) -> Option<Container> {
+        let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+        match extension.as_str() {
+            "mp4" | "m4v" => Some(Container::Mp4),
+            "mov" => Some(Container::Mov),
+            "mkv" => Some(Container::Mkv),
+            "webm" => Some(Container::Webm),
+            _ => None,
+        }
+    }
+
+    fn muxer(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mov => "mov",
+            Container::Mkv => "matroska",
+            Container::Webm => "webm",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "MP4",
+            Container::Mov => "MOV",
+            Container::Mkv => "MKV",
+            Container::Webm => "WebM",
+        }
+    }
+
+    /// Video and audio encoders the container accepts; None if it takes any
+    fn encoders(&self) -> Option<(&'static [&'static str], &'static [&'static str])> {
+        match self {
+            Container::Mp4 => Some((MP4_VIDEO, MP4_AUDIO)),
+            Container::Mov => Some((MOV_VIDEO, MOV_AUDIO)),
+            Container::Mkv => None,
+            Container::Webm => Some((WEBM_VIDEO, WEBM_AUDIO)),
+        }
+    }
+}
+
+/// Whether `encoder` is one of `families` or a variant of one (`h264_nvenc`)
+fn in_family(encoder: &str, families: &[&str]) -> bool {
+    families.iter().any(|family| {
+        encoder
+            .strip_prefix(family)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('_'))
+    })
+}
+
+/// Encoder settings for an export. Unset fields use the FFmpeg defaults,
+/// except the codecs, which default to libx264/aac.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodeSettings {
+    /// Named preset to start from; the other fields override it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_codec: Option<String>,
+    /// Constant quality; ignored when `video_bitrate` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crf: Option<u32>,
+    /// Target bitrate such as "8M" or "2500k"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_bitrate: Option<String>,
+    /// Encoder speed preset (`-preset`), e.g. "veryfast" or "slow"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoder_preset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel_format: Option<String>,
+    /// Keyframe interval in frames
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gop: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_codec: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_bitrate: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_sample_rate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<Container>,
+}
+
+impl EncodeSettings {
+    /// Apply `self` on top of `base`. Rate control is taken as a unit, so an
+    /// override that sets a CRF also drops the base bitrate and vice versa.
+    pub fn merged_over(self, base: EncodeSettings) -> EncodeSettings {
+        let (crf, video_bitrate) = if self.crf.is_some() || self.video_bitrate.is_some() {
+            (self.crf, self.video_bitrate)
+        } else {
+            (base.crf, base.video_bitrate)
+        };
+
+        EncodeSettings {
+            preset: self.preset.or(base.preset),
+            video_codec: self.video_codec.or(base.video_codec),
+            crf,
+            video_bitrate,
+            encoder_preset: self.encoder_preset.or(base.encoder_preset),
+            profile: self.profile.or(base.profile),
+            level: self.level.or(base.level),
+            pixel_format: self.pixel_format.or(base.pixel_format),
+            gop: self.gop.or(base.gop),
+            audio_codec: self.audio_codec.or(base.audio_codec),
+            audio_bitrate: self.audio_bitrate.or(base.audio_bitrate),
+            audio_sample_rate: self.audio_sample_rate.or(base.audio_sample_rate),
+            container: self.container.or(base.container),
+        }
+    }
+
+    /// Reject values that are out of range or could be read as FFmpeg options
+    pub fn validate(&self) -> Result<(), String> {
+        let names = [
+            ("video codec", &self.video_codec),
+            ("encoder preset", &self.encoder_preset),
+            ("profile", &self.profile),
+            ("level", &self.level),
+            ("pixel format", &self.pixel_format),
+            ("audio codec", &self.audio_codec),
+        ];
+        for (what, value) in names {
+            if let Some(value) = value {
+                if !is_plain_token(value) {
+                    return Err(format!("Invalid {}: {}", what, value));
+                }
+            }
+        }
+
+        for (what, value) in [("video bitrate", &self.video_bitrate), ("audio bitrate", &self.audio_bitrate)] {
+            if let Some(value) = value {
+                if !is_bitrate(value) {
+                    return Err(format!("Invalid {}: {}", what, value));
+                }
+            }
+        }
+
+        if let Some(crf) = self.crf {
+            if crf > MAX_CRF {
+                return Err(format!("CRF must be between 0 and {}", MAX_CRF));
+            }
+        }
+        if self.gop == Some(0) {
+            return Err("GOP size must be at least 1".to_string());
+        }
+        if let Some(rate) = self.audio_sample_rate {
+            if !(8000..=192000).contains(&rate) {
+                return Err(format!("Invalid audio sample rate: {}", rate));
+            }
+        }
+
+        if let Some((video, audio)) = self.container.and_then(|c| c.encoders()) {
+            let container = self.container.map(|c| c.name()).unwrap_or_default();
+            let video_codec = self.video_codec();
+            if !in_family(video_codec, video) {
+                return Err(format!("{} files cannot hold {} video", container, video_codec));
+            }
+            let audio_codec = self.audio_codec();
+            if !in_family(audio_codec, audio) {
+                return Err(format!("{} files cannot hold {} audio", container, audio_codec));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// These settings for writing `output`. Without a container, the one the
+    /// file extension names is used; WebM output then gets VP9/Opus in place
+    /// of the default codecs, which it cannot hold.
+    pub fn for_output(&self, output: &Path) -> Result<EncodeSettings, String> {
+        let settings = match (self.container, Container::from_extension(output)) {
+            (None, Some(Container::Webm)) => EncodeSettings {
+                video_codec: self.video_codec.clone().or_else(|| Some("libvpx-vp9".to_string())),
+                audio_codec: self.audio_codec.clone().or_else(|| Some("libopus".to_string())),
+                container: Some(Container::Webm),
+                ..self.clone()
+            },
+            (None, container) => EncodeSettings { container, ..self.clone() },
+            (Some(_), _) => self.clone(),
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Video encoder, defaulting to libx264
+    fn video_codec(&self) -> &str {
+        self.video_codec.as_deref().unwrap_or("libx264")
+    }
+
+    /// Audio encoder, defaulting to aac
+    fn audio_codec(&self) -> &str {
+        self.audio_codec.as_deref().unwrap_or("aac")
+    }
+
+    /// Codec, rate control and muxer arguments to place before the output path
+    pub fn output_args(&self) -> Vec<String> {
+        let mut args = vec!["-c:v".to_string(), self.video_codec().to_string()];
+
+        match (&self.video_bitrate, self.crf) {
+            (Some(bitrate), _) => args.extend(vec!["-b:v".to_string(), bitrate.clone()]),
+            (None, Some(crf)) => args.extend(vec!["-crf".to_string(), crf.to_string()]),
+            (None, None) => {}
+        }
+        if let Some(preset) = &self.encoder_preset {
+            args.extend(vec!["-preset".to_string(), preset.clone()]);
+        }
+        if let Some(profile) = &self.profile {
+            args.extend(vec!["-profile:v".to_string(), profile.clone()]);
+        }
+        if let Some(level) = &self.level {
+            args.extend(vec!["-level:v".to_string(), level.clone()]);
+        }
+        if let Some(pixel_format) = &self.pixel_format {
+            args.extend(vec!["-pix_fmt".to_string(), pixel_format.clone()]);
+        }
+        if let Some(gop) = self.gop {
+            args.extend(vec!["-g".to_string(), gop.to_string()]);
+        }
+
+        args.extend(vec!["-c:a".to_string(), self.audio_codec().to_string()]);
+        if let Some(bitrate) = &self.audio_bitrate {
+            args.extend(vec!["-b:a".to_string(), bitrate.clone()]);
+        }
+        if let Some(rate) = self.audio_sample_rate {
+            args.extend(vec!["-ar".to_string(), rate.to_string()]);
+        }
+
+        match self.container {
+            Some(container) => {
+                args.extend(vec!["-f".to_string(), container.muxer().to_string()]);
+                if matches!(container, Container::Mp4 | Container::Mov) {
+                    args.extend(vec!["-movflags".to_string(), "+faststart".to_string()]);
+                }
+            }
+            None => args.extend(vec!["-movflags".to_string(), "+faststart".to_string()]),
+        }
+
+        args
+    }
+}
+
+fn is_plain_token(value: &str) -> bool {
+    !value.is_empty()
+        && !value.starts_with('-')
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn is_bitrate(value: &str) -> bool {
+    let digits = value.strip_suffix(['k', 'K', 'm', 'M']).unwrap_or(value);
+    !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.parse::<f64>().is_ok_and(|n| n > 0.0)
+}
+
+/// A named set of encoder settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodePreset {
+    pub id: String,
+    pub name: String,
+    pub settings: EncodeSettings,
+    #[serde(default)]
+    pub built_in: bool,
+}
+
+fn built_in(id: &str, name: &str, settings: EncodeSettings) -> EncodePreset {
+    EncodePreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        settings,
+        built_in: true,
+    }
+}
+
+/// Presets shipped with the app
+pub fn builtin_presets() -> Vec<EncodePreset> {
+    vec![
+        built_in("youtube-1080p", "YouTube 1080p", EncodeSettings {
+            video_codec: Some("libx264".to_string()),
+            crf: Some(18),
+            encoder_preset: Some("slow".to_string()),
+            profile: Some("high".to_string()),
+            level: Some("4.2".to_string()),
+            pixel_format: Some("yuv420p".to_string()),
+            gop: Some(60),
+            audio_codec: Some("aac".to_string()),
+            audio_bitrate: Some("384k".to_string()),
+            audio_sample_rate: Some(48000),
+            container: Some(Container::Mp4),
+            ..Default::default()
+        }),
+        built_in("twitter", "Twitter / X", EncodeSettings {
+            video_codec: Some("libx264".to_string()),
+            video_bitrate: Some("5000k".to_string()),
+            encoder_preset: Some("medium".to_string()),
+            profile: Some("high".to_string()),
+            level: Some("4.1".to_string()),
+            pixel_format: Some("yuv420p".to_string()),
+            gop: Some(60),
+            audio_codec: Some("aac".to_string()),
+            audio_bitrate: Some("128k".to_string()),
+            audio_sample_rate: Some(44100),
+            container: Some(Container::Mp4),
+            ..Default::default()
+        }),
+        built_in("archival", "Archival (lossless)", EncodeSettings {
+            video_codec: Some("ffv1".to_string()),
+            gop: Some(1),
+            audio_codec: Some("flac".to_string()),
+            container: Some(Container::Mkv),
+            ..Default::default()
+        }),
+        built_in("fast-draft", "Fast draft", EncodeSettings {
+            video_codec: Some("libx264".to_string()),
+            crf: Some(28),
+            encoder_preset: Some("ultrafast".to_string()),
+            pixel_format: Some("yuv420p".to_string()),
+            audio_codec: Some("aac".to_string()),
+            audio_bitrate: Some("128k".to_string()),
+            container: Some(Container::Mp4),
+            ..Default::default()
+        }),
+    ]
+}
+
+fn presets_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_dir.join(PRESETS_FILE))
+}
+
+/// User presets from the JSON store; a missing file means none
+fn read_user_presets(app: &AppHandle) -> Result<Vec<EncodePreset>, String> {
+    let path = presets_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read encode presets: {}", e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse encode presets: {}", e))
+}
+
+fn write_user_presets(app: &AppHandle, presets: &[EncodePreset]) -> Result<(), String> {
+    let path = presets_path(app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(presets)
+        .map_err(|e| format!("Failed to serialize encode presets: {}", e))?;
+    write_file_atomic(&path, json.as_bytes())
+}
+
+/// Built-in presets followed by the user's own
+fn all_presets(app: &AppHandle) -> Result<Vec<EncodePreset>, String> {
+    let mut presets = builtin_presets();
+    presets.extend(read_user_presets(app)?);
+    Ok(presets)
+}
+
+/// Resolve the settings an export should use: the named preset (if any) with
+/// the explicit fields layered on top, validated
+pub fn resolve_encode_settings(app: &AppHandle, settings: Option<EncodeSettings>) -> Result<EncodeSettings, String> {
+    let settings = settings.unwrap_or_default();
+    let resolved = match &settings.preset {
+        Some(id) => {
+            let preset = all_presets(app)?
+                .into_iter()
+                .find(|p| &p.id == id)
+                .ok_or_else(|| format!("Unknown encode preset: {}", id))?;
+            settings.merged_over(preset.settings)
+        }
+        None => settings,
+    };
+    resolved.validate()?;
+    Ok(resolved)
+}
+
+/// List built-in and user encode presets
+#[tauri::command]
+pub fn list_encode_presets(app: AppHandle) -> Result<Vec<EncodePreset>, String> {
+    all_presets(&app)
+}
+
+/// Create or replace a user encode preset
+#[tauri::command]
+pub fn save_encode_preset(app: AppHandle, preset: EncodePreset) -> Result<EncodePreset, String> {
+    if !is_plain_token(&preset.id) {
+        return Err(format!("Invalid preset id: {}", preset.id));
+    }
+    if builtin_presets().iter().any(|p| p.id == preset.id) {
+        return Err(format!("Cannot overwrite built-in preset {}", preset.id));
+    }
+    if preset.settings.preset.is_some() {
+        return Err("A preset cannot reference another preset".to_string());
+    }
+    preset.settings.validate()?;
+
+    let preset = EncodePreset { built_in: false, ..preset };
+    let mut presets = read_user_presets(&app)?;
+    match presets.iter_mut().find(|p| p.id == preset.id) {
+        Some(existing) => *existing = preset.clone(),
+        None => presets.push(preset.clone()),
+    }
+    write_user_presets(&app, &presets)?;
+    Ok(preset)
+}
+
+/// Delete a user encode preset
+#[tauri::command]
+pub fn delete_encode_preset(app: AppHandle, id: String) -> Result<(), String> {
+    if builtin_presets().iter().any(|p| p.id == id) {
+        return Err(format!("Cannot delete built-in preset {}", id));
+    }
+    let mut presets = read_user_presets(&app)?;
+    let before = presets.len();
+    presets.retain(|p| p.id != id);
+    if presets.len() == before {
+        return Err(format!("No encode preset with id {}", id));
+    }
+    write_user_presets(&app, &presets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(container: Container, video: Option<&str>, audio: Option<&str>) -> EncodeSettings {
+        EncodeSettings {
+            video_codec: video.map(str::to_string),
+            audio_codec: audio.map(str::to_string),
+            container: Some(container),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn builtin_presets_are_valid() {
+        for preset in builtin_presets() {
+            assert!(preset.settings.validate().is_ok(), "{}", preset.id);
+        }
+    }
+
+    #[test]
+    fn rejects_codecs_the_container_cannot_hold() {
+        // The defaults are libx264/aac
+        assert!(settings(Container::Webm, None, None).validate().is_err());
+        assert!(settings(Container::Webm, Some("libvpx-vp9"), Some("aac")).validate().is_err());
+        assert!(settings(Container::Mp4, Some("libx264"), Some("libvorbis")).validate().is_err());
+        assert!(settings(Container::Mp4, Some("prores_ks"), None).validate().is_err());
+
+        let error = settings(Container::Webm, Some("libx264"), Some("libopus")).validate().unwrap_err();
+        assert_eq!(error, "WebM files cannot hold libx264 video");
+    }
+
+    #[test]
+    fn accepts_matching_codecs_and_hardware_variants() {
+        assert!(settings(Container::Webm, Some("libvpx-vp9"), Some("libopus")).validate().is_ok());
+        assert!(settings(Container::Mp4, None, None).validate().is_ok());
+        assert!(settings(Container::Mp4, Some("h264_nvenc"), Some("aac_at")).validate().is_ok());
+        assert!(settings(Container::Mov, Some("prores_ks"), Some("pcm_s24le")).validate().is_ok());
+        // Not a variant, just a shared prefix
+        assert!(settings(Container::Mp4, Some("h264x"), None).validate().is_err());
+    }
+
+    #[test]
+    fn output_extension_picks_the_container_and_webm_codecs() {
+        let default = EncodeSettings::default();
+        let webm = default.for_output(Path::new("/p/exports/out.webm")).unwrap();
+        assert_eq!(webm, settings(Container::Webm, Some("libvpx-vp9"), Some("libopus")));
+        let mov = default.for_output(Path::new("/p/exports/out.MOV")).unwrap();
+        assert_eq!(mov, settings(Container::Mov, None, None));
+        assert_eq!(default.for_output(Path::new("/p/exports/out.m4a")).unwrap(), default);
+
+        // Codecs that were asked for are kept, and still checked
+        let x264 = EncodeSettings { video_codec: Some("libx264".to_string()), ..Default::default() };
+        assert_eq!(x264.for_output(Path::new("/p/out.webm")).unwrap_err(), "WebM files cannot hold libx264 video");
+        // An explicit container wins over the extension
+        let mkv = settings(Container::Mkv, None, None);
+        assert_eq!(mkv.for_output(Path::new("/p/out.webm")).unwrap(), mkv);
+    }
+
+    #[test]
+    fn matroska_and_unset_containers_take_any_codec() {
+        assert!(settings(Container::Mkv, Some("ffv1"), Some("flac")).validate().is_ok());
+        let unset = EncodeSettings { video_codec: Some("libvpx-vp9".to_string()), ..Default::default() };
+        assert!(unset.validate().is_ok());
+    }
+}
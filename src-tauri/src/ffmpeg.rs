@@ -1,19 +1,22 @@
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
-use std::time::Duration;
 use tauri::AppHandle;
 use tauri::Manager;
-use serde_json::Value;
+
+use crate::crossfade::compile_crossfades;
+use crate::encode::{resolve_encode_settings, EncodeSettings};
+use crate::ffmpeg_policy::sanitize_ffmpeg_args;
+use crate::jobs::{start_job, Job};
+use crate::media::{probe_keyframes, probe_media_info, run_ffprobe, MediaInfo};
+use crate::render::{conform_filter, fit_filter, num, Canvas, AUDIO_SYNC_FILTER};
+use crate::sandbox::{CommandError, PathPolicy};
+use crate::timeline::{FitMode, FrameRateConversion, Resolution};
+use crate::transitions::TransitionSpec;
+use crate::trim::{plan_trim, plan_trim_for_file, run_trim, TrimMode};
 
 // Global FFmpeg path storage
 static FFMPEG_PATH: OnceLock<PathBuf> = OnceLock::new();
 
-// FFmpeg timeout constants
-pub const FFMPEG_TIMEOUT_SECS: u64 = 5 * 60; // 5 minutes default timeout
-pub const FFMPEG_TIMEOUT_SHORT: u64 = 30; // 30 seconds for quick operations
-pub const FFMPEG_TIMEOUT_LONG: u64 = 30 * 60; // 30 minutes for large files
-
 /// Initialize FFmpeg path based on platform and bundled resources
 pub fn init_ffmpeg_path(app_handle: &AppHandle) -> Result<(), String> {
     // In development mode, we need to look in the actual resources folder
@@ -93,147 +96,156 @@ pub fn get_ffmpeg_path() -> Result<&'static PathBuf, String> {
     FFMPEG_PATH.get().ok_or_else(|| "FFmpeg path not initialized".to_string())
 }
 
-/// Run FFmpeg with given arguments
-#[tauri::command]
-pub async fn run_ffmpeg(args: Vec<String>) -> Result<i32, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
-    
-    let output = Command::new(ffmpeg_path)
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg failed: {}", stderr));
-    }
-    
-    Ok(output.status.code().unwrap_or(0))
-}
-
-/// Run FFmpeg with given arguments and timeout
+/// Run FFmpeg with given arguments. The arguments must pass the allowlist in
+/// `ffmpeg_policy`; every invocation is logged, rejected or not.
 #[tauri::command]
-pub async fn run_ffmpeg_with_timeout(
+pub async fn run_ffmpeg(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
     args: Vec<String>,
-    timeout_secs: Option<u64>,
-) -> Result<i32, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
-    let timeout_duration = timeout_secs.unwrap_or(FFMPEG_TIMEOUT_SECS);
-    
-    let mut child = Command::new(ffmpeg_path)
-        .args(&args)
-        .spawn()
-        .map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
-    
-    // Simple timeout handling - wait for child process with timeout
-    let timeout = Duration::from_secs(timeout_duration);
-    let start = std::time::Instant::now();
-    
-    loop {
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                return if status.success() {
-                    Ok(status.code().unwrap_or(0))
-                } else {
-                    Err(format!("FFmpeg failed with code: {:?}", status.code()))
-                };
-            }
-            Ok(None) => {
-                if start.elapsed() > timeout {
-                    // Kill the process on timeout
-                    let _ = child.kill();
-                    return Err(format!(
-                        "FFmpeg operation timed out after {} seconds",
-                        timeout_duration
-                    ));
-                }
-                // Sleep briefly before checking again
-                std::thread::sleep(Duration::from_millis(100));
-            }
-            Err(e) => return Err(format!("Failed to wait for FFmpeg: {}", e)),
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    let args = match sanitize_ffmpeg_args(&policy, &args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("run_ffmpeg rejected ({}): ffmpeg {}", e, args.join(" "));
+            return Err(e);
         }
-    }
+    };
+
+    let job = start_job(&app, "ffmpeg", job_id)?;
+    eprintln!("run_ffmpeg job {}: ffmpeg {}", job.id(), args.join(" "));
+    let result = job.run_ffmpeg("command", &args, None, None).await;
+    job.finish(result).map_err(CommandError::from)
 }
 
 /// Helper: probe media duration (in seconds) using FFprobe
-fn probe_duration_seconds(path: &str) -> Result<f64, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
-    let ffprobe_path = ffmpeg_path.with_file_name(
-        if cfg!(target_os = "windows") { "ffprobe.exe" } else { "ffprobe" }
-    );
-
-    let output = Command::new(&ffprobe_path)
-        .args(&[
-            "-v", "quiet",
-            "-print_format", "json",
-            "-show_format",
-            path,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute FFprobe: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFprobe failed: {}", stderr));
-    }
+async fn probe_duration_seconds(path: &str) -> Result<f64, String> {
+    probe_inputs(&[path])
+        .await?
+        .remove(0)
+        .duration_seconds()
+}
+
+/// Probe video file for metadata using FFprobe (raw JSON, see `probe_media` for the typed model)
+#[tauri::command]
+pub async fn ffprobe_json(path: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || run_ffprobe(&path))
+        .await
+        .map_err(|e| format!("Failed to probe media: {}", e))?
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let v: Value = serde_json::from_str(&stdout)
-        .map_err(|e| format!("Failed to parse FFprobe JSON: {}", e))?;
+/// Helper: total duration of a list of inputs, if every input can be probed
+async fn sum_durations<S: AsRef<str>>(inputs: &[S]) -> Option<f64> {
+    let infos = probe_inputs(inputs).await.ok()?;
+    infos.iter().map(MediaInfo::duration_seconds).sum::<Result<f64, String>>().ok()
+}
 
-    // Try format.duration first
-    if let Some(dur_str) = v.get("format").and_then(|f| f.get("duration")).and_then(|d| d.as_str()) {
-        if let Ok(d) = dur_str.parse::<f64>() {
-            return Ok(d);
+/// Helper: probe every input off the async runtime, failing on the first
+/// file that cannot be read
+async fn probe_inputs<S: AsRef<str>>(inputs: &[S]) -> Result<Vec<MediaInfo>, String> {
+    let paths: Vec<String> = inputs.iter().map(|input| input.as_ref().to_string()).collect();
+    tauri::async_runtime::spawn_blocking(move || {
+        paths.iter().map(|path| probe_media_info(path)).collect()
+    })
+    .await
+    .map_err(|e| format!("Failed to probe inputs: {}", e))?
+}
+
+/// Canvas requested by an export command: size, fit mode and frame rate
+#[derive(Clone, Copy, Debug, Default)]
+struct OutputTarget {
+    resolution: Option<Resolution>,
+    fit: FitMode,
+    frame_rate: Option<f64>,
+    conversion: Option<FrameRateConversion>,
+}
+
+impl OutputTarget {
+    fn new(
+        resolution: Option<Resolution>,
+        fit_mode: Option<FitMode>,
+        frame_rate: Option<f64>,
+        conversion: Option<FrameRateConversion>,
+    ) -> Self {
+        OutputTarget {
+            resolution,
+            fit: fit_mode.unwrap_or_default(),
+            frame_rate,
+            conversion,
         }
     }
-    // Try numeric
-    if let Some(d) = v.get("format").and_then(|f| f.get("duration")).and_then(|d| d.as_f64()) {
-        return Ok(d);
-    }
 
-    Err("Could not determine media duration".to_string())
+    /// Canvas for an export whose first input is `first`
+    fn canvas(&self, first: &MediaInfo) -> Canvas {
+        Canvas::for_source(first, self.resolution).with_frame_rate(self.frame_rate, self.conversion)
+    }
 }
 
-/// Probe video file for metadata using FFprobe
-#[tauri::command]
-pub async fn ffprobe_json(path: String) -> Result<String, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
-    let ffprobe_path = ffmpeg_path.with_file_name(
-        if cfg!(target_os = "windows") { "ffprobe.exe" } else { "ffprobe" }
-    );
-    
-    let output = Command::new(&ffprobe_path)
-        .args(&[
-            "-v", "quiet",
-            "-print_format", "json",
-            "-show_format",
-            "-show_streams",
-            &path
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute FFprobe: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFprobe failed: {}", stderr));
+/// Helper: read input file paths from a concat demuxer list
+fn read_concat_list_inputs(list_path: &str) -> Result<Vec<String>, String> {
+    // Use PathBuf for cross-platform compatibility
+    let list_path_buf = PathBuf::from(list_path);
+    let list_content = std::fs::read_to_string(&list_path_buf)
+        .map_err(|e| format!("Failed to read concat list: {}", e))?;
+    
+    let input_files: Vec<String> = list_content
+        .lines()
+        .filter_map(|line| {
+            if line.starts_with("file '") && line.ends_with("'") {
+                Some(line[6..line.len()-1].to_string()) // Remove "file '" and "'"
+            } else {
+                None
+            }
+        })
+        .collect();
+    
+    if input_files.is_empty() {
+        return Err("No valid input files found in concat list".to_string());
     }
     
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.to_string())
+    Ok(input_files)
 }
 
 /// Apply fade effects to a video clip
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn apply_fade_effects(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
     input: String,
     output: String,
     fade_in_duration: Option<f64>,
     fade_out_duration: Option<f64>,
     total_duration: f64,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&output))?;
+    let encode = resolve_encode_settings(&app, encode)?;
+    let job = start_job(&app, "fade", job_id)?;
+    let result = apply_fade_effects_internal(
+        &job,
+        input,
+        output,
+        fade_in_duration,
+        fade_out_duration,
+        total_duration,
+        &encode,
+    ).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+/// Internal implementation of fade effects, run as part of a job
+async fn apply_fade_effects_internal(
+    job: &Job,
+    input: String,
+    output: String,
+    fade_in_duration: Option<f64>,
+    fade_out_duration: Option<f64>,
+    total_duration: f64,
+    encode: &EncodeSettings,
 ) -> Result<i32, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
-    
     let mut video_filters = Vec::new();
     let mut audio_filters = Vec::new();
     
@@ -271,263 +283,396 @@ pub async fn apply_fade_effects(
         args.push(audio_filters.join(","));
     }
     
+    args.extend(encode.output_args());
     args.extend(vec![
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
         "-y".to_string(),
-        output,
+        output.clone(),
     ]);
     
-    let output = Command::new(ffmpeg_path)
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg fade: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg fade failed: {}", stderr));
-    }
-    
-    Ok(output.status.code().unwrap_or(0))
+    job.run_ffmpeg("fade", &args, Some(&output), Some(total_duration)).await
 }
 
 /// Trim a video clip destructively (creates new file)
 #[tauri::command]
-pub async fn trim_clip(input: String, start: f64, end: f64, output: String) -> Result<i32, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
+#[allow(clippy::too_many_arguments)]
+pub async fn trim_clip(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    input: String,
+    start: f64,
+    end: f64,
+    output: String,
+    mode: Option<TrimMode>,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&output))?;
     let duration = end - start;
     
     if duration <= 0.0 {
-        return Err("Invalid trim duration".to_string());
-    }
-    
-    let args = vec![
-        "-ss".to_string(),
-        start.to_string(),
-        "-i".to_string(),
-        input,
-        "-t".to_string(),
-        duration.to_string(),
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
-        "-y".to_string(),
-        output,
-    ];
-    
-    let output = Command::new(ffmpeg_path)
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg trim: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg trim failed: {}", stderr));
+        return Err("Invalid trim duration".to_string().into());
     }
+    let encode = resolve_encode_settings(&app, encode)?;
     
-    Ok(output.status.code().unwrap_or(0))
+    let job = start_job(&app, "trim", job_id)?;
+    let result = trim_clip_internal(&job, &input, start, end, &output, mode.unwrap_or_default(), &encode).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+/// Internal implementation of trim, run as part of a job
+async fn trim_clip_internal(
+    job: &Job,
+    input: &str,
+    start: f64,
+    end: f64,
+    output: &str,
+    mode: TrimMode,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    let (plan, info) = {
+        let path = input.to_string();
+        tauri::async_runtime::spawn_blocking(move || plan_trim_for_file(&path, start, end, mode))
+            .await
+            .map_err(|e| format!("Failed to plan trim: {}", e))??
+    };
+    run_trim(job, input, output, &plan, info.as_ref(), encode).await
 }
 
 /// Split a video clip at a specific time point (creates two new files)
 #[tauri::command]
-pub async fn split_clip(input: String, split_time: f64, left_output: String, right_output: String, total_duration: f64) -> Result<i32, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
-    
+#[allow(clippy::too_many_arguments)]
+pub async fn split_clip(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    input: String,
+    split_time: f64,
+    left_output: String,
+    right_output: String,
+    total_duration: f64,
+    mode: Option<TrimMode>,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&left_output))?;
+    policy.check_write(Path::new(&right_output))?;
     if split_time <= 0.0 || split_time >= total_duration {
-        return Err("Invalid split time".to_string());
+        return Err("Invalid split time".to_string().into());
     }
     
     if split_time < 0.2 || (total_duration - split_time) < 0.2 {
-        return Err("Split would create segments too short (< 0.2s)".to_string());
+        return Err("Split would create segments too short (< 0.2s)".to_string().into());
     }
+    let encode = resolve_encode_settings(&app, encode)?;
     
-    // Create left part (from start to split_time)
-    let left_args = vec![
-        "-ss".to_string(),
-        "0".to_string(),
-        "-i".to_string(),
-        input.clone(),
-        "-t".to_string(),
-        split_time.to_string(),
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
-        "-y".to_string(),
-        left_output.clone(),
-    ];
+    let job = start_job(&app, "split", job_id)?;
+    let mode = mode.unwrap_or_default();
+    let result = async {
+        // Create left part (from start to split_time)
+        trim_clip_internal(&job, &input, 0.0, split_time, &left_output, mode, &encode)
+            .await
+            .map_err(|e| format!("Split (left part): {}", e))?;
+        
+        // Create right part (from split_time to end)
+        trim_clip_internal(&job, &input, split_time, total_duration, &right_output, mode, &encode)
+            .await
+            .map_err(|e| format!("Split (right part): {}", e))?;
+        
+        Ok(0)
+    }.await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+// Shortest segment a split may produce
+const MIN_SPLIT_SEGMENT_SECS: f64 = 0.2;
+
+/// Helper: turn split points into (start, end) segments covering the whole clip
+fn split_segments(split_times: &[f64], total_duration: f64) -> Result<Vec<(f64, f64)>, String> {
+    if split_times.is_empty() {
+        return Err("No split times provided".to_string());
+    }
     
-    let left_output_cmd = Command::new(ffmpeg_path)
-        .args(&left_args)
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg split (left part): {}", e))?;
+    let mut times = split_times.to_vec();
+    times.sort_by(f64::total_cmp);
     
-    if !left_output_cmd.status.success() {
-        let stderr = String::from_utf8_lossy(&left_output_cmd.stderr);
-        return Err(format!("FFmpeg split (left part) failed: {}", stderr));
+    let mut segments = Vec::with_capacity(times.len() + 1);
+    let mut start = 0.0;
+    for &time in &times {
+        if !time.is_finite() || time <= 0.0 || time >= total_duration {
+            return Err(format!("Invalid split time {}", time));
+        }
+        segments.push((start, time));
+        start = time;
     }
+    segments.push((start, total_duration));
     
-    // Create right part (from split_time to end)
-    let right_duration = total_duration - split_time;
-    let right_args = vec![
-        "-ss".to_string(),
-        split_time.to_string(),
-        "-i".to_string(),
-        input,
-        "-t".to_string(),
-        right_duration.to_string(),
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
-        "-y".to_string(),
-        right_output.clone(),
-    ];
+    if segments.iter().any(|(start, end)| end - start < MIN_SPLIT_SEGMENT_SECS) {
+        return Err("Split would create segments too short (< 0.2s)".to_string());
+    }
     
-    let right_output_cmd = Command::new(ffmpeg_path)
-        .args(&right_args)
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg split (right part): {}", e))?;
+    Ok(segments)
+}
+
+/// Split a video clip at any number of time points in a single FFmpeg pass.
+///
+/// `outputs` must hold one path per resulting segment (split count + 1), in
+/// time order. Re-encode mode decodes once and fans out with split/trim filters;
+/// copy mode first moves each split point back to the keyframe at or before
+/// it, so every part starts on a keyframe, then stream-copies the ranges.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn split_clip_multi(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    input: String,
+    split_times: Vec<f64>,
+    outputs: Vec<String>,
+    total_duration: Option<f64>,
+    mode: Option<TrimMode>,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    for output in &outputs {
+        policy.check_write(Path::new(output))?;
+    }
+    let mode = mode.unwrap_or_default();
+    let encode = resolve_encode_settings(&app, encode)?;
+    let (info, keyframes) = {
+        let path = input.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let info = probe_media_info(&path)?;
+            let keyframes = if mode == TrimMode::Copy { probe_keyframes(&path)? } else { Vec::new() };
+            Ok::<_, String>((info, keyframes))
+        })
+        .await
+        .map_err(|e| format!("Failed to probe {}: {}", input, e))??
+    };
+    let total_duration = match total_duration {
+        Some(duration) => duration,
+        None => info.duration_seconds()?,
+    };
     
-    if !right_output_cmd.status.success() {
-        let stderr = String::from_utf8_lossy(&right_output_cmd.stderr);
-        return Err(format!("FFmpeg split (right part) failed: {}", stderr));
+    let segments = if mode == TrimMode::Copy {
+        // A copied part can only start on a keyframe
+        let snapped: Vec<f64> = split_times
+            .iter()
+            .map(|&time| plan_trim(time, total_duration, mode, &keyframes, false).start)
+            .collect();
+        split_segments(&snapped, total_duration).map_err(|e| {
+            format!("{} after moving split points to keyframes; use re-encode mode to split between keyframes", e)
+        })?
+    } else {
+        split_segments(&split_times, total_duration)?
+    };
+    if outputs.len() != segments.len() {
+        return Err(format!(
+            "Expected {} output paths for {} split points, got {}",
+            segments.len(), split_times.len(), outputs.len()
+        ).into());
+    }
+    
+    let mut args = vec!["-i".to_string(), input];
+    match mode {
+        TrimMode::Reencode => {
+            let n = segments.len();
+            let mut filters = Vec::new();
+            
+            let video_pads: String = (0..n).map(|i| format!("[sv{}]", i)).collect();
+            filters.push(format!("[0:v:0]split={}{}", n, video_pads));
+            if info.has_audio() {
+                let audio_pads: String = (0..n).map(|i| format!("[sa{}]", i)).collect();
+                filters.push(format!("[0:a:0]asplit={}{}", n, audio_pads));
+            }
+            
+            for (i, (start, end)) in segments.iter().enumerate() {
+                filters.push(format!(
+                    "[sv{i}]trim=start={}:end={},setpts=PTS-STARTPTS[v{i}]",
+                    num(*start), num(*end)
+                ));
+                if info.has_audio() {
+                    filters.push(format!(
+                        "[sa{i}]atrim=start={}:end={},asetpts=PTS-STARTPTS[a{i}]",
+                        num(*start), num(*end)
+                    ));
+                }
+            }
+            
+            args.extend(vec!["-filter_complex".to_string(), filters.join(";")]);
+            for (i, output) in outputs.iter().enumerate() {
+                args.extend(vec!["-map".to_string(), format!("[v{}]", i)]);
+                if info.has_audio() {
+                    args.extend(vec!["-map".to_string(), format!("[a{}]", i)]);
+                }
+                args.extend(encode.output_args());
+                args.extend(vec!["-y".to_string(), output.clone()]);
+            }
+        }
+        TrimMode::Copy => {
+            for ((start, end), output) in segments.iter().zip(&outputs) {
+                args.extend(vec![
+                    "-map".to_string(), "0".to_string(),
+                    "-ss".to_string(), num(*start),
+                    "-to".to_string(), num(*end),
+                    "-c".to_string(), "copy".to_string(),
+                    "-avoid_negative_ts".to_string(), "make_zero".to_string(),
+                    "-movflags".to_string(), "+faststart".to_string(),
+                    "-y".to_string(), output.clone(),
+                ]);
+            }
+        }
+        TrimMode::SmartCut => {
+            return Err("Smart cut is not supported for multi-point splits".to_string().into());
+        }
     }
     
-    Ok(0)
+    let job = start_job(&app, "split", job_id)?;
+    for output in &outputs {
+        job.track_output(output);
+    }
+    let result = job.run_ffmpeg("split", &args, None, Some(total_duration)).await;
+    job.finish(result).map_err(CommandError::from)
 }
 
-/// Transcode video to MP4 format
+/// Transcode video to MP4 format.
+///
+/// Variable frame rate sources (and any source when `frame_rate` is given) are
+/// conformed to a constant rate, with audio resampled to stay in sync.
 #[tauri::command]
-pub async fn transcode_to_mp4(input: String, output: String) -> Result<i32, String> {
-    let _ffmpeg_path = get_ffmpeg_path()?;
+#[allow(clippy::too_many_arguments)]
+pub async fn transcode_to_mp4(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    input: String,
+    output: String,
+    frame_rate: Option<f64>,
+    frame_rate_conversion: Option<FrameRateConversion>,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&output))?;
+    let encode = resolve_encode_settings(&app, encode)?;
+    let info = probe_inputs(&[&input]).await.ok().map(|mut infos| infos.remove(0));
+    let duration = info.as_ref().and_then(|info| info.duration);
     
-    let args = vec![
+    let mut args = vec![
         "-i".to_string(),
         input,
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
-        "-y".to_string(),
-        output,
     ];
-    
-    let output = Command::new(_ffmpeg_path)
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg transcode: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg transcode failed: {}", stderr));
+    if let Some(info) = info.as_ref().filter(|info| info.has_video()) {
+        let vfr = info.is_variable_frame_rate();
+        if vfr || frame_rate.is_some() {
+            let canvas = Canvas::for_source(info, None).with_frame_rate(frame_rate, frame_rate_conversion);
+            args.extend(vec!["-vf".to_string(), conform_filter(&canvas)]);
+        }
+        if vfr && info.has_audio() {
+            args.extend(vec!["-af".to_string(), AUDIO_SYNC_FILTER.to_string()]);
+        }
     }
+    args.extend(encode.output_args());
+    args.extend(vec![
+        "-y".to_string(),
+        output.clone(),
+    ]);
     
-    Ok(output.status.code().unwrap_or(0))
+    let job = start_job(&app, "transcode", job_id)?;
+    let result = job.run_ffmpeg("transcode", &args, Some(&output), duration).await;
+    job.finish(result).map_err(CommandError::from)
 }
 
 /// Export concatenated video with fade effects support
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn export_concat_with_fades(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
     list_path: String,
     output: String,
-    resolution: Option<String>,
+    resolution: Option<Resolution>,
+    fit_mode: Option<FitMode>,
+    frame_rate: Option<f64>,
+    frame_rate_conversion: Option<FrameRateConversion>,
     fade_effects: Option<Vec<(f64, f64)>>, // Vec of (fade_in_duration, fade_out_duration) for each clip
-) -> Result<i32, String> {
-    let _ffmpeg_path = get_ffmpeg_path()?;
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&output))?;
+    let encode = resolve_encode_settings(&app, encode)?;
+    let target = OutputTarget::new(resolution, fit_mode, frame_rate, frame_rate_conversion);
+    let job = start_job(&app, "export", job_id)?;
     
     // If no fade effects, use regular concat
-    if fade_effects.is_none() {
-        return export_concat(list_path, output, resolution).await;
-    }
-    
-    // Apply fade effects to individual clips first, then concatenate
-    let fade_data = fade_effects.unwrap();
-    
+    let result = match fade_effects {
+        None => export_concat_internal(&job, &list_path, &output, target, &encode).await,
+        Some(fade_data) => {
+            export_concat_with_fades_internal(&job, &list_path, &output, target, fade_data, &encode).await
+        }
+    };
+    job.finish(result).map_err(CommandError::from)
+}
+
+/// Internal implementation of fade export: fade each clip, then concatenate
+async fn export_concat_with_fades_internal(
+    job: &Job,
+    list_path: &str,
+    output: &str,
+    target: OutputTarget,
+    fade_data: Vec<(f64, f64)>,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
     // Read the file list to get input files
-    let list_path_buf = PathBuf::from(&list_path);
-    let list_content = std::fs::read_to_string(&list_path_buf)
-        .map_err(|e| format!("Failed to read concat list: {}", e))?;
-    
-    let input_files: Vec<&str> = list_content
-        .lines()
-        .filter_map(|line| {
-            if line.starts_with("file '") && line.ends_with("'") {
-                Some(&line[6..line.len()-1]) // Remove "file '" and "'"
-            } else {
-                None
-            }
-        })
-        .collect();
-    
-    if input_files.is_empty() {
-        return Err("No valid input files found in concat list".to_string());
-    }
+    let input_files = read_concat_list_inputs(list_path)?;
     
-    // Create temporary directory for processed clips
-    let temp_dir = std::env::temp_dir().join("trimbot_fade_export");
+    // Create temporary directory for processed clips, unique per job
+    let temp_dir = std::env::temp_dir().join(format!("trimbot_fade_export_{}", job.id()));
     std::fs::create_dir_all(&temp_dir)
         .map_err(|e| format!("Failed to create temp directory: {}", e))?;
     
-    let mut processed_files = Vec::new();
-    
-    // Process each clip with fade effects
-    for (i, input_file) in input_files.iter().enumerate() {
-        let temp_output = temp_dir.join(format!("clip_{}.mp4", i));
-        
-        // Get fade settings for this clip
-        let (fade_in, fade_out) = fade_data.get(i).copied().unwrap_or((0.0, 0.0));
+    let result = async {
+        let mut processed_files = Vec::new();
         
-        // Get clip duration
-        let duration = probe_duration_seconds(input_file)?;
+        // Process each clip with fade effects
+        for (i, input_file) in input_files.iter().enumerate() {
+            let temp_output = temp_dir.join(format!("clip_{}.mp4", i));
+            
+            // Get fade settings for this clip
+            let (fade_in, fade_out) = fade_data.get(i).copied().unwrap_or((0.0, 0.0));
+            
+            if fade_in > 0.0 || fade_out > 0.0 {
+                // Get clip duration
+                let duration = probe_duration_seconds(input_file).await?;
+                
+                // Apply fade effects; the final concat re-encodes with `encode`
+                apply_fade_effects_internal(
+                    job,
+                    input_file.to_string(),
+                    temp_output.to_string_lossy().to_string(),
+                    if fade_in > 0.0 { Some(fade_in) } else { None },
+                    if fade_out > 0.0 { Some(fade_out) } else { None },
+                    duration,
+                    &EncodeSettings::default(),
+                ).await?;
+            } else {
+                // No fade effects, just copy the file
+                std::fs::copy(input_file, &temp_output)
+                    .map_err(|e| format!("Failed to copy file: {}", e))?;
+            }
+            
+            processed_files.push(temp_output);
+        }
         
-        if fade_in > 0.0 || fade_out > 0.0 {
-            // Apply fade effects
-            apply_fade_effects(
-                input_file.to_string(),
-                temp_output.to_string_lossy().to_string(),
-                if fade_in > 0.0 { Some(fade_in) } else { None },
-                if fade_out > 0.0 { Some(fade_out) } else { None },
-                duration,
-            ).await?;
-        } else {
-            // No fade effects, just copy the file
-            std::fs::copy(input_file, &temp_output)
-                .map_err(|e| format!("Failed to copy file: {}", e))?;
+        // Create new concat list with processed files
+        let temp_list = temp_dir.join("processed_list.txt");
+        let mut list_content = String::new();
+        for file in &processed_files {
+            list_content.push_str(&format!("file '{}'\n", file.to_string_lossy()));
         }
         
-        processed_files.push(temp_output);
-    }
-    
-    // Create new concat list with processed files
-    let temp_list = temp_dir.join("processed_list.txt");
-    let mut list_content = String::new();
-    for file in &processed_files {
-        list_content.push_str(&format!("file '{}'\n", file.to_string_lossy()));
-    }
-    
-    std::fs::write(&temp_list, list_content)
-        .map_err(|e| format!("Failed to write temp concat list: {}", e))?;
-    
-    // Export concatenated video
-    let result = export_concat_demuxer(
-        &temp_list.to_string_lossy(),
-        &output,
-        resolution.as_deref()
-    ).await;
+        std::fs::write(&temp_list, list_content)
+            .map_err(|e| format!("Failed to write temp concat list: {}", e))?;
+        
+        // Export concatenated video
+        let total_duration = sum_durations(&input_files).await;
+        export_concat_demuxer(job, &temp_list.to_string_lossy(), output, target, total_duration, encode).await
+    }.await;
     
     // Clean up temporary files
     let _ = std::fs::remove_dir_all(&temp_dir);
@@ -535,26 +680,68 @@ pub async fn export_concat_with_fades(
     result
 }
 
-/// Export concatenated video using concat demuxer with resolution presets
+/// Export concatenated video using concat demuxer.
+///
+/// Every input is fitted onto `resolution` (or the first input's size) and
+/// conformed to `frame_rate` (or the first input's rate, 30 fps if variable).
 #[tauri::command]
-pub async fn export_concat(list_path: String, output: String, resolution: Option<String>) -> Result<i32, String> {
-    let _ffmpeg_path = get_ffmpeg_path()?;
+#[allow(clippy::too_many_arguments)]
+pub async fn export_concat(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    list_path: String,
+    output: String,
+    resolution: Option<Resolution>,
+    fit_mode: Option<FitMode>,
+    frame_rate: Option<f64>,
+    frame_rate_conversion: Option<FrameRateConversion>,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&output))?;
+    let encode = resolve_encode_settings(&app, encode)?;
+    let target = OutputTarget::new(resolution, fit_mode, frame_rate, frame_rate_conversion);
+    let job = start_job(&app, "export", job_id)?;
+    let result = export_concat_internal(&job, &list_path, &output, target, &encode).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+/// Internal implementation of concat export with filter-concat fallback
+async fn export_concat_internal(
+    job: &Job,
+    list_path: &str,
+    output: &str,
+    target: OutputTarget,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    let total_duration = match read_concat_list_inputs(list_path) {
+        Ok(inputs) => sum_durations(&inputs).await,
+        Err(_) => None,
+    };
     
     // Try concat demuxer first
-    let result = export_concat_demuxer(&list_path, &output, resolution.as_deref()).await;
+    let result = export_concat_demuxer(job, list_path, output, target, total_duration, encode).await;
     
     // If concat demuxer fails, try filter-concat as fallback
-    if result.is_err() {
+    if result.is_err() && !job.is_cancelled() {
         println!("Concat demuxer failed, trying filter-concat fallback");
-        return export_concat_filter_internal(&list_path, &output, resolution.as_deref()).await;
+        return export_concat_filter_internal(job, list_path, output, target, encode).await;
     }
     
     result
 }
 
 /// Export using concat demuxer (preferred method)
-async fn export_concat_demuxer(list_path: &str, output: &str, resolution: Option<&str>) -> Result<i32, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
+async fn export_concat_demuxer(
+    job: &Job,
+    list_path: &str,
+    output: &str,
+    target: OutputTarget,
+    total_duration: Option<f64>,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    let infos = probe_inputs(&read_concat_list_inputs(list_path)?).await?;
+    let canvas = target.canvas(&infos[0]);
     
     let mut args = vec![
         "-f".to_string(),
@@ -565,78 +752,60 @@ async fn export_concat_demuxer(list_path: &str, output: &str, resolution: Option
         list_path.to_string(),
     ];
     
-    // Add resolution scaling if specified
-    match resolution {
-        Some("720p") => {
-            args.extend(vec![
-                "-vf".to_string(),
-                "scale=-2:720".to_string(),
-            ]);
-        },
-        Some("1080p") => {
-            args.extend(vec![
-                "-vf".to_string(),
-                "scale=-2:1080".to_string(),
-            ]);
-        },
-        _ => {
-            // Source resolution - no scaling
-        }
+    // Fit and conform every input onto the canvas so size and frame rate
+    // changes between files are absorbed
+    args.extend(vec![
+        "-vf".to_string(),
+        format!("{},setsar=1,{}", fit_filter(&canvas, target.fit), conform_filter(&canvas)),
+    ]);
+    if infos.iter().any(MediaInfo::is_variable_frame_rate) {
+        args.extend(vec!["-af".to_string(), AUDIO_SYNC_FILTER.to_string()]);
     }
     
+    args.extend(encode.output_args());
     args.extend(vec![
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
         "-y".to_string(),
         output.to_string(),
     ]);
     
-    let output = Command::new(ffmpeg_path)
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg concat demuxer: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg concat demuxer failed: {}", stderr));
-    }
-    
-    Ok(output.status.code().unwrap_or(0))
+    job.run_ffmpeg("concat demuxer", &args, Some(output), total_duration).await
 }
 
 /// Export using filter-concat (fallback method)
 #[tauri::command]
-pub async fn export_concat_filter(list_path: String, output: String, resolution: Option<String>) -> Result<i32, String> {
-    export_concat_filter_internal(&list_path, &output, resolution.as_deref()).await
+#[allow(clippy::too_many_arguments)]
+pub async fn export_concat_filter(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    list_path: String,
+    output: String,
+    resolution: Option<Resolution>,
+    fit_mode: Option<FitMode>,
+    frame_rate: Option<f64>,
+    frame_rate_conversion: Option<FrameRateConversion>,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&output))?;
+    let encode = resolve_encode_settings(&app, encode)?;
+    let target = OutputTarget::new(resolution, fit_mode, frame_rate, frame_rate_conversion);
+    let job = start_job(&app, "export", job_id)?;
+    let result = export_concat_filter_internal(&job, &list_path, &output, target, &encode).await;
+    job.finish(result).map_err(CommandError::from)
 }
 
 /// Internal implementation of filter-concat export
-async fn export_concat_filter_internal(list_path: &str, output: &str, resolution: Option<&str>) -> Result<i32, String> {
-    let ffmpeg_path = get_ffmpeg_path()?;
-    
-    // Read the file list to get input files - use PathBuf for cross-platform compatibility
-    let list_path_buf = PathBuf::from(list_path);
-    let list_content = std::fs::read_to_string(&list_path_buf)
-        .map_err(|e| format!("Failed to read concat list: {}", e))?;
-    
-    let input_files: Vec<&str> = list_content
-        .lines()
-        .filter_map(|line| {
-            if line.starts_with("file '") && line.ends_with("'") {
-                Some(&line[6..line.len()-1]) // Remove "file '" and "'"
-            } else {
-                None
-            }
-        })
-        .collect();
-    
-    if input_files.is_empty() {
-        return Err("No valid input files found in concat list".to_string());
-    }
+async fn export_concat_filter_internal(
+    job: &Job,
+    list_path: &str,
+    output: &str,
+    target: OutputTarget,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    // Read the file list to get input files
+    let input_files = read_concat_list_inputs(list_path)?;
+    let infos = probe_inputs(&input_files).await?;
+    let canvas = target.canvas(&infos[0]);
     
     let mut args = vec![];
     
@@ -645,199 +814,131 @@ async fn export_concat_filter_internal(list_path: &str, output: &str, resolution
         args.extend(vec!["-i".to_string(), file.to_string()]);
     }
     
-    // Build filter complex for concatenation
+    // Normalize every input to the canvas and one audio format, since concat
+    // needs matching streams; clips without audio get a silent track
+    let mut filters = Vec::new();
     let mut filter_inputs = Vec::new();
-    for i in 0..input_files.len() {
-        filter_inputs.push(format!("[{}:v][{}:a]", i, i));
-    }
-    
-    let filter_complex = format!(
+    let mut silence_index = input_files.len();
+    for (i, info) in infos.iter().enumerate() {
+        let audio_input = if info.has_audio() {
+            i
+        } else {
+            args.extend(vec![
+                "-f".to_string(), "lavfi".to_string(),
+                "-t".to_string(), info.duration_seconds()?.to_string(),
+                "-i".to_string(), "anullsrc=r=48000:cl=stereo".to_string(),
+            ]);
+            silence_index += 1;
+            silence_index - 1
+        };
+        
+        let sync = if info.is_variable_frame_rate() {
+            format!("{},", AUDIO_SYNC_FILTER)
+        } else {
+            String::new()
+        };
+        filters.push(format!(
+            "[{}:v:0]{},setsar=1,{},format=yuv420p[nv{}]",
+            i, fit_filter(&canvas, target.fit), conform_filter(&canvas), i
+        ));
+        filters.push(format!(
+            "[{}:a:0]{}aformat=sample_fmts=fltp:sample_rates=48000:channel_layouts=stereo[na{}]",
+            audio_input, sync, i
+        ));
+        filter_inputs.push(format!("[nv{}][na{}]", i, i));
+    }
+    
+    filters.push(format!(
         "{}concat=n={}:v=1:a=1[v][a]",
         filter_inputs.join(""),
         input_files.len()
-    );
+    ));
     
     args.extend(vec![
         "-filter_complex".to_string(),
-        filter_complex,
+        filters.join(";"),
     ]);
     
-    // Add resolution scaling if specified
-    match resolution {
-        Some("720p") => {
-            args.extend(vec![
-                "-vf".to_string(),
-                "scale=-2:720".to_string(),
-            ]);
-        },
-        Some("1080p") => {
-            args.extend(vec![
-                "-vf".to_string(),
-                "scale=-2:1080".to_string(),
-            ]);
-        },
-        _ => {
-            // Source resolution - no scaling
-        }
-    }
-    
     args.extend(vec![
         "-map".to_string(),
         "[v]".to_string(),
         "-map".to_string(),
         "[a]".to_string(),
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
+    ]);
+    args.extend(encode.output_args());
+    args.extend(vec![
         "-y".to_string(),
         output.to_string(),
     ]);
     
-    let output = Command::new(ffmpeg_path)
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg filter-concat: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg filter-concat failed: {}", stderr));
-    }
-    
-    Ok(output.status.code().unwrap_or(0))
+    let total_duration = infos.iter().map(|info| info.duration).sum::<Option<f64>>();
+    job.run_ffmpeg("filter-concat", &args, Some(output), total_duration).await
 }
 
-/// Export with crossfades between all adjacent inputs using iterative merges.
-/// This approach avoids building a complex single filter graph across N inputs.
+/// Export with crossfades between all adjacent inputs in a single filter graph.
+///
+/// Inputs are fitted onto `resolution` (or the first input's size) and
+/// conformed to `frame_rate` (or the first input's rate, 30 fps if variable).
+/// `duration` is the default transition length; `transitions` optionally sets
+/// the transition type, duration and audio curve per boundary (one entry per
+/// adjacent pair, missing entries use a plain fade).
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn export_with_crossfades(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
     inputs: Vec<String>,
     output: String,
     duration: f32,
-    resolution: Option<String>,
-    temp_dir: Option<String>,
-) -> Result<i32, String> {
+    resolution: Option<Resolution>,
+    fit_mode: Option<FitMode>,
+    frame_rate: Option<f64>,
+    frame_rate_conversion: Option<FrameRateConversion>,
+    transitions: Option<Vec<TransitionSpec>>,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&output))?;
     if inputs.is_empty() {
-        return Err("No input files provided".to_string());
+        return Err("No input files provided".to_string().into());
     }
+    let encode = resolve_encode_settings(&app, encode)?;
 
-    let ffmpeg_path = get_ffmpeg_path()?;
-    let crossfade_d = if duration > 0.0 { duration as f64 } else { 1.0_f64 };
-
-    // If only one input, optionally scale and write out directly
-    if inputs.len() == 1 {
-        let mut args = vec!["-i".to_string(), inputs[0].clone()];
-
-        match resolution.as_deref() {
-            Some("720p") => { args.extend(vec!["-vf".to_string(), "scale=-2:720".to_string()]); },
-            Some("1080p") => { args.extend(vec!["-vf".to_string(), "scale=-2:1080".to_string()]); },
-            _ => {}
-        }
-
-        args.extend(vec![
-            "-c:v".to_string(), "libx264".to_string(),
-            "-c:a".to_string(), "aac".to_string(),
-            "-movflags".to_string(), "+faststart".to_string(),
-            "-y".to_string(), output.clone(),
-        ]);
-
-        let out = Command::new(ffmpeg_path)
-            .args(&args)
-            .output()
-            .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
-
-        if !out.status.success() {
-            let stderr = String::from_utf8_lossy(&out.stderr);
-            return Err(format!("FFmpeg failed: {}", stderr));
-        }
-        return Ok(out.status.code().unwrap_or(0));
-    }
+    let job = start_job(&app, "export", job_id)?;
+    let result = export_with_crossfades_internal(
+        &job,
+        &inputs,
+        &output,
+        duration as f64,
+        OutputTarget::new(resolution, fit_mode, frame_rate, frame_rate_conversion),
+        &transitions.unwrap_or_default(),
+        &encode,
+    ).await;
+    job.finish(result).map_err(CommandError::from)
+}
 
-    // Temp directory for intermediates
-    let temp_dir_path = temp_dir.unwrap_or_else(|| std::env::temp_dir().to_string_lossy().to_string());
-    let mut current_left = inputs[0].clone();
-
-    // Iteratively merge with crossfades
-    for (idx, right) in inputs.iter().enumerate().skip(1) {
-        let left_duration = probe_duration_seconds(&current_left)?;
-        let offset = (left_duration - crossfade_d).max(0.0);
-
-        let intermediate = format!("{}/xfade_step_{}.mp4", temp_dir_path.trim_end_matches('/'), idx);
-
-        // Build filter_complex: video xfade and audio acrossfade
-        // Example:
-        // [0:v][1:v] xfade=transition=fade:duration=t:offset=off [v]; [0:a][1:a] acrossfade=d=t [a]
-        let filter = format!(
-            "[0:v][1:v]xfade=transition=fade:duration={}:offset={}[v];[0:a][1:a]acrossfade=d={}[a]",
-            crossfade_d, offset, crossfade_d
-        );
-
-        let args = vec![
-            "-i".to_string(), current_left.clone(),
-            "-i".to_string(), right.clone(),
-            "-filter_complex".to_string(), filter,
-            "-map".to_string(), "[v]".to_string(),
-            "-map".to_string(), "[a]".to_string(),
-            "-c:v".to_string(), "libx264".to_string(),
-            "-c:a".to_string(), "aac".to_string(),
-            "-movflags".to_string(), "+faststart".to_string(),
-            "-y".to_string(), intermediate.clone(),
-        ];
-
-        let out = Command::new(ffmpeg_path)
-            .args(&args)
-            .output()
-            .map_err(|e| format!("Failed to execute FFmpeg xfade step: {}", e))?;
-
-        if !out.status.success() {
-            let stderr = String::from_utf8_lossy(&out.stderr);
-            return Err(format!("FFmpeg xfade step failed: {}", stderr));
-        }
+/// Internal implementation of crossfade export, run as part of a job
+#[allow(clippy::too_many_arguments)]
+async fn export_with_crossfades_internal(
+    job: &Job,
+    inputs: &[String],
+    output: &str,
+    duration: f64,
+    target: OutputTarget,
+    transitions: &[TransitionSpec],
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    let media = probe_inputs(inputs).await?;
+    let canvas = target.canvas(&media[0]);
+    let plan = compile_crossfades(inputs, &media, &canvas, target.fit, duration, transitions)?;
 
-        current_left = intermediate;
-    }
+    let mut args = plan.args;
+    args.extend(vec![
+        "-map".to_string(), plan.video_out,
+        "-map".to_string(), plan.audio_out,
+    ]);
+    args.extend(encode.output_args());
+    args.extend(vec!["-y".to_string(), output.to_string()]);
 
-    // Handle optional scaling of final output
-    if matches!(resolution.as_deref(), Some("720p" | "1080p")) {
-        let mut args = vec!["-i".to_string(), current_left.clone()];
-        match resolution.as_deref() {
-            Some("720p") => { args.extend(vec!["-vf".to_string(), "scale=-2:720".to_string()]); },
-            Some("1080p") => { args.extend(vec!["-vf".to_string(), "scale=-2:1080".to_string()]); },
-            _ => {}
-        }
-        args.extend(vec![
-            "-c:v".to_string(), "libx264".to_string(),
-            "-c:a".to_string(), "aac".to_string(),
-            "-movflags".to_string(), "+faststart".to_string(),
-            "-y".to_string(), output.clone(),
-        ]);
-
-        let out = Command::new(ffmpeg_path)
-            .args(&args)
-            .output()
-            .map_err(|e| format!("Failed to execute FFmpeg scaling: {}", e))?;
-        if !out.status.success() {
-            let stderr = String::from_utf8_lossy(&out.stderr);
-            return Err(format!("FFmpeg scaling failed: {}", stderr));
-        }
-        return Ok(out.status.code().unwrap_or(0));
-    } else {
-        // No scaling requested; move/copy the last intermediate to final output by remuxing
-        let args = vec![
-            "-i".to_string(), current_left.clone(),
-            "-c".to_string(), "copy".to_string(),
-            "-y".to_string(), output.clone(),
-        ];
-        let out = Command::new(ffmpeg_path)
-            .args(&args)
-            .output()
-            .map_err(|e| format!("Failed to finalize output: {}", e))?;
-        if !out.status.success() {
-            let stderr = String::from_utf8_lossy(&out.stderr);
-            return Err(format!("Finalization failed: {}", stderr));
-        }
-        return Ok(out.status.code().unwrap_or(0));
-    }
+    job.run_ffmpeg("crossfade export", &args, Some(output), Some(plan.duration)).await
 }
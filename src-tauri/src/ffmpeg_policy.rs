@@ -0,0 +1,587 @@
+use std::io::Read;
+use std::path::Path;
+
+use crate::sandbox::{CommandError, PathPolicy};
+
+/// Options accepted by `run_ffmpeg`, by name without the leading `-` or a
+/// stream specifier (`-c:v:0` is checked as `c`), and whether they take a value.
+/// Anything else is rejected, including options that read or write files on
+/// their own (`-filter_complex_script`, `-passlogfile`, `-report`, `-attach`,
+/// `-x264-params stats=...`, ...).
+const ALLOWED_OPTIONS: &[(&str, bool)] = &[
+    // Global
+    ("y", false),
+    ("n", false),
+    ("hide_banner", false),
+    ("nostdin", false),
+    ("nostats", false),
+    ("loglevel", true),
+    ("v", true),
+    ("threads", true),
+    ("filter_threads", true),
+    ("filter_complex", true),
+    ("lavfi", true),
+    // Input/output
+    ("i", true),
+    ("f", true),
+    ("ss", true),
+    ("sseof", true),
+    ("t", true),
+    ("to", true),
+    ("itsoffset", true),
+    ("stream_loop", true),
+    ("safe", true),
+    ("fflags", true),
+    ("accurate_seek", false),
+    ("noaccurate_seek", false),
+    ("copyts", false),
+    ("start_at_zero", false),
+    ("shortest", false),
+    ("map", true),
+    ("map_metadata", true),
+    ("map_chapters", true),
+    ("metadata", true),
+    ("disposition", true),
+    ("movflags", true),
+    ("avoid_negative_ts", true),
+    ("max_muxing_queue_size", true),
+    ("video_track_timescale", true),
+    ("frames", true),
+    ("vframes", true),
+    ("aframes", true),
+    ("an", false),
+    ("vn", false),
+    ("sn", false),
+    ("dn", false),
+    // Codecs and filters
+    ("c", true),
+    ("codec", true),
+    ("vcodec", true),
+    ("acodec", true),
+    ("scodec", true),
+    ("tag", true),
+    ("bsf", true),
+    ("filter", true),
+    ("vf", true),
+    ("af", true),
+    ("strict", true),
+    // Video
+    ("r", true),
+    ("fps_mode", true),
+    ("vsync", true),
+    ("s", true),
+    ("aspect", true),
+    ("pix_fmt", true),
+    ("crf", true),
+    ("qscale", true),
+    ("q", true),
+    ("b", true),
+    ("maxrate", true),
+    ("minrate", true),
+    ("bufsize", true),
+    ("preset", true),
+    ("tune", true),
+    ("profile", true),
+    ("level", true),
+    ("g", true),
+    ("keyint_min", true),
+    ("bf", true),
+    ("sc_threshold", true),
+    ("force_key_frames", true),
+    ("color_primaries", true),
+    ("color_trc", true),
+    ("colorspace", true),
+    ("color_range", true),
+    // Audio
+    ("ar", true),
+    ("ac", true),
+    ("sample_fmt", true),
+    ("async", true),
+];
+
+// Formats `-f` may name for an input or output. Anything else is rejected:
+// several formats read or write other files or devices on their own (`lavfi`,
+// `tee`, `fifo`, `hls`, capture devices) or pass arbitrary bytes through
+// (`data`).
+const ALLOWED_FORMATS: &[&str] = &[
+    "adts", "concat", "flac", "gif", "image2", "matroska", "mov", "mp3", "mp4", "mpegts",
+    "null", "ogg", "s16le", "wav", "webm",
+];
+
+// Filters the app emits, plus a few that only transform frames. Anything else
+// is rejected: several filters write files (`metadata`, `signature`, the
+// `psnr`/`ssim` stats files), open them (`movie`) or load plugins (`frei0r`,
+// `ladspa`, `lv2`).
+const ALLOWED_FILTERS: &[&str] = &[
+    "acrossfade", "afade", "aformat", "amix", "anull", "anullsrc", "apad", "aresample",
+    "asetpts", "asettb", "asplit", "atrim", "color", "colorchannelmixer", "concat", "crop",
+    "fade", "format", "fps", "geq", "hflip", "null", "overlay", "pad", "scale", "setdar",
+    "setpts", "setsar", "settb", "silencedetect", "split", "tile", "tpad", "transpose",
+    "trim", "vflip", "volume", "xfade",
+];
+
+// Restricts every input, and anything a concat list or playlist opens from
+// it, to local files
+const PROTOCOL_WHITELIST: &str = "file";
+
+// Enough of an input to recognize a concat list or HLS playlist
+const LIST_SNIFF_BYTES: u64 = 64;
+
+fn rejected(argument: &str, message: String) -> CommandError {
+    CommandError::RejectedArgument {
+        argument: argument.to_string(),
+        message,
+    }
+}
+
+/// Whether `target` names a protocol (`http://...`, `tcp:...`, `concat:a|b`)
+/// rather than a plain path. Single letters are Windows drive prefixes.
+fn is_protocol(target: &str) -> bool {
+    match target.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.' | '_'))
+        }
+        None => false,
+    }
+}
+
+/// Names of the filters used in a filtergraph, parsed the way FFmpeg does:
+/// filters are separated by `,` and `;` outside quotes and link labels, and
+/// quotes and `\` escapes are removed from the name
+fn filter_names(graph: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut chars = graph.chars().peekable();
+
+    loop {
+        // Skip whitespace and input link labels such as `[0:v][1:v]`
+        loop {
+            match chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    chars.next();
+                }
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        // The name ends at an unquoted `=`, `,`, `;` or `[`
+        let mut name = String::new();
+        let mut quoted = false;
+        while let Some(&c) = chars.peek() {
+            if !quoted && matches!(c, '=' | ',' | ';' | '[') {
+                break;
+            }
+            chars.next();
+            match c {
+                '\\' => name.extend(chars.next()),
+                '\'' => quoted = !quoted,
+                _ => name.push(c),
+            }
+        }
+        let name = name.trim();
+        let name = name.split('@').next().unwrap_or(name);
+        if !name.is_empty() {
+            names.push(name.to_string());
+        }
+
+        // Skip the arguments and output labels up to the next separator
+        let mut quoted = false;
+        let mut in_label = false;
+        loop {
+            match chars.next() {
+                None => return names,
+                Some('\\') if !in_label => {
+                    chars.next();
+                }
+                Some('\'') if !in_label => quoted = !quoted,
+                Some('[') if !quoted => in_label = true,
+                Some(']') if in_label => in_label = false,
+                Some(',' | ';') if !quoted && !in_label => break,
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+fn check_filtergraph(graph: &str) -> Result<(), CommandError> {
+    match filter_names(graph).into_iter().find(|name| !ALLOWED_FILTERS.contains(&name.as_str())) {
+        Some(name) => Err(rejected(graph, format!("Filter '{}' is not allowed", name))),
+        None => Ok(()),
+    }
+}
+
+/// Lists of other inputs FFmpeg can open from a local file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum InputList {
+    Concat,
+    Hls,
+}
+
+/// Whether an input is a list of other inputs rather than a plain media file:
+/// named by `-f`, or recognized from its header the way FFmpeg probes it
+fn input_list_kind(input: &str, format: Option<&str>) -> Option<InputList> {
+    match format {
+        Some("concat") => return Some(InputList::Concat),
+        Some("hls") | Some("applehttp") => return Some(InputList::Hls),
+        Some(_) => return None,
+        None => {}
+    }
+
+    let mut head = Vec::new();
+    std::fs::File::open(input)
+        .and_then(|file| file.take(LIST_SNIFF_BYTES).read_to_end(&mut head))
+        .ok()?;
+    let head = String::from_utf8_lossy(&head);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if head.starts_with("ffconcat") {
+        Some(InputList::Concat)
+    } else if head.starts_with("#EXTM3U") {
+        Some(InputList::Hls)
+    } else {
+        None
+    }
+}
+
+/// Unquote a concat list `file` entry
+fn concat_entry(entry: &str) -> String {
+    entry
+        .strip_prefix('\'')
+        .and_then(|e| e.strip_suffix('\''))
+        .map(|e| e.replace("'\\''", "'"))
+        .unwrap_or_else(|| entry.to_string())
+}
+
+/// Check every location a concat list or playlist references; relative
+/// entries are resolved against the list's directory, as FFmpeg does
+fn check_input_list(policy: &PathPolicy, list: &str, kind: InputList) -> Result<(), CommandError> {
+    let contents = std::fs::read_to_string(list)
+        .map_err(|e| format!("Failed to read input list {}: {}", list, e))?;
+
+    for line in contents.lines().map(str::trim) {
+        let location = match kind {
+            InputList::Concat => match line.strip_prefix("file ") {
+                Some(entry) => concat_entry(entry.trim()),
+                None => continue,
+            },
+            // Tags may carry URIs too (keys, maps, alternative renditions)
+            InputList::Hls => match line.strip_prefix('#') {
+                Some(tag) => match tag.split_once("URI=\"") {
+                    Some((_, rest)) => rest.split('"').next().unwrap_or_default().to_string(),
+                    None => continue,
+                },
+                None if line.is_empty() => continue,
+                None => line.to_string(),
+            },
+        };
+        if is_protocol(&location) {
+            return Err(rejected(&location, format!("Input list {} references a protocol", list)));
+        }
+        let base = Path::new(list).parent().unwrap_or(Path::new(""));
+        policy.check_read(&base.join(&location))?;
+    }
+    Ok(())
+}
+
+fn check_input(policy: &PathPolicy, input: &str, format: Option<&str>) -> Result<(), CommandError> {
+    if is_protocol(input) {
+        return Err(rejected(input, format!("Input {} is a protocol, not a local file", input)));
+    }
+    if input == "-" {
+        return Err(rejected(input, "Reading from stdin is not allowed".to_string()));
+    }
+    policy.check_read(Path::new(input))?;
+    if let Some(kind) = input_list_kind(input, format) {
+        check_input_list(policy, input, kind)?;
+    }
+    Ok(())
+}
+
+fn check_output(policy: &PathPolicy, output: &str, format: Option<&str>) -> Result<(), CommandError> {
+    // The null muxer discards everything (used for analysis passes)
+    if format == Some("null") {
+        return Ok(());
+    }
+    if is_protocol(output) {
+        return Err(rejected(output, format!("Output {} is a protocol, not a local file", output)));
+    }
+    policy.check_write(Path::new(output)).map(|_| ())
+}
+
+/// Check a raw FFmpeg argument vector against the allowlist: only known
+/// options and formats, inputs the policy lets commands read (concat list and
+/// playlist entries included), allowlisted filters, and outputs inside the
+/// sandbox.
+///
+/// Returns the arguments to run, with every input limited to the `file`
+/// protocol so nothing a list opens can reach the network.
+pub fn sanitize_ffmpeg_args(policy: &PathPolicy, args: &[String]) -> Result<Vec<String>, CommandError> {
+    // `-f` applies to the next input or output
+    let mut format: Option<&str> = None;
+    let mut sanitized = Vec::with_capacity(args.len());
+    let mut i = 0;
+
+    while i < args.len() {
+        let arg = args[i].as_str();
+        let Some(option) = arg.strip_prefix('-').filter(|o| !o.is_empty()) else {
+            check_output(policy, arg, format.take())?;
+            sanitized.push(arg.to_string());
+            i += 1;
+            continue;
+        };
+
+        let name = option.split(':').next().unwrap_or(option);
+        let takes_value = ALLOWED_OPTIONS
+            .iter()
+            .find(|(allowed, _)| *allowed == name)
+            .map(|(_, takes_value)| *takes_value)
+            .ok_or_else(|| rejected(arg, format!("FFmpeg option {} is not allowed", arg)))?;
+        if !takes_value {
+            sanitized.push(arg.to_string());
+            i += 1;
+            continue;
+        }
+
+        let value = args
+            .get(i + 1)
+            .map(String::as_str)
+            .ok_or_else(|| rejected(arg, format!("FFmpeg option {} needs a value", arg)))?;
+        match name {
+            "i" => {
+                check_input(policy, value, format.take())?;
+                sanitized.extend(["-protocol_whitelist".to_string(), PROTOCOL_WHITELIST.to_string()]);
+            }
+            "f" => {
+                if !ALLOWED_FORMATS.contains(&value) {
+                    return Err(rejected(value, format!("Format '{}' is not allowed", value)));
+                }
+                format = Some(value);
+            }
+            // Unsafe mode lets concat entries use absolute paths and `..`
+            "safe" if value != "1" => {
+                return Err(rejected(value, "Unsafe concat lists are not allowed".to_string()));
+            }
+            "vf" | "af" | "filter" | "filter_complex" | "lavfi" => check_filtergraph(value)?,
+            _ => {}
+        }
+        sanitized.extend([arg.to_string(), value.to_string()]);
+        i += 2;
+    }
+
+    Ok(sanitized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A project directory under the system temp dir, removed on drop
+    struct TempProject(PathBuf);
+
+    impl TempProject {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("trimbot_policy_{}_{}", name, uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempProject(dir.canonicalize().unwrap())
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.0.join(name).display().to_string()
+        }
+
+        fn policy(&self) -> PathPolicy {
+            let policy = PathPolicy::default();
+            policy.set_project_dir(&self.0).unwrap();
+            policy
+        }
+    }
+
+    impl Drop for TempProject {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn rejected_argument(result: Result<Vec<String>, CommandError>) -> String {
+        match result {
+            Err(CommandError::RejectedArgument { argument, .. }) => argument,
+            other => panic!("expected a rejected argument, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn allows_a_plain_export_and_whitelists_inputs() {
+        let project = TempProject::new("plain");
+        let input = project.path("clip.mp4");
+        let output = project.path("exports/out.mp4");
+        let sanitized = sanitize_ffmpeg_args(
+            &project.policy(),
+            &args(&["-y", "-ss", "1", "-i", &input, "-vf", "scale=1280:-2,fps=30", "-c:v", "libx264", &output]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            sanitized,
+            args(&[
+                "-y", "-ss", "1", "-protocol_whitelist", "file", "-i", &input,
+                "-vf", "scale=1280:-2,fps=30", "-c:v", "libx264", &output,
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_protocol_and_stdin_inputs() {
+        let project = TempProject::new("protocol");
+        let policy = project.policy();
+        let output = project.path("out.mp4");
+
+        for input in ["http://example.com/a.mp4", "tcp://127.0.0.1:9000", "concat:a.mp4|b.mp4", "-"] {
+            assert_eq!(rejected_argument(sanitize_ffmpeg_args(&policy, &args(&["-i", input, &output]))), input);
+        }
+    }
+
+    #[test]
+    fn rejects_inputs_outside_the_read_set() {
+        let project = TempProject::new("read");
+        let footage = TempProject::new("footage");
+        let policy = project.policy();
+        let output = project.path("out.mp4");
+        let picked = footage.path("take1.mp4");
+        std::fs::write(&picked, b"").unwrap();
+
+        let result = sanitize_ffmpeg_args(&policy, &args(&["-i", &picked, "-c", "copy", &output]));
+        assert!(matches!(result, Err(CommandError::PermissionDenied { .. })), "{:?}", result);
+        let result = sanitize_ffmpeg_args(&policy, &args(&["-i", "clip.mp4", &output]));
+        assert!(matches!(result, Err(CommandError::PermissionDenied { .. })), "{:?}", result);
+
+        // Files picked in a dialog may be read
+        policy.allow_read(Path::new(&picked)).unwrap();
+        assert!(sanitize_ffmpeg_args(&policy, &args(&["-i", &picked, "-c", "copy", &output])).is_ok());
+    }
+
+    #[test]
+    fn rejects_formats_outside_the_allowlist() {
+        let project = TempProject::new("format");
+        let input = project.path("clip.mp4");
+        let output = project.path("out.mp4");
+
+        for format in ["lavfi", "tee", "fifo", "data", "hls", "v4l2"] {
+            let result = sanitize_ffmpeg_args(&project.policy(), &args(&["-f", format, "-i", &input, &output]));
+            assert_eq!(rejected_argument(result), format);
+            let result = sanitize_ffmpeg_args(&project.policy(), &args(&["-i", &input, "-f", format, &output]));
+            assert_eq!(rejected_argument(result), format);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        let project = TempProject::new("option");
+        let input = project.path("clip.mp4");
+        let output = project.path("out.mp4");
+
+        for option in ["-filter_complex_script", "-report", "-x264-params", "-/vf", "-protocol_whitelist"] {
+            let result = sanitize_ffmpeg_args(&project.policy(), &args(&["-i", &input, option, "x", &output]));
+            assert_eq!(rejected_argument(result), option);
+        }
+    }
+
+    #[test]
+    fn rejects_filters_outside_the_allowlist() {
+        let project = TempProject::new("filter");
+        let input = project.path("clip.mp4");
+        let output = project.path("out.mp4");
+
+        for graph in [
+            "movie=/etc/passwd",
+            "scale=640:-2,metadata=mode=print:file=/tmp/x",
+            "[0:a]ametadata=mode=print:file=/tmp/x[a]",
+            "[0:v][1:v]psnr=stats_file=/tmp/x",
+            "frei0r=filter_name=x",
+            "nonexistent",
+        ] {
+            let result = sanitize_ffmpeg_args(&project.policy(), &args(&["-i", &input, "-filter_complex", graph, &output]));
+            assert_eq!(rejected_argument(result), graph);
+        }
+    }
+
+    #[test]
+    fn parses_quoted_and_escaped_filtergraphs() {
+        assert_eq!(filter_names("[0:v][1:v]xfade=transition=fade:offset=4[v];[0:a]atrim=0:5[a]"), ["xfade", "atrim"]);
+        // Separators inside quotes or escaped do not start a new filter
+        assert_eq!(filter_names("geq=lum='p(X,Y)';color=c=black"), ["geq", "color"]);
+        assert_eq!(filter_names("scale=w=640\\,h=360"), ["scale"]);
+        // Quotes and escapes in a name are removed, as FFmpeg does
+        assert_eq!(filter_names("scale=640:-2,'mov'ie=x"), ["scale", "movie"]);
+        assert_eq!(filter_names("met\\adata@probe=mode=print"), ["metadata"]);
+
+        let project = TempProject::new("quoted");
+        let input = project.path("clip.mp4");
+        let output = project.path("out.mp4");
+        let result = sanitize_ffmpeg_args(&project.policy(), &args(&["-i", &input, "-vf", "volume='1,2',am'ovie'=x", &output]));
+        assert_eq!(rejected_argument(result), "volume='1,2',am'ovie'=x");
+    }
+
+    #[test]
+    fn checks_concat_lists_and_playlists() {
+        let project = TempProject::new("lists");
+        let policy = project.policy();
+        let output = project.path("out.mp4");
+
+        let local = project.path("local.txt");
+        let inside = project.path("media/a.mp4");
+        std::fs::write(&local, format!("file '{}'\nfile 'b.mp4'\n", inside)).unwrap();
+        assert!(sanitize_ffmpeg_args(&policy, &args(&["-f", "concat", "-i", &local, &output])).is_ok());
+        let result = sanitize_ffmpeg_args(&policy, &args(&["-f", "concat", "-safe", "0", "-i", &local, &output]));
+        assert_eq!(rejected_argument(result), "0");
+
+        // Entries must be readable, wherever the list itself is
+        for entry in ["/etc/passwd", "../outside.mp4"] {
+            let escape = project.path("escape.txt");
+            std::fs::write(&escape, format!("file '{}'\n", entry)).unwrap();
+            let result = sanitize_ffmpeg_args(&policy, &args(&["-f", "concat", "-i", &escape, &output]));
+            assert!(matches!(result, Err(CommandError::PermissionDenied { .. })), "{}: {:?}", entry, result);
+        }
+
+        let remote = project.path("remote.txt");
+        std::fs::write(&remote, "file 'a.mp4'\nfile 'http://example.com/b.mp4'\n").unwrap();
+        let result = sanitize_ffmpeg_args(&policy, &args(&["-f", "concat", "-i", &remote, &output]));
+        assert_eq!(rejected_argument(result), "http://example.com/b.mp4");
+
+        // Recognized from the header without `-f`
+        let ffconcat = project.path("list.txt");
+        std::fs::write(&ffconcat, "ffconcat version 1.0\nfile tcp://127.0.0.1:9000\n").unwrap();
+        let result = sanitize_ffmpeg_args(&policy, &args(&["-i", &ffconcat, &output]));
+        assert_eq!(rejected_argument(result), "tcp://127.0.0.1:9000");
+
+        let playlist = project.path("list.m3u8");
+        std::fs::write(&playlist, "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"https://example.com/key\"\nseg0.ts\n").unwrap();
+        let result = sanitize_ffmpeg_args(&policy, &args(&["-i", &playlist, &output]));
+        assert_eq!(rejected_argument(result), "https://example.com/key");
+    }
+
+    #[test]
+    fn rejects_outputs_outside_the_project() {
+        let project = TempProject::new("output");
+        let input = project.path("clip.mp4");
+
+        let result = sanitize_ffmpeg_args(&project.policy(), &args(&["-i", &input, "/etc/cron.d/x.mp4"]));
+        assert!(matches!(result, Err(CommandError::PermissionDenied { .. })), "{:?}", result);
+        let result = sanitize_ffmpeg_args(&project.policy(), &args(&["-i", &input, "file:/tmp/x.mp4"]));
+        assert_eq!(rejected_argument(result), "file:/tmp/x.mp4");
+        // The null muxer writes nothing
+        assert!(sanitize_ffmpeg_args(&project.policy(), &args(&["-i", &input, "-f", "null", "-"])).is_ok());
+    }
+}
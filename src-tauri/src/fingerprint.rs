@@ -0,0 +1,113 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::media::probe_media_info;
+
+// Bytes hashed from each end of a file for the partial hash
+const PARTIAL_HASH_CHUNK: u64 = 1024 * 1024;
+
+/// Identity of a source file, recorded so it can be found again after it is
+/// moved or renamed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaFingerprint {
+    pub path: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// SHA-256 of the size and the first and last MiB (see `partial_hash`)
+    pub partial_hash: String,
+}
+
+/// Fast content hash: the file size plus its first and last MiB. Files that
+/// differ only in the middle collide.
+pub fn partial_hash(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read metadata of {}: {}", path.display(), e))?
+        .len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    let mut chunk = Vec::new();
+    (&mut file)
+        .take(PARTIAL_HASH_CHUNK)
+        .read_to_end(&mut chunk)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    hasher.update(&chunk);
+
+    if size > PARTIAL_HASH_CHUNK * 2 {
+        chunk.clear();
+        file.seek(SeekFrom::End(-(PARTIAL_HASH_CHUNK as i64)))
+            .and_then(|_| file.read_to_end(&mut chunk))
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        hasher.update(&chunk);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Read buffer for streaming hashes
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Stream `reader` into the hasher, copying every chunk to `sink` if given
+fn hash_stream(
+    reader: &mut impl Read,
+    mut sink: Option<&mut dyn Write>,
+) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        if let Some(sink) = sink.as_mut() {
+            sink.write_all(&buffer[..read])?;
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// SHA-256 of the whole file, read in chunks
+pub fn full_hash(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    hash_stream(&mut file, None)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+/// Copy `source` to `destination` and return the SHA-256 of what was copied,
+/// reading the source only once
+pub fn copy_with_hash(source: &Path, destination: &Path) -> Result<String, String> {
+    let mut input = File::open(source)
+        .map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+    let mut output = File::create(destination)
+        .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+    let hash = hash_stream(&mut input, Some(&mut output))
+        .and_then(|hash| output.sync_all().map(|_| hash))
+        .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+    Ok(hash)
+}
+
+/// Fingerprint a file on disk. Duration is best effort; non-media files have none.
+pub fn fingerprint_file(path: &str) -> Result<MediaFingerprint, String> {
+    let file_path = Path::new(path);
+    let size = std::fs::metadata(file_path)
+        .map_err(|e| format!("Failed to read metadata of {}: {}", path, e))?
+        .len();
+
+    Ok(MediaFingerprint {
+        path: path.to_string(),
+        size,
+        duration: probe_media_info(path).ok().and_then(|info| info.duration),
+        partial_hash: partial_hash(file_path)?,
+    })
+}
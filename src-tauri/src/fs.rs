@@ -1,25 +1,59 @@
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use tauri::Manager;
 use tauri_plugin_dialog::DialogExt;
 
+use crate::fingerprint::{copy_with_hash, full_hash, partial_hash};
+use crate::sandbox::{activate_project_dir, check_file_name, CommandError, PathPolicy};
+
+/// Write a file so readers see either the old or the new contents, never a
+/// partial write: write a sibling temp file, flush it to disk, then rename
+pub fn write_file_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+
+    result.map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        format!("Failed to write {}: {}", path.display(), e)
+    })
+}
+
 /// Write concat list file for FFmpeg
 #[tauri::command]
-pub async fn write_concat_list(lines: Vec<String>, list_path: String) -> Result<(), String> {
+pub async fn write_concat_list(
+    policy: tauri::State<'_, PathPolicy>,
+    lines: Vec<String>,
+    list_path: String,
+) -> Result<(), CommandError> {
     let content = lines.join("\n");
-    let path = PathBuf::from(&list_path);
+    let path = policy.check_write(Path::new(&list_path))?;
     fs::write(&path, content).await
         .map_err(|e| format!("Failed to write concat list: {}", e))?;
     Ok(())
 }
 
-/// Save blob data to file
+/// Save blob data to file. Large files such as recordings should be streamed
+/// with `begin_blob_upload` instead.
 #[tauri::command]
 pub async fn save_blob(
     app: tauri::AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
     filename: String,
     data: Vec<u8>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
+    check_file_name(&filename)?;
     let app_dir = app.path().app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
     
@@ -27,7 +61,7 @@ pub async fn save_blob(
     tokio::fs::create_dir_all(&media_dir).await
         .map_err(|e| format!("Failed to create media directory: {}", e))?;
     
-    let file_path = media_dir.join(&filename);
+    let file_path = policy.check_write(&media_dir.join(&filename))?;
     tokio::fs::write(&file_path, data).await
         .map_err(|e| format!("Failed to write file: {}", e))?;
     
@@ -35,9 +69,13 @@ pub async fn save_blob(
     Ok(file_path.display().to_string())
 }
 
-/// Open file dialog for multiple file selection
+/// Open file dialog for multiple file selection. The chosen files may then
+/// be read by commands that check inputs, such as `run_ffmpeg`.
 #[tauri::command]
-pub async fn open_file_dialog_multi(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+pub async fn open_file_dialog_multi(
+    app: tauri::AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+) -> Result<Vec<String>, String> {
     let file_paths = app.dialog()
         .file()
         .add_filter("Video Files", &["mp4", "mov", "webm", "avi", "mkv"])
@@ -45,12 +83,19 @@ pub async fn open_file_dialog_multi(app: tauri::AppHandle) -> Result<Vec<String>
         .blocking_pick_files();
     
     match file_paths {
-        Some(paths) => Ok(paths.iter().map(|p| p.to_string()).collect()),
+        Some(paths) => {
+            let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+            for path in &paths {
+                policy.allow_read(Path::new(path))?;
+            }
+            Ok(paths)
+        }
         None => Ok(vec![]), // User cancelled
     }
 }
 
-/// Open directory dialog
+/// Open directory dialog. The chosen directory becomes the active project
+/// directory that filesystem commands may write to.
 #[tauri::command]
 pub async fn open_dir_dialog(app: tauri::AppHandle) -> Result<String, String> {
     let dir_path = app.dialog()
@@ -59,15 +104,22 @@ pub async fn open_dir_dialog(app: tauri::AppHandle) -> Result<String, String> {
         .blocking_pick_folder();
     
     match dir_path {
-        Some(path) => Ok(path.to_string()),
+        Some(path) => {
+            let path = path.to_string();
+            activate_project_dir(&app, Path::new(&path))?;
+            Ok(path)
+        }
         None => Err("No directory selected".to_string()),
     }
 }
 
 /// Create project directory structure
 #[tauri::command]
-pub async fn create_project_dirs(project_dir: String) -> Result<(), String> {
-    let project_path = PathBuf::from(&project_dir);
+pub async fn create_project_dirs(
+    policy: tauri::State<'_, PathPolicy>,
+    project_dir: String,
+) -> Result<(), CommandError> {
+    let project_path = policy.check_write(Path::new(&project_dir))?;
     
     // Create main directories
     let dirs = ["media", "exports", ".temp"];
@@ -82,35 +134,136 @@ pub async fn create_project_dirs(project_dir: String) -> Result<(), String> {
 
 /// Ensure a directory exists, creating it if necessary
 #[tauri::command]
-pub async fn ensure_dir(path: String) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
+pub async fn ensure_dir(policy: tauri::State<'_, PathPolicy>, path: String) -> Result<(), CommandError> {
+    let path_buf = policy.check_write(Path::new(&path))?;
     fs::create_dir_all(&path_buf).await
         .map_err(|e| format!("Failed to create directory {}: {}", path, e))?;
     Ok(())
 }
 
-/// Copy file to project media directory
+/// A file imported into the project's media folder
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedMedia {
+    /// SHA-256 of the file contents; identical files get the same ID
+    pub id: String,
+    pub path: String,
+    /// The contents were already in the media folder and that copy was reused
+    pub reused: bool,
+}
+
+/// Look for a file in `media_dir` with the same contents as `source`.
+///
+/// Only files of the same size are considered; their partial hashes are
+/// compared before anything is hashed in full. Returns the copy and the
+/// full content hash.
+fn find_existing_copy(media_dir: &Path, source: &Path, size: u64) -> Result<Option<(PathBuf, String)>, String> {
+    let Ok(entries) = std::fs::read_dir(media_dir) else {
+        return Ok(None);
+    };
+
+    let mut source_partial = None;
+    let mut source_full = None;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let candidate = entry.path();
+        let is_match = entry.file_type().is_ok_and(|t| t.is_file())
+            && entry.metadata().is_ok_and(|m| m.len() == size)
+            && !entry.file_name().to_string_lossy().ends_with(".part");
+        if !is_match {
+            continue;
+        }
+
+        if source_partial.is_none() {
+            source_partial = Some(partial_hash(source)?);
+        }
+        if partial_hash(&candidate).ok() != source_partial {
+            continue;
+        }
+
+        if source_full.is_none() {
+            source_full = Some(full_hash(source)?);
+        }
+        if full_hash(&candidate).ok() == source_full {
+            return Ok(source_full.map(|hash| (candidate, hash)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// `media_dir/filename`, or `name-1.ext`, `name-2.ext`, ... if that is taken
+pub fn unique_media_path(media_dir: &Path, filename: &str) -> PathBuf {
+    let path = media_dir.join(filename);
+    if !path.exists() {
+        return path;
+    }
+
+    let name = Path::new(filename);
+    let stem = name.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = name.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..)
+        .map(|n| media_dir.join(format!("{}-{}{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Import `source` into `media_dir`, reusing an identical copy if there is one
+fn import_media_file(source: &Path, media_dir: &Path, filename: &str) -> Result<ImportedMedia, String> {
+    let size = std::fs::metadata(source)
+        .map_err(|e| format!("Failed to read source file: {}", e))?
+        .len();
+    std::fs::create_dir_all(media_dir)
+        .map_err(|e| format!("Failed to create media directory: {}", e))?;
+
+    if let Some((path, id)) = find_existing_copy(media_dir, source, size)? {
+        return Ok(ImportedMedia {
+            id,
+            path: path.display().to_string(),
+            reused: true,
+        });
+    }
+
+    // Copy under a temporary name so a failed import never leaves a partial file
+    let temp_path = media_dir.join(format!(".{}.part", filename));
+    let id = copy_with_hash(source, &temp_path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })?;
+
+    let destination = unique_media_path(media_dir, filename);
+    std::fs::rename(&temp_path, &destination).map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        format!("Failed to copy file: {}", e)
+    })?;
+
+    // Use display() for consistent cross-platform path representation
+    Ok(ImportedMedia {
+        id,
+        path: destination.display().to_string(),
+        reused: false,
+    })
+}
+
+/// Copy file to project media directory.
+///
+/// Files whose contents are already in `media/` are not copied again, and a
+/// name clash with different contents gets a numbered name instead of
+/// overwriting.
 #[tauri::command]
 pub async fn copy_file_to_media(
+    policy: tauri::State<'_, PathPolicy>,
     source_path: String,
     project_dir: String,
     filename: String,
-) -> Result<String, String> {
-    let source = PathBuf::from(&source_path);
-    let project_path = PathBuf::from(&project_dir);
-    let media_dir = project_path.join("media");
-    let destination = media_dir.join(&filename);
-    
-    // Ensure media directory exists
-    fs::create_dir_all(&media_dir).await
-        .map_err(|e| format!("Failed to create media directory: {}", e))?;
-    
-    // Copy the file
-    fs::copy(&source, &destination).await
-        .map_err(|e| format!("Failed to copy file: {}", e))?;
-    
-    // Use display() for consistent cross-platform path representation
-    Ok(destination.display().to_string())
+) -> Result<ImportedMedia, CommandError> {
+    check_file_name(&filename)?;
+    let media_dir = policy.check_write(&Path::new(&project_dir).join("media"))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        import_media_file(Path::new(&source_path), &media_dir, &filename)
+    })
+    .await
+    .map_err(|e| format!("Failed to copy file: {}", e))?
+    .map_err(CommandError::from)
 }
 
 /// Get app directories info
@@ -129,4 +282,125 @@ pub fn resolve_app_dirs(app_handle: tauri::AppHandle) -> Result<AppDirs, String>
 #[derive(serde::Serialize)]
 pub struct AppDirs {
     pub app_data: String,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("trimbot_fs_{}_{}", name, uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, name: &str, contents: &[u8]) -> PathBuf {
+            let path = self.0.join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn media_files(media_dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(media_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn importing_the_same_contents_twice_reuses_the_copy() {
+        let sources = TempDir::new("sources");
+        let media = TempDir::new("media");
+        let first = sources.file("take1.mp4", b"footage");
+        let again = sources.file("renamed.mp4", b"footage");
+
+        let imported = import_media_file(&first, &media.0, "take1.mp4").unwrap();
+        assert!(!imported.reused);
+        assert_eq!(imported.id, full_hash(&first).unwrap());
+
+        let reused = import_media_file(&again, &media.0, "renamed.mp4").unwrap();
+        assert!(reused.reused);
+        assert_eq!(reused.id, imported.id);
+        assert_eq!(reused.path, imported.path);
+        assert_eq!(media_files(&media.0), ["take1.mp4"]);
+    }
+
+    #[test]
+    fn name_clashes_get_numbered_names() {
+        let sources = TempDir::new("clash_sources");
+        let media = TempDir::new("clash_media");
+
+        for contents in [&b"first"[..], b"second", b"third"] {
+            let source = sources.file("clip.mp4", contents);
+            let imported = import_media_file(&source, &media.0, "clip.mp4").unwrap();
+            assert!(!imported.reused);
+        }
+        assert_eq!(media_files(&media.0), ["clip-1.mp4", "clip-2.mp4", "clip.mp4"]);
+        assert_eq!(std::fs::read(media.0.join("clip-2.mp4")).unwrap(), b"third");
+    }
+
+    #[test]
+    fn unique_paths_number_names_with_and_without_extensions() {
+        let media = TempDir::new("unique");
+        assert_eq!(unique_media_path(&media.0, "clip.mp4"), media.0.join("clip.mp4"));
+
+        media.file("clip.mp4", b"");
+        media.file("clip-1.mp4", b"");
+        media.file("notes", b"");
+        media.file("archive.tar.gz", b"");
+        assert_eq!(unique_media_path(&media.0, "clip.mp4"), media.0.join("clip-2.mp4"));
+        assert_eq!(unique_media_path(&media.0, "notes"), media.0.join("notes-1"));
+        assert_eq!(unique_media_path(&media.0, "archive.tar.gz"), media.0.join("archive.tar-1.gz"));
+    }
+
+    #[test]
+    fn partial_hash_collisions_are_settled_by_the_full_hash() {
+        let sources = TempDir::new("partial_sources");
+        let media = TempDir::new("partial_media");
+        // Same size, first and last MiB; only the middle differs
+        let mut contents = vec![7u8; 3 * 1024 * 1024];
+        let existing = media.file("existing.mp4", &contents);
+        contents[3 * 512 * 1024] = 8;
+        let source = sources.file("take.mp4", &contents);
+        assert_eq!(partial_hash(&existing).unwrap(), partial_hash(&source).unwrap());
+
+        assert_eq!(find_existing_copy(&media.0, &source, contents.len() as u64).unwrap(), None);
+        let imported = import_media_file(&source, &media.0, "take.mp4").unwrap();
+        assert!(!imported.reused);
+        assert_eq!(media_files(&media.0), ["existing.mp4", "take.mp4"]);
+    }
+
+    #[test]
+    fn partial_downloads_and_other_sizes_are_not_reused() {
+        let sources = TempDir::new("skip_sources");
+        let media = TempDir::new("skip_media");
+        let source = sources.file("take.mp4", b"footage");
+        media.file(".take.mp4.part", b"footage");
+        media.file("longer.mp4", b"footage and more");
+
+        assert_eq!(find_existing_copy(&media.0, &source, 7).unwrap(), None);
+        // No media folder yet is not an error
+        assert_eq!(find_existing_copy(&media.0.join("missing"), &source, 7).unwrap(), None);
+    }
+
+    #[test]
+    fn failed_imports_leave_nothing_behind() {
+        let media = TempDir::new("failed");
+        let missing = media.0.join("does-not-exist.mp4");
+
+        assert!(import_media_file(&missing, &media.0.join("media"), "clip.mp4").is_err());
+        assert!(!media.0.join("media").exists());
+    }
+}
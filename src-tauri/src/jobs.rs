@@ -0,0 +1,466 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::Notify;
+
+use crate::ffmpeg::get_ffmpeg_path;
+
+// Event names emitted to the frontend
+pub const JOB_STARTED_EVENT: &str = "job://started";
+pub const JOB_PROGRESS_EVENT: &str = "job://progress";
+pub const JOB_FINISHED_EVENT: &str = "job://finished";
+
+// Keep only the tail of stderr for error messages
+const STDERR_TAIL_BYTES: usize = 8 * 1024;
+
+/// Shared cancellation state for one job
+#[derive(Default)]
+struct JobControl {
+    cancelled: AtomicBool,
+    notify: Notify,
+    outputs: Mutex<Vec<PathBuf>>,
+}
+
+/// Registry of running FFmpeg jobs, managed as Tauri state
+#[derive(Default)]
+pub struct JobManager {
+    jobs: Arc<Mutex<HashMap<String, Arc<JobControl>>>>,
+}
+
+impl JobManager {
+    /// Request cancellation of a job. Returns false if the job is unknown.
+    pub fn cancel(&self, job_id: &str) -> bool {
+        let jobs = self.jobs.lock().unwrap();
+        match jobs.get(job_id) {
+            Some(control) => {
+                control.cancelled.store(true, Ordering::SeqCst);
+                control.notify.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// IDs of all jobs currently registered
+    pub fn job_ids(&self) -> Vec<String> {
+        self.jobs.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+/// Progress snapshot parsed from `-progress pipe:1` output
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobProgress {
+    pub job_id: String,
+    pub out_time_sec: f64,
+    pub percent: Option<f64>,
+    pub fps: Option<f64>,
+    pub speed: Option<f64>,
+    pub eta_sec: Option<f64>,
+    pub done: bool,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobStarted {
+    job_id: String,
+    kind: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobFinished {
+    job_id: String,
+    status: &'static str,
+    error: Option<String>,
+}
+
+/// Incremental parser for FFmpeg `-progress` key=value blocks
+pub struct ProgressParser {
+    total_duration: Option<f64>,
+    current: JobProgress,
+}
+
+impl ProgressParser {
+    pub fn new(job_id: &str, total_duration: Option<f64>) -> Self {
+        Self {
+            total_duration: total_duration.filter(|d| *d > 0.0),
+            current: JobProgress {
+                job_id: job_id.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Feed one line; returns a snapshot when a progress block is complete
+    pub fn feed_line(&mut self, line: &str) -> Option<JobProgress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+
+        match key {
+            // Both keys are reported in microseconds by FFmpeg
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(us) = value.parse::<i64>() {
+                    self.current.out_time_sec = (us.max(0) as f64) / 1_000_000.0;
+                }
+            }
+            "fps" => self.current.fps = value.parse::<f64>().ok(),
+            "speed" => {
+                self.current.speed = value
+                    .trim_end_matches('x')
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|s| *s > 0.0);
+            }
+            "progress" => {
+                self.current.done = value == "end";
+                self.update_estimates();
+                return Some(self.current.clone());
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn update_estimates(&mut self) {
+        let Some(total) = self.total_duration else {
+            return;
+        };
+
+        if self.current.done {
+            self.current.percent = Some(100.0);
+            self.current.eta_sec = Some(0.0);
+            return;
+        }
+
+        let elapsed = self.current.out_time_sec.min(total);
+        self.current.percent = Some(elapsed / total * 100.0);
+        self.current.eta_sec = self.current.speed.map(|speed| (total - elapsed) / speed);
+    }
+}
+
+/// Handle for a tracked operation. One job may run several FFmpeg processes.
+pub struct Job {
+    id: String,
+    app: AppHandle,
+    control: Arc<JobControl>,
+    registry: Arc<Mutex<HashMap<String, Arc<JobControl>>>>,
+}
+
+/// Register a new job, using the caller-provided ID if there is one.
+/// Fails if a job with that ID is still running.
+pub fn start_job(app: &AppHandle, kind: &str, job_id: Option<String>) -> Result<Job, String> {
+    let manager = app.state::<JobManager>();
+    let id = job_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let control = Arc::new(JobControl::default());
+
+    match manager.jobs.lock().unwrap().entry(id.clone()) {
+        Entry::Occupied(_) => return Err(format!("A job with id {} is already running", id)),
+        Entry::Vacant(entry) => {
+            entry.insert(control.clone());
+        }
+    }
+
+    let _ = app.emit(
+        JOB_STARTED_EVENT,
+        JobStarted {
+            job_id: id.clone(),
+            kind: kind.to_string(),
+        },
+    );
+
+    Ok(Job {
+        id,
+        app: app.clone(),
+        control,
+        registry: manager.jobs.clone(),
+    })
+}
+
+impl Job {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.control.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Error returned when the job has been cancelled
+    pub fn cancelled_error(&self) -> String {
+        format!("Job {} was cancelled", self.id)
+    }
+
+    /// Register a file to delete if the job is cancelled
+    pub fn track_output(&self, output: &str) {
+        self.control.outputs.lock().unwrap().push(PathBuf::from(output));
+    }
+
+    /// Run FFmpeg as a tracked child process, emitting progress events.
+    ///
+    /// `what` names the step for error messages, `output` is deleted if the
+    /// job is cancelled, and `duration` (seconds of output) drives percent/ETA.
+    pub async fn run_ffmpeg(
+        &self,
+        what: &str,
+        args: &[String],
+        output: Option<&str>,
+        duration: Option<f64>,
+    ) -> Result<i32, String> {
+        self.spawn_ffmpeg(what, args, output, duration, false)
+            .await
+            .map(|(code, _)| code)
+    }
+
+    /// Like `run_ffmpeg`, but returns everything FFmpeg logged to stderr, for
+    /// analysis filters (e.g. `silencedetect`) that report through the log
+    pub async fn run_ffmpeg_logged(
+        &self,
+        what: &str,
+        args: &[String],
+        duration: Option<f64>,
+    ) -> Result<String, String> {
+        let (_, log) = self.spawn_ffmpeg(what, args, None, duration, true).await?;
+        Ok(String::from_utf8_lossy(&log).to_string())
+    }
+
+    async fn spawn_ffmpeg(
+        &self,
+        what: &str,
+        args: &[String],
+        output: Option<&str>,
+        duration: Option<f64>,
+        keep_log: bool,
+    ) -> Result<(i32, Vec<u8>), String> {
+        if self.is_cancelled() {
+            return Err(self.cancelled_error());
+        }
+
+        if let Some(output) = output {
+            self.track_output(output);
+        }
+
+        let ffmpeg_path = get_ffmpeg_path()?;
+        let mut child = Command::new(ffmpeg_path)
+            .args(["-progress", "pipe:1", "-nostats"])
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to execute FFmpeg {}: {}", what, e))?;
+
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+
+        let progress_task = {
+            let app = self.app.clone();
+            let mut parser = ProgressParser::new(&self.id, duration);
+            tokio::spawn(async move {
+                let Some(stdout) = stdout else { return };
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Some(progress) = parser.feed_line(&line) {
+                        let _ = app.emit(JOB_PROGRESS_EVENT, progress);
+                    }
+                }
+            })
+        };
+
+        let stderr_task = tokio::spawn(async move {
+            let mut buf = Vec::new();
+            if let Some(mut stderr) = stderr {
+                let mut chunk = [0u8; 4096];
+                while let Ok(n) = stderr.read(&mut chunk).await {
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                    if !keep_log && buf.len() > STDERR_TAIL_BYTES * 2 {
+                        buf.drain(..buf.len() - STDERR_TAIL_BYTES);
+                    }
+                }
+            }
+            buf
+        });
+
+        let status = tokio::select! {
+            status = child.wait() => {
+                status.map_err(|e| format!("Failed to wait for FFmpeg {}: {}", what, e))?
+            }
+            _ = self.control.notify.notified() => {
+                let _ = child.kill().await;
+                let _ = progress_task.await;
+                let _ = stderr_task.await;
+                return Err(self.cancelled_error());
+            }
+        };
+
+        let _ = progress_task.await;
+        let stderr = stderr_task.await.unwrap_or_default();
+
+        if !status.success() {
+            let stderr = String::from_utf8_lossy(&stderr);
+            let stderr = if stderr.len() > STDERR_TAIL_BYTES {
+                let mut start = stderr.len() - STDERR_TAIL_BYTES;
+                while !stderr.is_char_boundary(start) {
+                    start += 1;
+                }
+                &stderr[start..]
+            } else {
+                &stderr[..]
+            };
+            return Err(format!("FFmpeg {} failed: {}", what, stderr));
+        }
+
+        Ok((status.code().unwrap_or(0), stderr))
+    }
+
+    /// Report the outcome, clean up after cancellation and unregister the job
+    pub fn finish<T>(self, result: Result<T, String>) -> Result<T, String> {
+        let (status, error) = match &result {
+            Ok(_) => ("completed", None),
+            Err(_) if self.is_cancelled() => {
+                for path in self.control.outputs.lock().unwrap().iter() {
+                    let _ = std::fs::remove_file(path);
+                }
+                ("cancelled", None)
+            }
+            Err(e) => ("failed", Some(e.clone())),
+        };
+
+        let _ = self.app.emit(
+            JOB_FINISHED_EVENT,
+            JobFinished {
+                job_id: self.id.clone(),
+                status,
+                error,
+            },
+        );
+
+        result
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        self.registry.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Cancel a running job: kills its FFmpeg process and deletes partial output
+#[tauri::command]
+pub fn cancel_job(jobs: tauri::State<'_, JobManager>, job_id: String) -> Result<(), String> {
+    if jobs.cancel(&job_id) {
+        Ok(())
+    } else {
+        Err(format!("No running job with id {}", job_id))
+    }
+}
+
+/// List IDs of jobs that are currently running
+#[tauri::command]
+pub fn list_jobs(jobs: tauri::State<'_, JobManager>) -> Vec<String> {
+    jobs.job_ids()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_block(parser: &mut ProgressParser, block: &str) -> Option<JobProgress> {
+        block.lines().filter_map(|line| parser.feed_line(line)).last()
+    }
+
+    #[test]
+    fn reads_out_time_in_microseconds_from_either_key() {
+        let mut parser = ProgressParser::new("job", Some(10.0));
+        let progress = feed_block(&mut parser, "out_time_us=2500000\nprogress=continue").unwrap();
+        assert_eq!(progress.out_time_sec, 2.5);
+        assert_eq!(progress.percent, Some(25.0));
+
+        // Despite its name, out_time_ms is in microseconds too
+        let progress = feed_block(&mut parser, "out_time_ms=5000000\nprogress=continue").unwrap();
+        assert_eq!(progress.out_time_sec, 5.0);
+        assert_eq!(progress.percent, Some(50.0));
+    }
+
+    #[test]
+    fn estimates_eta_from_speed() {
+        let mut parser = ProgressParser::new("job", Some(10.0));
+        let block = "fps=48.5\nout_time_us=4000000\nspeed=2.0x\nprogress=continue";
+        let progress = feed_block(&mut parser, block).unwrap();
+
+        assert_eq!(progress.job_id, "job");
+        assert_eq!(progress.fps, Some(48.5));
+        assert_eq!(progress.speed, Some(2.0));
+        assert_eq!(progress.eta_sec, Some(3.0));
+        assert!(!progress.done);
+    }
+
+    #[test]
+    fn ignores_not_available_values() {
+        let mut parser = ProgressParser::new("job", Some(10.0));
+        feed_block(&mut parser, "out_time_us=1000000\nspeed=1x\nprogress=continue").unwrap();
+
+        let block = "fps=N/A\nout_time_us=N/A\nout_time_ms=N/A\nspeed=N/A\nprogress=continue";
+        let progress = feed_block(&mut parser, block).unwrap();
+        // The last known position is kept; unknown rates clear the ETA
+        assert_eq!(progress.out_time_sec, 1.0);
+        assert_eq!(progress.percent, Some(10.0));
+        assert_eq!(progress.fps, None);
+        assert_eq!(progress.speed, None);
+        assert_eq!(progress.eta_sec, None);
+    }
+
+    #[test]
+    fn clamps_negative_and_overlong_positions() {
+        let mut parser = ProgressParser::new("job", Some(10.0));
+        let progress = feed_block(&mut parser, "out_time_us=-23220\nprogress=continue").unwrap();
+        assert_eq!(progress.out_time_sec, 0.0);
+
+        let progress = feed_block(&mut parser, "out_time_us=12000000\nprogress=continue").unwrap();
+        assert_eq!(progress.percent, Some(100.0));
+    }
+
+    #[test]
+    fn progress_end_completes_the_job() {
+        let mut parser = ProgressParser::new("job", Some(10.0));
+        let progress = feed_block(&mut parser, "out_time_us=9900000\nspeed=1.5x\nprogress=end").unwrap();
+
+        assert!(progress.done);
+        assert_eq!(progress.percent, Some(100.0));
+        assert_eq!(progress.eta_sec, Some(0.0));
+    }
+
+    #[test]
+    fn reports_no_estimates_without_a_total_duration() {
+        for total in [None, Some(0.0)] {
+            let mut parser = ProgressParser::new("job", total);
+            let progress = feed_block(&mut parser, "out_time_us=3000000\nspeed=2x\nprogress=continue").unwrap();
+            assert_eq!(progress.out_time_sec, 3.0);
+            assert_eq!(progress.percent, None);
+            assert_eq!(progress.eta_sec, None);
+
+            let progress = feed_block(&mut parser, "progress=end").unwrap();
+            assert!(progress.done);
+            assert_eq!(progress.percent, None);
+        }
+    }
+
+    #[test]
+    fn only_progress_lines_complete_a_block() {
+        let mut parser = ProgressParser::new("job", Some(10.0));
+        assert!(parser.feed_line("out_time_us=1000000").is_none());
+        assert!(parser.feed_line("bitrate=1024.0kbits/s").is_none());
+        assert!(parser.feed_line("not a key value line").is_none());
+        assert!(parser.feed_line("progress=continue").is_some());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::render::num;
+use crate::timeline::ClipKeyframe;
+
+// Upper bound for volume keyframes (+12 dB)
+const MAX_VOLUME_GAIN: f64 = 4.0;
+
+/// How a keyframe's value moves toward the next keyframe
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    Hold,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Interpolation {
+    /// Parse the frontend interpolation string; unknown modes fall back to linear
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "hold" | "step" | "constant" => Interpolation::Hold,
+            "ease-in" | "easein" => Interpolation::EaseIn,
+            "ease-out" | "easeout" => Interpolation::EaseOut,
+            "ease-in-out" | "easeinout" | "ease" => Interpolation::EaseInOut,
+            _ => Interpolation::Linear,
+        }
+    }
+
+    /// Easing applied to the normalized progress expression `p` (0..1)
+    fn ease(&self, p: &str) -> String {
+        match self {
+            Interpolation::Linear | Interpolation::Hold => p.to_string(),
+            Interpolation::EaseIn => format!("pow({p},2)"),
+            Interpolation::EaseOut => format!("(1-pow(1-{p},2))"),
+            Interpolation::EaseInOut => format!("(pow({p},2)*(3-2*{p}))"),
+        }
+    }
+}
+
+/// Expression for the segment between keyframes `a` and `b`
+fn segment_expression(a: &ClipKeyframe, b: &ClipKeyframe, time_var: &str) -> String {
+    let interpolation = Interpolation::parse(&a.interpolation);
+    let span = b.time_sec - a.time_sec;
+    if interpolation == Interpolation::Hold || span <= 0.0 || a.value == b.value {
+        return num(a.value);
+    }
+
+    let progress = format!("(({}-{})/{})", time_var, num(a.time_sec), num(span));
+    format!(
+        "({}+{}*{})",
+        num(a.value),
+        num(b.value - a.value),
+        interpolation.ease(&progress)
+    )
+}
+
+/// Build an FFmpeg expression evaluating a keyframe curve at `time_var`.
+///
+/// Times are relative to the clip start. Before the first keyframe the curve
+/// holds its first value and after the last keyframe it holds the last one.
+/// Returns None when there are no usable keyframes.
+pub fn curve_expression(keyframes: &[ClipKeyframe], time_var: &str) -> Option<String> {
+    let mut points: Vec<&ClipKeyframe> = keyframes
+        .iter()
+        .filter(|k| k.time_sec.is_finite() && k.value.is_finite())
+        .collect();
+    points.sort_by(|a, b| a.time_sec.total_cmp(&b.time_sec));
+
+    let first = *points.first()?;
+    let last = *points.last()?;
+
+    let mut expr = num(last.value);
+    for pair in points.windows(2).rev() {
+        let (a, b) = (pair[0], pair[1]);
+        expr = format!(
+            "if(lt({},{}),{},{})",
+            time_var,
+            num(b.time_sec),
+            segment_expression(a, b, time_var),
+            expr
+        );
+    }
+
+    Some(format!(
+        "if(lt({},{}),{},{})",
+        time_var,
+        num(first.time_sec),
+        num(first.value),
+        expr
+    ))
+}
+
+/// Clamp keyframe values into `min..=max`
+pub fn clamped(keyframes: &[ClipKeyframe], min: f64, max: f64) -> Vec<ClipKeyframe> {
+    keyframes
+        .iter()
+        .cloned()
+        .map(|mut k| {
+            k.value = k.value.clamp(min, max);
+            k
+        })
+        .collect()
+}
+
+/// Video filter that fades a clip toward black following its opacity curve.
+/// Used on the main track, which has nothing underneath it.
+pub fn opacity_to_black_filter(keyframes: &[ClipKeyframe]) -> Option<String> {
+    let expr = curve_expression(&clamped(keyframes, 0.0, 1.0), "T")?;
+    // Limited-range YUV: black is luma 16 and neutral chroma 128
+    Some(format!(
+        "geq=lum='16+(p(X,Y)-16)*({e})':cb='128+(p(X,Y)-128)*({e})'",
+        e = expr
+    ))
+}
+
+/// Video filter that scales a clip's alpha channel by its opacity curve.
+/// Expects a pixel format with alpha (e.g. yuva420p).
+pub fn opacity_alpha_filter(keyframes: &[ClipKeyframe]) -> Option<String> {
+    let expr = curve_expression(&clamped(keyframes, 0.0, 1.0), "T")?;
+    Some(format!(
+        "geq=lum='p(X,Y)':cb='p(X,Y)':a='p(X,Y)*({})'",
+        expr
+    ))
+}
+
+/// Audio filter applying a clip's volume curve as gain
+pub fn volume_filter(keyframes: &[ClipKeyframe]) -> Option<String> {
+    let expr = curve_expression(&clamped(keyframes, 0.0, MAX_VOLUME_GAIN), "t")?;
+    Some(format!("volume='{}':eval=frame", expr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kf(time_sec: f64, value: f64, interpolation: &str) -> ClipKeyframe {
+        ClipKeyframe {
+            id: String::new(),
+            time_sec,
+            property: String::new(),
+            value,
+            interpolation: interpolation.to_string(),
+        }
+    }
+
+    /// Curve from 0 at 0s to 1 at 2s with the given interpolation
+    fn ramp(interpolation: &str) -> String {
+        curve_expression(&[kf(0.0, 0.0, interpolation), kf(2.0, 1.0, "linear")], "t").unwrap()
+    }
+
+    #[test]
+    fn parses_interpolation_names() {
+        assert_eq!(Interpolation::parse("linear"), Interpolation::Linear);
+        assert_eq!(Interpolation::parse("Step"), Interpolation::Hold);
+        assert_eq!(Interpolation::parse("ease_in"), Interpolation::EaseIn);
+        assert_eq!(Interpolation::parse("easeOut"), Interpolation::EaseOut);
+        assert_eq!(Interpolation::parse(" ease-in-out "), Interpolation::EaseInOut);
+        assert_eq!(Interpolation::parse("bezier"), Interpolation::Linear);
+        assert_eq!(Interpolation::parse(""), Interpolation::Linear);
+    }
+
+    #[test]
+    fn no_keyframes_give_no_curve_or_filter() {
+        assert_eq!(curve_expression(&[], "t"), None);
+        assert_eq!(opacity_to_black_filter(&[]), None);
+        assert_eq!(opacity_alpha_filter(&[]), None);
+        assert_eq!(volume_filter(&[]), None);
+    }
+
+    #[test]
+    fn unusable_keyframes_are_ignored() {
+        let keyframes = [kf(f64::NAN, 1.0, "linear"), kf(1.0, f64::INFINITY, "linear")];
+        assert_eq!(curve_expression(&keyframes, "t"), None);
+    }
+
+    #[test]
+    fn one_keyframe_holds_its_value() {
+        let expr = curve_expression(&[kf(1.0, 0.5, "ease-in")], "t").unwrap();
+        assert_eq!(expr, "if(lt(t,1),0.5,0.5)");
+    }
+
+    #[test]
+    fn each_interpolation_mode_shapes_the_segment() {
+        assert_eq!(ramp("linear"), "if(lt(t,0),0,if(lt(t,2),(0+1*((t-0)/2)),1))");
+        assert_eq!(ramp("hold"), "if(lt(t,0),0,if(lt(t,2),0,1))");
+        assert_eq!(ramp("ease-in"), "if(lt(t,0),0,if(lt(t,2),(0+1*pow(((t-0)/2),2)),1))");
+        assert_eq!(ramp("ease-out"), "if(lt(t,0),0,if(lt(t,2),(0+1*(1-pow(1-((t-0)/2),2))),1))");
+        assert_eq!(
+            ramp("ease-in-out"),
+            "if(lt(t,0),0,if(lt(t,2),(0+1*(pow(((t-0)/2),2)*(3-2*((t-0)/2)))),1))"
+        );
+    }
+
+    #[test]
+    fn many_keyframes_are_sorted_and_nested() {
+        let keyframes = [
+            kf(3.0, 0.25, "linear"),
+            kf(1.0, 1.0, "hold"),
+            kf(0.5, 0.0, "linear"),
+        ];
+        let expr = curve_expression(&keyframes, "T").unwrap();
+        assert_eq!(
+            expr,
+            "if(lt(T,0.5),0,if(lt(T,1),(0+1*((T-0.5)/0.5)),if(lt(T,3),1,0.25)))"
+        );
+    }
+
+    #[test]
+    fn flat_and_coincident_segments_are_constant() {
+        let flat = curve_expression(&[kf(0.0, 0.7, "ease-in"), kf(1.0, 0.7, "linear")], "t").unwrap();
+        assert_eq!(flat, "if(lt(t,0),0.7,if(lt(t,1),0.7,0.7))");
+
+        // Two keyframes at the same time jump straight to the later value
+        let jump = curve_expression(&[kf(1.0, 0.0, "linear"), kf(1.0, 1.0, "linear")], "t").unwrap();
+        assert_eq!(jump, "if(lt(t,1),0,if(lt(t,1),0,1))");
+    }
+
+    #[test]
+    fn opacity_to_black_mixes_toward_limited_range_black() {
+        let filter = opacity_to_black_filter(&[kf(0.0, 1.5, "linear")]).unwrap();
+        // Values above 1 are clamped
+        assert_eq!(
+            filter,
+            "geq=lum='16+(p(X,Y)-16)*(if(lt(T,0),1,1))':cb='128+(p(X,Y)-128)*(if(lt(T,0),1,1))'"
+        );
+    }
+
+    #[test]
+    fn opacity_alpha_scales_the_alpha_plane() {
+        let filter = opacity_alpha_filter(&[kf(0.0, 0.0, "linear"), kf(1.0, -2.0, "linear")]).unwrap();
+        assert_eq!(filter, "geq=lum='p(X,Y)':cb='p(X,Y)':a='p(X,Y)*(if(lt(T,0),0,if(lt(T,1),0,0)))'");
+
+        let fade = opacity_alpha_filter(&[kf(0.0, 0.0, "linear"), kf(1.0, 1.0, "linear")]).unwrap();
+        assert!(fade.contains("a='p(X,Y)*(if(lt(T,0),0,if(lt(T,1),(0+1*((T-0)/1)),1)))'"), "{}", fade);
+    }
+
+    #[test]
+    fn volume_is_clamped_and_evaluated_per_frame() {
+        assert_eq!(
+            volume_filter(&[kf(0.0, 10.0, "linear")]).unwrap(),
+            "volume='if(lt(t,0),4,4)':eval=frame"
+        );
+        assert_eq!(
+            volume_filter(&[kf(0.0, 1.0, "ease-out"), kf(2.0, 0.0, "linear")]).unwrap(),
+            "volume='if(lt(t,0),1,if(lt(t,2),(1+-1*(1-pow(1-((t-0)/2),2))),0))':eval=frame"
+        );
+    }
+}
@@ -1,33 +1,99 @@
+mod archive;
+mod autosave;
+mod crossfade;
+mod encode;
 mod ffmpeg;
+mod ffmpeg_policy;
+mod fingerprint;
 mod fs;
+mod jobs;
+mod keyframes;
+mod media;
+mod migrations;
+mod project;
+mod relink;
+mod render;
+mod repair;
+mod sandbox;
+mod silence;
+mod thumbnails;
+mod timeline;
+mod transitions;
+mod trim;
+mod upload;
+mod waveform;
+
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(jobs::JobManager::default())
+        .manage(autosave::AutosaveManager::default())
+        .manage(sandbox::PathPolicy::default())
+        .manage(upload::UploadManager::default())
         .setup(|app| {
             // Initialize FFmpeg path on startup
             if let Err(e) = ffmpeg::init_ffmpeg_path(app.handle()) {
                 eprintln!("Warning: Failed to initialize FFmpeg path: {}", e);
             }
+
+            // Filesystem commands may always write to the app data directory
+            let app_data_dir = app.path().app_data_dir()?;
+            app.state::<sandbox::PathPolicy>().set_app_data_dir(&app_data_dir)?;
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // FFmpeg commands
             ffmpeg::run_ffmpeg,
             ffmpeg::ffprobe_json,
+            media::probe_media,
+            media::probe_keyframes_index,
             ffmpeg::trim_clip,
+            trim::plan_trim_cut,
             ffmpeg::split_clip,
+            ffmpeg::split_clip_multi,
             ffmpeg::transcode_to_mp4,
             ffmpeg::export_concat,
             ffmpeg::export_concat_filter,
             ffmpeg::export_with_crossfades,
             ffmpeg::apply_fade_effects,
             ffmpeg::export_concat_with_fades,
+            render::export_timeline,
+            repair::repair_recording,
+            thumbnails::generate_thumbnails,
+            waveform::compute_waveform,
+            silence::detect_silence,
+            silence::auto_trim_silence,
+            // Encode preset commands
+            encode::list_encode_presets,
+            encode::save_encode_preset,
+            encode::delete_encode_preset,
+            // Job commands
+            jobs::cancel_job,
+            jobs::list_jobs,
+            // Project commands
+            project::save_project,
+            project::load_project,
+            relink::check_project_media,
+            relink::relink_media,
+            archive::consolidate_project,
+            archive::import_project_archive,
+            // Autosave commands
+            autosave::start_autosave,
+            autosave::update_autosave,
+            autosave::stop_autosave,
+            autosave::list_recovery_points,
+            autosave::restore_recovery_point,
             // File system commands
             fs::write_concat_list,
             fs::save_blob,
+            upload::begin_blob_upload,
+            upload::append_blob_chunk,
+            upload::finish_blob_upload,
+            upload::abort_blob_upload,
             fs::open_file_dialog_multi,
             fs::open_dir_dialog,
             fs::create_project_dirs,
@@ -35,6 +101,14 @@ pub fn run() {
             fs::copy_file_to_media,
             fs::resolve_app_dirs,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // A clean exit flushes autosave and clears its crash marker
+            if let tauri::RunEvent::Exit = event {
+                if let Err(e) = app.state::<autosave::AutosaveManager>().shutdown() {
+                    eprintln!("Warning: Failed to stop autosave: {}", e);
+                }
+            }
+        });
 }
@@ -0,0 +1,578 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::ffmpeg::get_ffmpeg_path;
+
+/// Exact frame rate or time base as reported by FFprobe (e.g. 30000/1001)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rational {
+    pub num: i64,
+    pub den: i64,
+}
+
+impl Rational {
+    /// Parse "num/den" or a plain number; zero or invalid values yield None
+    pub fn parse(value: &str) -> Option<Self> {
+        let (num, den) = match value.split_once('/') {
+            Some((n, d)) => (n.trim().parse::<i64>().ok()?, d.trim().parse::<i64>().ok()?),
+            None => (value.trim().parse::<i64>().ok()?, 1),
+        };
+        if num == 0 || den == 0 {
+            return None;
+        }
+        Some(Self { num, den })
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Attachment,
+    Unknown,
+}
+
+/// One stream of a probed media file
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamInfo {
+    pub index: u32,
+    pub kind: StreamKind,
+    pub codec: Option<String>,
+    pub profile: Option<String>,
+    pub duration: Option<f64>,
+    pub bit_rate: Option<u64>,
+    pub timecode: Option<String>,
+    // Video
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub pixel_format: Option<String>,
+    pub frame_rate: Option<Rational>,
+    pub avg_frame_rate: Option<Rational>,
+    pub time_base: Option<Rational>,
+    pub rotation: i32,
+    /// Frame timing is irregular (e.g. browser screen recordings)
+    pub variable_frame_rate: bool,
+    // Audio
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+}
+
+// Real-time frame rates above this are container time bases, not frame rates
+const MAX_PLAUSIBLE_FRAME_RATE: f64 = 240.0;
+
+// Relative difference between nominal and average frame rate tolerated as constant
+const VFR_TOLERANCE: f64 = 0.01;
+
+/// Guess whether a video stream is variable frame rate from its nominal
+/// (`r_frame_rate`) and average frame rates. Only positive evidence counts:
+/// a rate that is really a time base (e.g. MediaRecorder WebM) or rates that
+/// disagree. Missing rates are treated as constant.
+fn looks_variable_frame_rate(nominal: Option<Rational>, average: Option<Rational>) -> bool {
+    let nominal = nominal.map(|r| r.as_f64());
+    let average = average.map(|r| r.as_f64());
+    if [nominal, average].into_iter().flatten().any(|rate| rate > MAX_PLAUSIBLE_FRAME_RATE) {
+        return true;
+    }
+    match (nominal, average) {
+        (Some(nominal), Some(average)) => (nominal - average).abs() / average > VFR_TOLERANCE,
+        _ => false,
+    }
+}
+
+impl StreamInfo {
+    /// Display size after applying rotation metadata
+    pub fn display_size(&self) -> Option<(u32, u32)> {
+        let (w, h) = (self.width?, self.height?);
+        if self.rotation.rem_euclid(180) == 90 {
+            Some((h, w))
+        } else {
+            Some((w, h))
+        }
+    }
+}
+
+/// Typed result of probing a media file with FFprobe
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfo {
+    pub path: String,
+    pub container: Option<String>,
+    pub duration: Option<f64>,
+    pub start_time: Option<f64>,
+    pub size: Option<u64>,
+    pub bit_rate: Option<u64>,
+    pub timecode: Option<String>,
+    pub streams: Vec<StreamInfo>,
+}
+
+impl MediaInfo {
+    /// First video stream, ignoring attached cover art
+    pub fn video_stream(&self) -> Option<&StreamInfo> {
+        self.streams.iter().find(|s| s.kind == StreamKind::Video)
+    }
+
+    /// First audio stream
+    pub fn audio_stream(&self) -> Option<&StreamInfo> {
+        self.streams.iter().find(|s| s.kind == StreamKind::Audio)
+    }
+
+    pub fn has_video(&self) -> bool {
+        self.video_stream().is_some()
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.audio_stream().is_some()
+    }
+
+    pub fn is_variable_frame_rate(&self) -> bool {
+        self.video_stream().is_some_and(|v| v.variable_frame_rate)
+    }
+
+    /// Duration in seconds, or an error if neither the container nor any stream reports one
+    pub fn duration_seconds(&self) -> Result<f64, String> {
+        self.duration
+            .ok_or_else(|| "Could not determine media duration".to_string())
+    }
+}
+
+// Raw FFprobe JSON shapes. Numeric fields are accepted as numbers or strings.
+
+#[derive(Deserialize, Default)]
+struct RawProbe {
+    #[serde(default)]
+    format: Option<RawFormat>,
+    #[serde(default)]
+    streams: Vec<RawStream>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawFormat {
+    format_name: Option<String>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    duration: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    start_time: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    size: Option<u64>,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    bit_rate: Option<u64>,
+    #[serde(default)]
+    tags: HashMap<String, Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawStream {
+    #[serde(default)]
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    profile: Option<String>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    duration: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    bit_rate: Option<u64>,
+    width: Option<u32>,
+    height: Option<u32>,
+    pix_fmt: Option<String>,
+    r_frame_rate: Option<String>,
+    avg_frame_rate: Option<String>,
+    time_base: Option<String>,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    sample_rate: Option<u64>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    #[serde(default)]
+    disposition: HashMap<String, Value>,
+    #[serde(default)]
+    tags: HashMap<String, Value>,
+    #[serde(default)]
+    side_data_list: Vec<HashMap<String, Value>>,
+}
+
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+fn lenient_f64<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    let value = Option::<Value>::deserialize(d)?;
+    Ok(value.as_ref().and_then(value_as_f64).filter(|v| v.is_finite()))
+}
+
+fn lenient_u64<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+    let value = Option::<Value>::deserialize(d)?;
+    Ok(value
+        .as_ref()
+        .and_then(value_as_f64)
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .map(|v| v as u64))
+}
+
+fn tag_string(tags: &HashMap<String, Value>, key: &str) -> Option<String> {
+    tags.get(key).and_then(|v| match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+impl RawStream {
+    fn rotation(&self) -> i32 {
+        // Newer FFprobe reports rotation in the display matrix side data
+        let from_side_data = self
+            .side_data_list
+            .iter()
+            .find_map(|sd| sd.get("rotation").and_then(value_as_f64));
+        let from_tags = self.tags.get("rotate").and_then(value_as_f64);
+
+        // Display matrix rotation is counter-clockwise; normalize to clockwise degrees
+        let degrees = match (from_side_data, from_tags) {
+            (Some(r), _) => -r,
+            (None, Some(r)) => r,
+            (None, None) => 0.0,
+        };
+        (degrees.round() as i32).rem_euclid(360)
+    }
+
+    fn into_stream_info(self) -> StreamInfo {
+        let is_cover_art = self
+            .disposition
+            .get("attached_pic")
+            .and_then(value_as_f64)
+            .is_some_and(|v| v != 0.0);
+
+        let kind = match self.codec_type.as_deref() {
+            Some("video") if !is_cover_art => StreamKind::Video,
+            Some("video") => StreamKind::Attachment,
+            Some("audio") => StreamKind::Audio,
+            Some("subtitle") => StreamKind::Subtitle,
+            Some("data") => StreamKind::Data,
+            Some("attachment") => StreamKind::Attachment,
+            _ => StreamKind::Unknown,
+        };
+
+        let frame_rate = self.r_frame_rate.as_deref().and_then(Rational::parse);
+        let avg_frame_rate = self.avg_frame_rate.as_deref().and_then(Rational::parse);
+        let variable_frame_rate = kind == StreamKind::Video
+            && looks_variable_frame_rate(frame_rate, avg_frame_rate);
+
+        StreamInfo {
+            index: self.index,
+            kind,
+            rotation: self.rotation(),
+            variable_frame_rate,
+            codec: self.codec_name,
+            profile: self.profile,
+            duration: self.duration,
+            bit_rate: self.bit_rate,
+            timecode: tag_string(&self.tags, "timecode"),
+            width: self.width.filter(|w| *w > 0),
+            height: self.height.filter(|h| *h > 0),
+            pixel_format: self.pix_fmt,
+            frame_rate,
+            avg_frame_rate,
+            time_base: self.time_base.as_deref().and_then(Rational::parse),
+            sample_rate: self.sample_rate.map(|r| r as u32),
+            channels: self.channels,
+            channel_layout: self.channel_layout,
+        }
+    }
+}
+
+/// Parse FFprobe `-show_format -show_streams` JSON output into a MediaInfo
+pub fn parse_ffprobe_output(path: &str, json: &str) -> Result<MediaInfo, String> {
+    let raw: RawProbe = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse FFprobe JSON: {}", e))?;
+    let format = raw.format.unwrap_or_default();
+
+    let streams: Vec<StreamInfo> = raw
+        .streams
+        .into_iter()
+        .map(RawStream::into_stream_info)
+        .collect();
+
+    // Fall back to the longest stream when the container has no duration (e.g. live WebM)
+    let duration = format.duration.filter(|d| *d > 0.0).or_else(|| {
+        streams
+            .iter()
+            .filter_map(|s| s.duration)
+            .filter(|d| *d > 0.0)
+            .reduce(f64::max)
+    });
+
+    let timecode = tag_string(&format.tags, "timecode")
+        .or_else(|| streams.iter().find_map(|s| s.timecode.clone()));
+
+    Ok(MediaInfo {
+        path: path.to_string(),
+        container: format.format_name,
+        duration,
+        start_time: format.start_time,
+        size: format.size,
+        bit_rate: format.bit_rate,
+        timecode,
+        streams,
+    })
+}
+
+/// Get the FFprobe executable path (next to FFmpeg)
+pub fn get_ffprobe_path() -> Result<PathBuf, String> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    Ok(ffmpeg_path.with_file_name(
+        if cfg!(target_os = "windows") { "ffprobe.exe" } else { "ffprobe" }
+    ))
+}
+
+/// Run FFprobe and return its raw JSON output
+pub fn run_ffprobe(path: &str) -> Result<String, String> {
+    let ffprobe_path = get_ffprobe_path()?;
+
+    let output = Command::new(&ffprobe_path)
+        .args([
+            "-v", "quiet",
+            "-print_format", "json",
+            "-show_format",
+            "-show_streams",
+            path,
+        ])
+        .output()
+        .map_err(|e| format!("Failed to execute FFprobe: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("FFprobe failed: {}", stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Probe a media file into a typed MediaInfo
+pub fn probe_media_info(path: &str) -> Result<MediaInfo, String> {
+    // Report offline media clearly instead of passing on FFprobe's error
+    if !std::path::Path::new(path).exists() {
+        return Err(format!("Media file not found: {}", path));
+    }
+    let json = run_ffprobe(path)?;
+    parse_ffprobe_output(path, &json)
+}
+
+/// Presentation times (seconds) of every keyframe in the first video stream.
+///
+/// Reads packet flags only, so nothing is decoded.
+pub fn probe_keyframes(path: &str) -> Result<Vec<f64>, String> {
+    let ffprobe_path = get_ffprobe_path()?;
+
+    let output = Command::new(&ffprobe_path)
+        .args([
+            "-v", "error",
+            "-select_streams", "v:0",
+            "-show_entries", "packet=pts_time,flags",
+            "-of", "csv=print_section=0",
+            path,
+        ])
+        .output()
+        .map_err(|e| format!("Failed to execute FFprobe: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("FFprobe keyframe scan failed: {}", stderr));
+    }
+
+    Ok(parse_keyframe_packets(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `pts_time,flags` CSV lines, keeping packets flagged as keyframes
+pub fn parse_keyframe_packets(csv: &str) -> Vec<f64> {
+    let mut keyframes: Vec<f64> = csv
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().split(',');
+            let pts = fields.next()?.parse::<f64>().ok()?;
+            let flags = fields.next()?;
+            (flags.starts_with('K') && pts.is_finite()).then_some(pts)
+        })
+        .collect();
+    keyframes.sort_by(f64::total_cmp);
+    keyframes.dedup();
+    keyframes
+}
+
+/// List keyframe timestamps (seconds) of a media file's video stream
+#[tauri::command]
+pub async fn probe_keyframes_index(path: String) -> Result<Vec<f64>, String> {
+    tauri::async_runtime::spawn_blocking(move || probe_keyframes(&path))
+        .await
+        .map_err(|e| format!("Failed to probe keyframes: {}", e))?
+}
+
+/// Probe a media file and return typed metadata
+#[tauri::command]
+pub async fn probe_media(path: String) -> Result<MediaInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || probe_media_info(&path))
+        .await
+        .map_err(|e| format!("Failed to probe media: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_string_and_numeric_fields_alike() {
+        let as_strings = r#"{
+            "format": { "format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "12.500000",
+                        "start_time": "0.000000", "size": "1048576", "bit_rate": "671088" },
+            "streams": [
+                { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080,
+                  "r_frame_rate": "30/1", "avg_frame_rate": "30/1", "time_base": "1/15360",
+                  "duration": "12.500000", "bit_rate": "600000" },
+                { "index": 1, "codec_type": "audio", "codec_name": "aac", "sample_rate": "48000",
+                  "channels": 2, "channel_layout": "stereo" }
+            ]
+        }"#;
+        let as_numbers = r#"{
+            "format": { "format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": 12.5,
+                        "start_time": 0, "size": 1048576, "bit_rate": 671088 },
+            "streams": [
+                { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080,
+                  "r_frame_rate": "30/1", "avg_frame_rate": "30/1", "time_base": "1/15360",
+                  "duration": 12.5, "bit_rate": 600000 },
+                { "index": 1, "codec_type": "audio", "codec_name": "aac", "sample_rate": 48000,
+                  "channels": 2, "channel_layout": "stereo" }
+            ]
+        }"#;
+
+        for json in [as_strings, as_numbers] {
+            let info = parse_ffprobe_output("/media/clip.mp4", json).unwrap();
+            assert_eq!(info.path, "/media/clip.mp4");
+            assert_eq!(info.duration, Some(12.5));
+            assert_eq!(info.start_time, Some(0.0));
+            assert_eq!(info.size, Some(1048576));
+            assert_eq!(info.bit_rate, Some(671088));
+
+            let video = info.video_stream().unwrap();
+            assert_eq!(video.codec.as_deref(), Some("h264"));
+            assert_eq!(video.bit_rate, Some(600000));
+            assert_eq!(video.frame_rate, Some(Rational { num: 30, den: 1 }));
+            assert_eq!(video.display_size(), Some((1920, 1080)));
+            assert!(!video.variable_frame_rate);
+
+            let audio = info.audio_stream().unwrap();
+            assert_eq!(audio.sample_rate, Some(48000));
+            assert_eq!(audio.channels, Some(2));
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_longest_stream_duration() {
+        // MediaRecorder WebM: no container duration, N/A values as strings
+        let json = r#"{
+            "format": { "format_name": "matroska,webm", "duration": "N/A", "bit_rate": "N/A" },
+            "streams": [
+                { "index": 0, "codec_type": "video", "codec_name": "vp8", "duration": "9.96" },
+                { "index": 1, "codec_type": "audio", "codec_name": "opus", "duration": "10.02" }
+            ]
+        }"#;
+        let info = parse_ffprobe_output("/media/recording.webm", json).unwrap();
+        assert_eq!(info.duration, Some(10.02));
+        assert_eq!(info.bit_rate, None);
+    }
+
+    #[test]
+    fn reports_a_missing_duration() {
+        let json = r#"{
+            "format": { "format_name": "matroska,webm", "duration": "0.000000" },
+            "streams": [{ "index": 0, "codec_type": "video", "codec_name": "vp8" }]
+        }"#;
+        let info = parse_ffprobe_output("/media/live.webm", json).unwrap();
+        assert_eq!(info.duration, None);
+        assert!(info.duration_seconds().is_err());
+
+        // Output without a format section at all still parses
+        let info = parse_ffprobe_output("/media/bare.ts", r#"{ "streams": [] }"#).unwrap();
+        assert_eq!(info.container, None);
+        assert_eq!(info.duration, None);
+    }
+
+    #[test]
+    fn handles_audio_only_input_with_cover_art() {
+        let json = r#"{
+            "format": { "format_name": "mp3", "duration": "180.0", "tags": { "title": "Voiceover" } },
+            "streams": [
+                { "index": 0, "codec_type": "audio", "codec_name": "mp3", "sample_rate": "44100", "channels": 1 },
+                { "index": 1, "codec_type": "video", "codec_name": "mjpeg", "width": 600, "height": 600,
+                  "disposition": { "attached_pic": 1 } }
+            ]
+        }"#;
+        let info = parse_ffprobe_output("/media/voiceover.mp3", json).unwrap();
+        assert!(info.has_audio());
+        assert!(!info.has_video());
+        assert!(!info.is_variable_frame_rate());
+        assert_eq!(info.streams[1].kind, StreamKind::Attachment);
+        assert_eq!(info.duration_seconds().unwrap(), 180.0);
+    }
+
+    #[test]
+    fn reads_rotation_from_side_data_or_tags() {
+        let json = r#"{
+            "streams": [
+                { "index": 0, "codec_type": "video", "width": 1920, "height": 1080,
+                  "side_data_list": [{ "side_data_type": "Display Matrix", "rotation": -90 }] },
+                { "index": 1, "codec_type": "video", "width": 1920, "height": 1080,
+                  "tags": { "rotate": "270" } }
+            ]
+        }"#;
+        let info = parse_ffprobe_output("/media/phone.mov", json).unwrap();
+        assert_eq!(info.streams[0].rotation, 90);
+        assert_eq!(info.streams[0].display_size(), Some((1080, 1920)));
+        assert_eq!(info.streams[1].rotation, 270);
+    }
+
+    #[test]
+    fn rejects_output_that_is_not_json() {
+        assert!(parse_ffprobe_output("/media/clip.mp4", "").is_err());
+        assert!(parse_ffprobe_output("/media/clip.mp4", "Invalid data found").is_err());
+    }
+
+    fn rate(value: &str) -> Option<Rational> {
+        Rational::parse(value)
+    }
+
+    #[test]
+    fn matching_rates_are_constant() {
+        assert!(!looks_variable_frame_rate(rate("30000/1001"), rate("30000/1001")));
+        // 0.2% apart, well within the tolerance
+        assert!(!looks_variable_frame_rate(rate("25/1"), rate("2495/100")));
+    }
+
+    #[test]
+    fn missing_rates_are_treated_as_constant() {
+        assert!(!looks_variable_frame_rate(None, None));
+        assert!(!looks_variable_frame_rate(rate("30/1"), None));
+        assert!(!looks_variable_frame_rate(None, rate("0/0")));
+    }
+
+    #[test]
+    fn time_base_or_disagreeing_rates_are_variable() {
+        assert!(looks_variable_frame_rate(rate("1000/1"), None));
+        assert!(looks_variable_frame_rate(rate("1000/1"), rate("30/1")));
+        assert!(looks_variable_frame_rate(rate("60/1"), rate("43/1")));
+        // 2% apart, clearly outside the tolerance
+        assert!(looks_variable_frame_rate(rate("25/1"), rate("245/10")));
+    }
+}
@@ -0,0 +1,214 @@
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::project::PROJECT_SCHEMA_VERSION;
+
+/// One upgrade step from schema `from` to `from + 1`
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut Map<String, Value>) -> Result<(), String>,
+}
+
+// Every step in order; each must leave the document valid for the next
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "Convert unversioned frontend project state to schema 1",
+    apply: migrate_v0_to_v1,
+}];
+
+/// Which migrations were applied while loading a project
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub applied: Vec<String>,
+    /// Copy of the file as it was before migrating
+    pub backup_path: Option<String>,
+}
+
+/// Schema version of a raw document; unversioned documents are version 0
+pub fn document_version(doc: &Value) -> Result<u32, String> {
+    match doc.get("version") {
+        None | Some(Value::Null) => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("Invalid project schema version: {}", value)),
+    }
+}
+
+/// Upgrade a raw project document to the current schema, one step at a time
+pub fn migrate_document(mut doc: Value) -> Result<(Value, MigrationReport), String> {
+    let from_version = document_version(&doc)?;
+    if from_version > PROJECT_SCHEMA_VERSION {
+        return Err(format!(
+            "Project was saved by a newer version of TrimBot (schema {}, supported {})",
+            from_version, PROJECT_SCHEMA_VERSION
+        ));
+    }
+
+    let object = doc
+        .as_object_mut()
+        .ok_or("Project file is not a JSON object")?;
+
+    let mut applied = Vec::new();
+    let mut version = from_version;
+    while version < PROJECT_SCHEMA_VERSION {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| format!("No migration from project schema {}", version))?;
+        (migration.apply)(object)
+            .map_err(|e| format!("Migration from schema {} failed: {}", version, e))?;
+        version += 1;
+        object.insert("version".to_string(), json!(version));
+        applied.push(migration.description.to_string());
+    }
+
+    let report = MigrationReport {
+        from_version,
+        to_version: version,
+        applied,
+        backup_path: None,
+    };
+    Ok((doc, report))
+}
+
+/// Schema 0 is the frontend's in-memory state (`ProjectState`, optionally with
+/// the `Project` metadata inline or under `project`). Schema 1 keeps the
+/// metadata at the top level, names the main track `mainTrack`, keeps overlay
+/// clips in `overlayTrack` and drops machine-specific directories.
+fn migrate_v0_to_v1(doc: &mut Map<String, Value>) -> Result<(), String> {
+    if let Some(Value::Object(project)) = doc.remove("project") {
+        for (key, value) in project {
+            doc.entry(key).or_insert(value);
+        }
+    }
+
+    if !doc.contains_key("mainTrack") {
+        let timeline = doc.remove("timeline").unwrap_or_else(|| json!([]));
+        doc.insert("mainTrack".to_string(), timeline);
+    }
+    if !doc.get("overlayTrack").is_some_and(Value::is_array) {
+        doc.insert("overlayTrack".to_string(), json!([]));
+    }
+
+    // Older builds kept overlay clips in the main list, tagged by track type
+    if let Some(Value::Array(main)) = doc.get_mut("mainTrack") {
+        let (overlay, kept): (Vec<Value>, Vec<Value>) = main
+            .drain(..)
+            .partition(|item| item.get("trackType").and_then(Value::as_str) == Some("overlay"));
+        *main = kept;
+        if let Some(Value::Array(overlay_track)) = doc.get_mut("overlayTrack") {
+            overlay_track.extend(overlay);
+        }
+    }
+
+    let fallback_name = doc
+        .get("projectDir")
+        .and_then(Value::as_str)
+        .and_then(|dir| dir.rsplit(['/', '\\']).find(|part| !part.is_empty()))
+        .unwrap_or("Untitled Project")
+        .to_string();
+    for key in ["projectDir", "mediaDir", "exportDir"] {
+        doc.remove(key);
+    }
+
+    let now = Utc::now().to_rfc3339();
+    doc.entry("id").or_insert_with(|| json!(uuid::Uuid::new_v4().to_string()));
+    doc.entry("name").or_insert_with(|| json!(fallback_name));
+    doc.entry("createdAt").or_insert_with(|| json!(now));
+    doc.entry("updatedAt").or_insert_with(|| json!(now));
+    doc.entry("settings").or_insert_with(|| {
+        json!({
+            "resolution": { "width": 1920, "height": 1080 },
+            "frameRate": 30,
+            "outputFormat": "mp4"
+        })
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ProjectFile;
+
+    #[test]
+    fn reads_document_versions() {
+        assert_eq!(document_version(&json!({})), Ok(0));
+        assert_eq!(document_version(&json!({ "version": null })), Ok(0));
+        assert_eq!(document_version(&json!({ "version": 1 })), Ok(1));
+        assert_eq!(document_version(&json!({ "version": u32::MAX })), Ok(u32::MAX));
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        for version in [json!("1"), json!(-1), json!(1.5), json!(u64::from(u32::MAX) + 1), json!(u64::MAX)] {
+            let doc = json!({ "version": version });
+            assert!(document_version(&doc).is_err(), "{}", version);
+        }
+    }
+
+    #[test]
+    fn migrates_unversioned_state_to_v1() {
+        let doc = json!({
+            "project": { "name": "Interview", "projectDir": "/home/me/Videos/Interview" },
+            "timeline": [
+                { "id": "a", "path": "/media/a.mp4", "durationSec": 2.0 },
+                { "id": "o", "path": "/media/logo.png", "durationSec": 1.0, "trackType": "overlay" },
+            ],
+            "mediaDir": "/home/me/Videos/Interview/media",
+        });
+        let (doc, report) = migrate_document(doc).unwrap();
+
+        assert_eq!((report.from_version, report.to_version), (0, PROJECT_SCHEMA_VERSION));
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(doc["version"], json!(1));
+        assert_eq!(doc["name"], json!("Interview"));
+        for key in ["project", "timeline", "projectDir", "mediaDir", "exportDir"] {
+            assert!(doc.get(key).is_none(), "{} was kept", key);
+        }
+        assert_eq!(doc["mainTrack"].as_array().unwrap().len(), 1);
+        assert_eq!(doc["overlayTrack"][0]["id"], json!("o"));
+        assert_eq!(doc["settings"]["frameRate"], json!(30));
+
+        let project: ProjectFile = serde_json::from_value(doc).unwrap();
+        assert_eq!(project.main_track[0].id, "a");
+    }
+
+    #[test]
+    fn names_v0_projects_after_their_folder() {
+        let doc = json!({ "projectDir": "C:\\Users\\me\\My Cut\\", "mainTrack": [], "overlayTrack": "bad" });
+        let (doc, _) = migrate_document(doc).unwrap();
+        assert_eq!(doc["name"], json!("My Cut"));
+        assert_eq!(doc["overlayTrack"], json!([]));
+
+        let (doc, _) = migrate_document(json!({})).unwrap();
+        assert_eq!(doc["name"], json!("Untitled Project"));
+    }
+
+    #[test]
+    fn leaves_current_documents_alone() {
+        let doc = json!({ "version": PROJECT_SCHEMA_VERSION, "name": "Current", "mainTrack": [] });
+        let (migrated, report) = migrate_document(doc.clone()).unwrap();
+        assert_eq!(migrated, doc);
+        assert!(report.applied.is_empty());
+    }
+
+    #[test]
+    fn rejects_documents_from_a_newer_version() {
+        let doc = json!({ "version": PROJECT_SCHEMA_VERSION + 1 });
+        let error = migrate_document(doc).unwrap_err();
+        assert!(error.contains("newer version"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_documents_that_are_not_objects() {
+        assert!(migrate_document(json!([1, 2, 3])).is_err());
+    }
+}
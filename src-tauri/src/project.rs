@@ -0,0 +1,523 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Component, Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::fingerprint::{fingerprint_file, MediaFingerprint};
+use crate::fs::write_file_atomic;
+use crate::migrations::{document_version, migrate_document, MigrationReport};
+use crate::sandbox::{CommandError, PathPolicy};
+use crate::timeline::{ProjectSettings, Timeline, TimelineItem};
+
+// Project file name inside the project directory
+pub const PROJECT_FILE: &str = "project.trimbot.json";
+
+// Schema version written by this build
+pub const PROJECT_SCHEMA_VERSION: u32 = 1;
+
+/// Track layout preferences, mirroring the frontend `TrackSettings`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_overlay_track: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlay_track_height: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_spacing: Option<f64>,
+}
+
+/// Contents of `project.trimbot.json`.
+///
+/// Clip paths inside the project directory are stored relative to it (with
+/// `/` separators); `load_project` and `save_project` convert to and from
+/// absolute paths, so the frontend only ever sees absolute ones.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFile {
+    pub version: u32,
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub settings: ProjectSettings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_settings: Option<TrackSettings>,
+    #[serde(default)]
+    pub main_track: Vec<TimelineItem>,
+    #[serde(default)]
+    pub overlay_track: Vec<TimelineItem>,
+    /// Fingerprints of the clip sources, used to find moved files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<MediaFingerprint>,
+}
+
+impl ProjectFile {
+    /// The project's clips and settings as a renderable timeline
+    pub fn timeline(&self) -> Timeline {
+        Timeline {
+            main_track: self.main_track.clone(),
+            overlay_track: self.overlay_track.clone(),
+            settings: Some(self.settings.clone()),
+        }
+    }
+
+    /// Check the version, settings and every clip
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version > PROJECT_SCHEMA_VERSION {
+            return Err(format!(
+                "Project was saved by a newer version of TrimBot (schema {}, supported {})",
+                self.version, PROJECT_SCHEMA_VERSION
+            ));
+        }
+        if self.id.trim().is_empty() {
+            return Err("Project has no id".to_string());
+        }
+        if self.name.trim().is_empty() {
+            return Err("Project has no name".to_string());
+        }
+
+        self.timeline().validate_clips()?;
+
+        let mut ids = HashSet::new();
+        for item in self.main_track.iter().chain(&self.overlay_track) {
+            if !ids.insert(item.id.as_str()) {
+                return Err(format!("Duplicate clip id {}", item.id));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn map_paths(&mut self, f: impl Fn(&str) -> String) {
+        for item in self.main_track.iter_mut().chain(self.overlay_track.iter_mut()) {
+            item.path = f(&item.path);
+        }
+        for media in &mut self.media {
+            media.path = f(&media.path);
+        }
+    }
+
+    /// Point every clip (and fingerprint) using `old` at `new`
+    pub fn replace_path(&mut self, old: &str, new: &str) {
+        self.map_paths(|p| if p == old { new.to_string() } else { p.to_string() });
+    }
+
+    /// Distinct source paths referenced by clips
+    pub fn source_paths(&self) -> BTreeSet<String> {
+        self.main_track
+            .iter()
+            .chain(&self.overlay_track)
+            .map(|item| item.path.clone())
+            .collect()
+    }
+
+    /// Fingerprint every source that is online, reusing recorded fingerprints
+    /// while the file size is unchanged. Offline sources keep their old
+    /// fingerprint so they can still be relinked.
+    pub fn refresh_media_fingerprints(&mut self) {
+        let mut media = Vec::new();
+        for path in self.source_paths() {
+            let known = self.media.iter().find(|m| m.path == path);
+            match (known, std::fs::metadata(&path).ok().map(|m| m.len())) {
+                (Some(known), Some(size)) if known.size == size => media.push(known.clone()),
+                (known, Some(_)) => {
+                    if let Some(fingerprint) = fingerprint_file(&path).ok().or_else(|| known.cloned()) {
+                        media.push(fingerprint);
+                    }
+                }
+                (Some(known), None) => media.push(known.clone()),
+                (None, None) => {}
+            }
+        }
+        self.media = media;
+    }
+
+    /// Turn stored (project-relative) clip paths into absolute ones
+    pub fn resolve_paths(&mut self, project_dir: &Path) {
+        self.map_paths(|p| from_project_relative(project_dir, p));
+    }
+}
+
+pub fn project_file_path(project_dir: &Path) -> PathBuf {
+    project_dir.join(PROJECT_FILE)
+}
+
+/// Express `path` relative to the project directory when it lies inside it
+pub fn to_project_relative(project_dir: &Path, path: &str) -> String {
+    let absolute = Path::new(path);
+    if !absolute.is_absolute() {
+        return path.to_string();
+    }
+
+    // A lexical match only counts without `.`/`..`, which it would misplace
+    let lexical = absolute
+        .strip_prefix(project_dir)
+        .ok()
+        .filter(|rest| rest.components().all(|c| matches!(c, Component::Normal(_))))
+        .map(Path::to_path_buf);
+    let relative = lexical.or_else(|| {
+        // Fall back to canonical paths to see through symlinks and `..`
+        let dir = project_dir.canonicalize().ok()?;
+        let file = absolute.canonicalize().ok()?;
+        file.strip_prefix(dir).ok().map(Path::to_path_buf)
+    });
+
+    match relative {
+        Some(relative) => relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/"),
+        None => path.to_string(),
+    }
+}
+
+/// Resolve a stored path against the project directory
+pub fn from_project_relative(project_dir: &Path, path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        return path.to_string();
+    }
+    let mut resolved = project_dir.to_path_buf();
+    resolved.extend(path.split('/').filter(|part| !part.is_empty()));
+    resolved.display().to_string()
+}
+
+/// A loaded project and, if it had to be upgraded, what was done
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedProject {
+    pub project: ProjectFile,
+    pub migration: Option<MigrationReport>,
+}
+
+/// Where to keep the pre-migration copy of a project file; never overwrites
+/// an earlier backup
+fn backup_path(project_dir: &Path, from_version: u32) -> PathBuf {
+    let path = project_dir.join(format!("{}.v{}.bak", PROJECT_FILE, from_version));
+    if !path.exists() {
+        return path;
+    }
+    project_dir.join(format!(
+        "{}.v{}.{}.bak",
+        PROJECT_FILE,
+        from_version,
+        Utc::now().format("%Y%m%d%H%M%S")
+    ))
+}
+
+/// Read, migrate and validate a project file, returning absolute clip paths.
+///
+/// Nothing is written: a migrated project is only upgraded in memory until
+/// `write_migrated_project` or the next save.
+pub fn read_project(project_dir: &Path) -> Result<LoadedProject, String> {
+    let json = std::fs::read_to_string(project_file_path(project_dir))
+        .map_err(|e| format!("Failed to read project file: {}", e))?;
+    let (mut project, report) = parse_project_json(&json)?;
+
+    let migration = (!report.applied.is_empty()).then_some(report);
+    project.resolve_paths(project_dir);
+    Ok(LoadedProject { project, migration })
+}
+
+/// Copy the project file to a backup if it is in an older schema, before it
+/// is overwritten. Returns the backup, if one was made.
+fn back_up_older_schema(project_dir: &Path) -> Result<Option<PathBuf>, String> {
+    let path = project_file_path(project_dir);
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let version = serde_json::from_str(&json).ok().and_then(|doc| document_version(&doc).ok());
+    match version {
+        Some(version) if version < PROJECT_SCHEMA_VERSION => {
+            let backup = backup_path(project_dir, version);
+            std::fs::copy(&path, &backup)
+                .map_err(|e| format!("Failed to back up project file: {}", e))?;
+            Ok(Some(backup))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Write a migrated project back in the current schema, keeping the original
+/// file as a backup
+pub fn write_migrated_project(project_dir: &Path, loaded: &mut LoadedProject) -> Result<(), String> {
+    let Some(report) = loaded.migration.as_mut() else {
+        return Ok(());
+    };
+    report.backup_path = back_up_older_schema(project_dir)?.map(|backup| backup.display().to_string());
+
+    // Older documents stored absolute paths
+    let json = stored_project_json(project_dir, &loaded.project)?;
+    write_file_atomic(&project_file_path(project_dir), json.as_bytes())
+}
+
+/// Parse a stored project document, migrating it to the current schema and
+/// validating it. Clip paths are left as stored.
+pub fn parse_project_json(json: &str) -> Result<(ProjectFile, MigrationReport), String> {
+    let doc: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse project file: {}", e))?;
+
+    let (doc, report) = migrate_document(doc)?;
+    let project: ProjectFile = serde_json::from_value(doc)
+        .map_err(|e| format!("Failed to parse project file: {}", e))?;
+    project.validate()?;
+    Ok((project, report))
+}
+
+/// Serialize a project for storage, with clip paths relative to the project
+pub fn stored_project_json(project_dir: &Path, project: &ProjectFile) -> Result<String, String> {
+    let mut stored = project.clone();
+    stored.map_paths(|p| to_project_relative(project_dir, p));
+    serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize project: {}", e))
+}
+
+/// Validate and atomically write a project file. Returns the project as saved,
+/// with the current schema version and a fresh `updated_at`.
+pub fn write_project(project_dir: &Path, mut project: ProjectFile) -> Result<ProjectFile, String> {
+    project.version = PROJECT_SCHEMA_VERSION;
+    project.updated_at = Utc::now();
+    project.validate()?;
+    project.refresh_media_fingerprints();
+
+    let json = stored_project_json(project_dir, &project)?;
+    std::fs::create_dir_all(project_dir)
+        .map_err(|e| format!("Failed to create project directory: {}", e))?;
+    // A project loaded without being migrated on disk still gets its backup
+    back_up_older_schema(project_dir)?;
+    write_file_atomic(&project_file_path(project_dir), json.as_bytes())?;
+
+    Ok(project)
+}
+
+/// Save the project to `project.trimbot.json` in the project directory
+#[tauri::command]
+pub async fn save_project(
+    policy: tauri::State<'_, PathPolicy>,
+    project_dir: String,
+    project: ProjectFile,
+) -> Result<ProjectFile, CommandError> {
+    policy.check_write(&project_file_path(Path::new(&project_dir)))?;
+    let saved = tauri::async_runtime::spawn_blocking(move || write_project(Path::new(&project_dir), project))
+        .await
+        .map_err(|e| format!("Failed to save project: {}", e))??;
+    Ok(saved)
+}
+
+/// Load `project.trimbot.json` from the project directory, upgrading older
+/// schemas. The upgrade is written back (the original is kept as a `.bak`
+/// file) only if the directory is writable, i.e. it was chosen in the
+/// directory dialog or lives under app data; otherwise the file is untouched.
+#[tauri::command]
+pub async fn load_project(
+    policy: tauri::State<'_, PathPolicy>,
+    project_dir: String,
+) -> Result<LoadedProject, CommandError> {
+    let writable = policy.check_write(&project_file_path(Path::new(&project_dir))).is_ok();
+    let loaded = tauri::async_runtime::spawn_blocking(move || {
+        let project_dir = Path::new(&project_dir);
+        let mut loaded = read_project(project_dir)?;
+        if writable {
+            write_migrated_project(project_dir, &mut loaded)?;
+        }
+        Ok::<_, String>(loaded)
+    })
+    .await
+    .map_err(|e| format!("Failed to load project: {}", e))??;
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A fresh directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("trimbot_project_{}_{}", name, uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir.canonicalize().unwrap())
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn project_doc(clips: serde_json::Value) -> serde_json::Value {
+        json!({
+            "version": PROJECT_SCHEMA_VERSION,
+            "id": "project-1",
+            "name": "Interview",
+            "createdAt": "2026-01-01T00:00:00Z",
+            "updatedAt": "2026-01-01T00:00:00Z",
+            "settings": { "resolution": { "width": 1920, "height": 1080 }, "frameRate": 30, "outputFormat": "mp4" },
+            "mainTrack": clips,
+            "overlayTrack": []
+        })
+    }
+
+    fn clip(id: &str, path: &str, start_time: f64) -> serde_json::Value {
+        json!({ "id": id, "path": path, "startTime": start_time, "durationSec": 2.0 })
+    }
+
+    fn write_doc(dir: &Path, doc: &serde_json::Value) {
+        std::fs::write(project_file_path(dir), serde_json::to_string(doc).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn paths_inside_the_project_round_trip_as_relative() {
+        let dir = Path::new("/projects/interview");
+        let inside = "/projects/interview/media/take 1.mp4";
+
+        let stored = to_project_relative(dir, inside);
+        assert_eq!(stored, "media/take 1.mp4");
+        assert_eq!(from_project_relative(dir, &stored), inside);
+    }
+
+    #[test]
+    fn paths_outside_the_project_stay_absolute() {
+        let dir = Path::new("/projects/interview");
+        for path in ["/footage/take 1.mp4", "/projects/interview-old/media/a.mp4"] {
+            assert_eq!(to_project_relative(dir, path), path);
+            assert_eq!(from_project_relative(dir, path), path);
+        }
+    }
+
+    #[test]
+    fn parent_dir_components_are_not_stripped_lexically() {
+        let dir = Path::new("/projects/interview");
+        // Does not exist, so it cannot be resolved and is kept as given
+        let path = "/projects/interview/media/../../other/a.mp4";
+        assert_eq!(to_project_relative(dir, path), path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_through_a_symlinked_project_dir_become_relative() {
+        let real = TempDir::new("real");
+        let links = TempDir::new("links");
+        std::fs::create_dir(real.0.join("media")).unwrap();
+        std::fs::write(real.0.join("media/a.mp4"), b"").unwrap();
+        let alias = links.0.join("alias");
+        std::os::unix::fs::symlink(&real.0, &alias).unwrap();
+
+        let path = alias.join("media/a.mp4").display().to_string();
+        assert_eq!(to_project_relative(&real.0, &path), "media/a.mp4");
+    }
+
+    #[test]
+    fn a_moved_project_resolves_clips_in_its_new_location() {
+        let original = TempDir::new("original");
+        let moved = TempDir::new("moved");
+        let inside = original.0.join("media/a.mp4").display().to_string();
+        let doc = project_doc(json!([clip("a", &inside, 0.0), clip("b", "/footage/b.mp4", 2.0)]));
+        let (project, _) = parse_project_json(&doc.to_string()).unwrap();
+        write_project(&original.0, project).unwrap();
+
+        std::fs::copy(project_file_path(&original.0), project_file_path(&moved.0)).unwrap();
+        let loaded = read_project(&moved.0).unwrap();
+
+        assert!(loaded.migration.is_none());
+        let paths: Vec<&str> = loaded.project.main_track.iter().map(|item| item.path.as_str()).collect();
+        assert_eq!(paths, [moved.0.join("media/a.mp4").display().to_string().as_str(), "/footage/b.mp4"]);
+    }
+
+    #[test]
+    fn load_rejects_invalid_projects() {
+        let cases = [
+            ("newer schema", {
+                let mut doc = project_doc(json!([]));
+                doc["version"] = json!(PROJECT_SCHEMA_VERSION + 1);
+                doc
+            }),
+            ("blank name", {
+                let mut doc = project_doc(json!([]));
+                doc["name"] = json!("  ");
+                doc
+            }),
+            ("duplicate clip ids", project_doc(json!([clip("a", "/a.mp4", 0.0), clip("a", "/b.mp4", 2.0)]))),
+            ("negative duration", project_doc(json!([{ "id": "a", "path": "/a.mp4", "startTime": 0.0, "durationSec": -1.0 }]))),
+        ];
+        for (what, doc) in cases {
+            let dir = TempDir::new("invalid");
+            write_doc(&dir.0, &doc);
+            let before = std::fs::read_to_string(project_file_path(&dir.0)).unwrap();
+
+            assert!(read_project(&dir.0).is_err(), "{} should be rejected", what);
+            // A rejected file is left untouched
+            assert_eq!(std::fs::read_to_string(project_file_path(&dir.0)).unwrap(), before);
+        }
+    }
+
+    fn legacy_doc(project_dir: &Path) -> serde_json::Value {
+        json!({
+            "projectDir": project_dir.display().to_string(),
+            "timeline": [clip("a", &project_dir.join("media/a.mp4").display().to_string(), 0.0)]
+        })
+    }
+
+    #[test]
+    fn reading_a_legacy_project_does_not_write_it() {
+        let dir = TempDir::new("legacy_read");
+        write_doc(&dir.0, &legacy_doc(&dir.0));
+        let before = std::fs::read_to_string(project_file_path(&dir.0)).unwrap();
+
+        let loaded = read_project(&dir.0).unwrap();
+        let report = loaded.migration.unwrap();
+        assert_eq!((report.from_version, report.backup_path), (0, None));
+        assert_eq!(std::fs::read_to_string(project_file_path(&dir.0)).unwrap(), before);
+        assert_eq!(std::fs::read_dir(&dir.0).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn migrated_projects_are_written_back_with_a_backup() {
+        let dir = TempDir::new("legacy_write");
+        write_doc(&dir.0, &legacy_doc(&dir.0));
+        let before = std::fs::read_to_string(project_file_path(&dir.0)).unwrap();
+
+        let mut loaded = read_project(&dir.0).unwrap();
+        write_migrated_project(&dir.0, &mut loaded).unwrap();
+
+        let backup = loaded.migration.unwrap().backup_path.unwrap();
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), before);
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(project_file_path(&dir.0)).unwrap()).unwrap();
+        assert_eq!(stored["version"], json!(PROJECT_SCHEMA_VERSION));
+        assert_eq!(stored["mainTrack"][0]["path"], json!("media/a.mp4"));
+    }
+
+    #[test]
+    fn saving_over_a_legacy_project_keeps_a_backup() {
+        let dir = TempDir::new("legacy_save");
+        write_doc(&dir.0, &legacy_doc(&dir.0));
+        let before = std::fs::read_to_string(project_file_path(&dir.0)).unwrap();
+
+        let loaded = read_project(&dir.0).unwrap();
+        write_project(&dir.0, loaded.project).unwrap();
+        write_project(&dir.0, read_project(&dir.0).unwrap().project).unwrap();
+
+        // One backup of the original, none of the already current file
+        let backup = dir.0.join(format!("{}.v0.bak", PROJECT_FILE));
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), before);
+        assert_eq!(std::fs::read_dir(&dir.0).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn load_reports_unreadable_files() {
+        let dir = TempDir::new("unreadable");
+        assert!(read_project(&dir.0).is_err());
+
+        std::fs::write(project_file_path(&dir.0), "{ not json").unwrap();
+        assert!(read_project(&dir.0).is_err());
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+
+use crate::fingerprint::{partial_hash, MediaFingerprint};
+use crate::media::probe_media_info;
+use crate::project::ProjectFile;
+
+// Stop scanning a search directory after this many files and match against
+// what was found so far
+const MAX_SCAN_FILES: usize = 100_000;
+
+// Durations closer than this are treated as the same clip
+const DURATION_TOLERANCE: f64 = 0.1;
+
+/// A source referenced by the project that is not on disk
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineMedia {
+    pub path: String,
+    pub clip_ids: Vec<String>,
+    /// Recorded identity of the file, if the project was saved while it was online
+    pub fingerprint: Option<MediaFingerprint>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaCheck {
+    pub total: usize,
+    pub offline: Vec<OfflineMedia>,
+}
+
+/// Evidence a relinked file was accepted on, strongest first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchKind {
+    /// Same size and partial content hash
+    Hash,
+    /// Same file name and duration (e.g. the file was re-encoded)
+    NameAndDuration,
+    /// Only candidate with the same file name; no fingerprint was recorded
+    Name,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkedMedia {
+    pub old_path: String,
+    pub new_path: String,
+    pub matched_by: MatchKind,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkResult {
+    /// The project with relinked paths; save it to keep the changes
+    pub project: ProjectFile,
+    pub relinked: Vec<RelinkedMedia>,
+    /// Offline paths no candidate was found for
+    pub unresolved: Vec<String>,
+    /// The search directory held too many files to scan them all; choosing a
+    /// narrower folder may resolve more sources
+    pub scan_truncated: bool,
+}
+
+/// Find sources that are missing on disk
+pub fn find_offline_media(project: &ProjectFile) -> MediaCheck {
+    let paths = project.source_paths();
+    let offline = paths
+        .iter()
+        .filter(|path| !Path::new(path.as_str()).exists())
+        .map(|path| OfflineMedia {
+            path: path.clone(),
+            clip_ids: project
+                .main_track
+                .iter()
+                .chain(&project.overlay_track)
+                .filter(|item| &item.path == path)
+                .map(|item| item.id.clone())
+                .collect(),
+            fingerprint: project.media.iter().find(|m| &m.path == path).cloned(),
+        })
+        .collect();
+
+    MediaCheck {
+        total: paths.len(),
+        offline,
+    }
+}
+
+/// A file found while scanning the search directory
+struct Candidate {
+    path: PathBuf,
+    name: String,
+    size: u64,
+}
+
+/// Collect regular files below `dir`, stopping after `limit` files; symlinks
+/// are not followed. Also reports whether the limit cut the scan short.
+fn scan_candidates(dir: &Path, limit: usize) -> (Vec<Candidate>, bool) {
+    let mut candidates = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                if candidates.len() >= limit {
+                    return (candidates, true);
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                candidates.push(Candidate {
+                    path: entry.path(),
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    size: metadata.len(),
+                });
+            }
+        }
+    }
+
+    (candidates, false)
+}
+
+/// File name component of a path in either separator style
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Pick the best candidate for one offline file. `duration_of` probes a
+/// candidate's duration and is only called for same-name candidates.
+fn find_match(
+    offline: &OfflineMedia,
+    candidates: &[Candidate],
+    hashes: &mut HashMap<PathBuf, Option<String>>,
+    duration_of: impl Fn(&Path) -> Option<f64>,
+) -> Option<(PathBuf, MatchKind)> {
+    let name = file_name(&offline.path);
+    let same_name = |c: &&Candidate| c.name.eq_ignore_ascii_case(name);
+
+    let Some(fingerprint) = &offline.fingerprint else {
+        let mut named = candidates.iter().filter(same_name);
+        return match (named.next(), named.next()) {
+            (Some(only), None) => Some((only.path.clone(), MatchKind::Name)),
+            _ => None,
+        };
+    };
+
+    // Content match; among several copies prefer one that kept its name
+    let mut hash_matches: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|c| c.size == fingerprint.size)
+        .filter(|c| {
+            let hash = hashes
+                .entry(c.path.clone())
+                .or_insert_with(|| partial_hash(&c.path).ok());
+            hash.as_deref() == Some(fingerprint.partial_hash.as_str())
+        })
+        .collect();
+    hash_matches.sort_by_key(|c| !c.name.eq_ignore_ascii_case(name));
+    if let Some(best) = hash_matches.first() {
+        return Some((best.path.clone(), MatchKind::Hash));
+    }
+
+    let duration = fingerprint.duration?;
+    candidates
+        .iter()
+        .filter(same_name)
+        .find(|c| duration_of(&c.path).is_some_and(|d| (d - duration).abs() <= DURATION_TOLERANCE))
+        .map(|c| (c.path.clone(), MatchKind::NameAndDuration))
+}
+
+fn probe_duration(path: &Path) -> Option<f64> {
+    probe_media_info(&path.to_string_lossy()).ok().and_then(|info| info.duration)
+}
+
+/// Relink every offline source to a matching file below `search_dir`
+pub fn relink_project_media(mut project: ProjectFile, search_dir: &Path) -> Result<RelinkResult, String> {
+    if !search_dir.is_dir() {
+        return Err(format!("Search directory not found: {}", search_dir.display()));
+    }
+
+    let check = find_offline_media(&project);
+    if check.offline.is_empty() {
+        return Ok(RelinkResult {
+            project,
+            relinked: Vec::new(),
+            unresolved: Vec::new(),
+            scan_truncated: false,
+        });
+    }
+
+    let (candidates, scan_truncated) = scan_candidates(search_dir, MAX_SCAN_FILES);
+    let mut hashes = HashMap::new();
+    let mut relinked = Vec::new();
+    let mut unresolved = Vec::new();
+
+    for offline in &check.offline {
+        match find_match(offline, &candidates, &mut hashes, probe_duration) {
+            Some((path, matched_by)) => {
+                let new_path = path.display().to_string();
+                project.replace_path(&offline.path, &new_path);
+                relinked.push(RelinkedMedia {
+                    old_path: offline.path.clone(),
+                    new_path,
+                    matched_by,
+                });
+            }
+            None => unresolved.push(offline.path.clone()),
+        }
+    }
+
+    Ok(RelinkResult {
+        project,
+        relinked,
+        unresolved,
+        scan_truncated,
+    })
+}
+
+/// Report clip sources that are missing on disk
+#[tauri::command]
+pub async fn check_project_media(project: ProjectFile) -> Result<MediaCheck, String> {
+    tauri::async_runtime::spawn_blocking(move || find_offline_media(&project))
+        .await
+        .map_err(|e| format!("Failed to check project media: {}", e))
+}
+
+/// Search a directory for moved or renamed sources and relink them in bulk.
+///
+/// Files are matched by size and content hash first, then by name and
+/// duration. The updated project is returned, not saved.
+#[tauri::command]
+pub async fn relink_media(project: ProjectFile, search_dir: String) -> Result<RelinkResult, String> {
+    tauri::async_runtime::spawn_blocking(move || relink_project_media(project, Path::new(&search_dir)))
+        .await
+        .map_err(|e| format!("Failed to relink media: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("trimbot_relink_{}_{}", name, uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, relative: &str, contents: &[u8]) -> PathBuf {
+            let path = self.0.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn offline(path: &str, fingerprint: Option<MediaFingerprint>) -> OfflineMedia {
+        OfflineMedia {
+            path: path.to_string(),
+            clip_ids: vec!["clip".to_string()],
+            fingerprint,
+        }
+    }
+
+    fn fingerprint_of(path: &Path, duration: Option<f64>) -> MediaFingerprint {
+        MediaFingerprint {
+            path: path.display().to_string(),
+            size: std::fs::metadata(path).unwrap().len(),
+            duration,
+            partial_hash: partial_hash(path).unwrap(),
+        }
+    }
+
+    fn scan(dir: &TempDir) -> Vec<Candidate> {
+        let (candidates, truncated) = scan_candidates(&dir.0, MAX_SCAN_FILES);
+        assert!(!truncated);
+        candidates
+    }
+
+    fn no_durations(_: &Path) -> Option<f64> {
+        None
+    }
+
+    #[test]
+    fn content_match_wins_over_name() {
+        let dir = TempDir::new("hash");
+        let renamed = dir.file("renamed.mp4", b"original footage");
+        dir.file("take1.mp4", b"different footage");
+        let fingerprint = fingerprint_of(&renamed, Some(4.0));
+        let candidates = scan(&dir);
+
+        let found = find_match(&offline("/old/take1.mp4", Some(fingerprint)), &candidates, &mut HashMap::new(), |_| Some(4.0));
+        assert_eq!(found, Some((renamed, MatchKind::Hash)));
+    }
+
+    #[test]
+    fn content_match_prefers_the_copy_that_kept_its_name() {
+        let dir = TempDir::new("copies");
+        dir.file("a/copy.mp4", b"original footage");
+        let named = dir.file("b/take1.mp4", b"original footage");
+        let fingerprint = fingerprint_of(&named, None);
+        let candidates = scan(&dir);
+
+        let found = find_match(&offline("/old/TAKE1.mp4", Some(fingerprint)), &candidates, &mut HashMap::new(), no_durations);
+        assert_eq!(found, Some((named, MatchKind::Hash)));
+    }
+
+    #[test]
+    fn falls_back_to_name_and_duration() {
+        let dir = TempDir::new("duration");
+        let original = dir.file("original.mp4", b"original footage");
+        let fingerprint = fingerprint_of(&original, Some(10.0));
+        std::fs::remove_file(&original).unwrap();
+        let short = dir.file("a/take1.mp4", b"re-encoded, wrong cut");
+        let reencoded = dir.file("b/take1.mp4", b"re-encoded footage");
+        let candidates = scan(&dir);
+
+        let duration_of = |path: &Path| Some(if path == short { 6.0 } else { 10.05 });
+        let found = find_match(&offline("/old/take1.mp4", Some(fingerprint.clone())), &candidates, &mut HashMap::new(), duration_of);
+        assert_eq!(found, Some((reencoded, MatchKind::NameAndDuration)));
+
+        // Without a recorded duration a fingerprinted source is not matched by name alone
+        let fingerprint = MediaFingerprint { duration: None, ..fingerprint };
+        let found = find_match(&offline("/old/take1.mp4", Some(fingerprint)), &candidates, &mut HashMap::new(), duration_of);
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn name_only_matches_need_a_single_candidate() {
+        let dir = TempDir::new("name");
+        let only = dir.file("media/take1.mp4", b"footage");
+        dir.file("media/take2.mp4", b"footage");
+        let candidates = scan(&dir);
+
+        let found = find_match(&offline("C:\\old\\Take1.mp4", None), &candidates, &mut HashMap::new(), no_durations);
+        assert_eq!(found, Some((only, MatchKind::Name)));
+
+        dir.file("backup/take1.mp4", b"other footage");
+        let candidates = scan(&dir);
+        let found = find_match(&offline("/old/take1.mp4", None), &candidates, &mut HashMap::new(), no_durations);
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn scan_finds_nested_files_and_stops_at_the_limit() {
+        let dir = TempDir::new("scan");
+        for i in 0..5 {
+            dir.file(&format!("level{}/clip{}.mp4", i % 2, i), b"x");
+        }
+
+        let (candidates, truncated) = scan_candidates(&dir.0, MAX_SCAN_FILES);
+        assert_eq!(candidates.len(), 5);
+        assert!(!truncated);
+
+        let (candidates, truncated) = scan_candidates(&dir.0, 3);
+        assert_eq!(candidates.len(), 3);
+        assert!(truncated);
+
+        // Exactly at the limit is not truncated
+        let (candidates, truncated) = scan_candidates(&dir.0, 5);
+        assert_eq!(candidates.len(), 5);
+        assert!(!truncated);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_does_not_follow_symlinks() {
+        let dir = TempDir::new("links");
+        let outside = TempDir::new("links_target");
+        outside.file("take1.mp4", b"x");
+        std::os::unix::fs::symlink(&outside.0, dir.0.join("linked")).unwrap();
+        std::os::unix::fs::symlink(outside.0.join("take1.mp4"), dir.0.join("take1.mp4")).unwrap();
+
+        assert!(scan(&dir).is_empty());
+    }
+}
@@ -0,0 +1,709 @@
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::encode::{resolve_encode_settings, EncodeSettings};
+use crate::jobs::{start_job, Job};
+use crate::keyframes::{opacity_alpha_filter, opacity_to_black_filter, volume_filter};
+use crate::media::{probe_media_info, MediaInfo};
+use crate::sandbox::{CommandError, PathPolicy};
+use crate::timeline::{FitMode, FrameRateConversion, Resolution, Timeline, TimelineItem};
+
+// Default canvas used when neither settings nor sources give a size
+const DEFAULT_WIDTH: u32 = 1920;
+const DEFAULT_HEIGHT: u32 = 1080;
+const DEFAULT_FRAME_RATE: f64 = 30.0;
+
+// Audio format every segment is normalized to before concatenation
+const AUDIO_SAMPLE_RATE: u32 = 48000;
+const AUDIO_LAYOUT: &str = "stereo";
+
+// Gaps shorter than this between main track clips are ignored
+const GAP_EPSILON: f64 = 0.001;
+
+/// Format a number for use inside a filter graph
+pub fn num(value: f64) -> String {
+    let s = format!("{:.6}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+/// Output canvas every clip is normalized to
+#[derive(Clone, Copy, Debug)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    pub conversion: FrameRateConversion,
+}
+
+impl Canvas {
+    /// Use project settings when given, otherwise the first video source
+    pub fn for_timeline(timeline: &Timeline, media: &HashMap<String, MediaInfo>) -> Self {
+        if let Some(settings) = &timeline.settings {
+            return Canvas {
+                width: even(settings.resolution.width),
+                height: even(settings.resolution.height),
+                frame_rate: settings.frame_rate,
+                conversion: settings.frame_rate_conversion.unwrap_or_default(),
+            };
+        }
+
+        let (width, height) = timeline
+            .main_track_sorted()
+            .into_iter()
+            .chain(&timeline.overlay_track)
+            .filter_map(|item| media.get(&item.path)?.video_stream()?.display_size())
+            .next()
+            .unwrap_or((DEFAULT_WIDTH, DEFAULT_HEIGHT));
+
+        Canvas {
+            width: even(width),
+            height: even(height),
+            frame_rate: DEFAULT_FRAME_RATE,
+            conversion: FrameRateConversion::default(),
+        }
+    }
+
+    /// Use the requested resolution when given, otherwise the source's display
+    /// size; the frame rate follows the source unless it is variable
+    pub fn for_source(source: &MediaInfo, resolution: Option<Resolution>) -> Self {
+        let video = source.video_stream();
+        let (width, height) = match resolution {
+            Some(resolution) => (resolution.width, resolution.height),
+            None => video
+                .and_then(|v| v.display_size())
+                .unwrap_or((DEFAULT_WIDTH, DEFAULT_HEIGHT)),
+        };
+        let frame_rate = video
+            .filter(|v| !v.variable_frame_rate)
+            .and_then(|v| v.avg_frame_rate.or(v.frame_rate))
+            .map(|r| r.as_f64())
+            .filter(|r| r.is_finite() && *r > 0.0)
+            .unwrap_or(DEFAULT_FRAME_RATE);
+
+        Canvas {
+            width: even(width),
+            height: even(height),
+            frame_rate,
+            conversion: FrameRateConversion::default(),
+        }
+    }
+
+    /// Override the frame rate and conversion method, e.g. from project settings
+    pub fn with_frame_rate(self, frame_rate: Option<f64>, conversion: Option<FrameRateConversion>) -> Self {
+        Canvas {
+            frame_rate: frame_rate
+                .filter(|r| r.is_finite() && *r > 0.0)
+                .unwrap_or(self.frame_rate),
+            conversion: conversion.unwrap_or(self.conversion),
+            ..self
+        }
+    }
+
+    pub fn size(&self) -> String {
+        format!("{}x{}", self.width, self.height)
+    }
+}
+
+// H.264 with yuv420p needs even dimensions
+fn even(value: u32) -> u32 {
+    (value.max(2) / 2) * 2
+}
+
+/// Builder for a single `-filter_complex` graph and its inputs
+#[derive(Default)]
+pub struct FilterGraph {
+    input_args: Vec<String>,
+    input_count: usize,
+    chains: Vec<String>,
+    next_label: usize,
+}
+
+impl FilterGraph {
+    /// Add a file input with optional input options; returns its input index
+    pub fn add_input(&mut self, options: Vec<String>, path: &str) -> usize {
+        self.input_args.extend(options);
+        self.input_args.push("-i".to_string());
+        self.input_args.push(path.to_string());
+        self.input_count += 1;
+        self.input_count - 1
+    }
+
+    /// Allocate a fresh pad label such as `[v3]`
+    pub fn label(&mut self, prefix: &str) -> String {
+        self.next_label += 1;
+        format!("[{}{}]", prefix, self.next_label)
+    }
+
+    pub fn push(&mut self, chain: String) {
+        self.chains.push(chain);
+    }
+
+    /// FFmpeg arguments for all inputs followed by the filter graph
+    pub fn into_args(self) -> Vec<String> {
+        let mut args = self.input_args;
+        args.push("-filter_complex".to_string());
+        args.push(self.chains.join(";"));
+        args
+    }
+}
+
+/// A compiled timeline: FFmpeg inputs, filter graph and output pads
+pub struct RenderPlan {
+    pub args: Vec<String>,
+    pub video_out: String,
+    pub audio_out: String,
+    pub duration: f64,
+}
+
+/// Video and audio pads for one rendered segment
+struct Segment {
+    video: String,
+    audio: String,
+}
+
+/// Black frames and silence for a gap in the main track
+fn gap_segment(graph: &mut FilterGraph, canvas: &Canvas, duration: f64) -> Segment {
+    let video = graph.label("gv");
+    let audio = graph.label("ga");
+    graph.push(format!(
+        "color=c=black:s={}:r={}:d={},format=yuv420p,setsar=1{}",
+        canvas.size(), num(canvas.frame_rate), num(duration), video
+    ));
+    graph.push(format!(
+        "anullsrc=r={}:cl={},atrim=duration={}{}",
+        AUDIO_SAMPLE_RATE, AUDIO_LAYOUT, num(duration), audio
+    ));
+    Segment { video, audio }
+}
+
+/// Scale a video stream onto the canvas using the given fit mode
+pub fn fit_filter(canvas: &Canvas, mode: FitMode) -> String {
+    let (w, h) = (canvas.width, canvas.height);
+    match mode {
+        FitMode::Letterbox => format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:color=black"
+        ),
+        FitMode::Crop => format!(
+            "scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h}"
+        ),
+        FitMode::Stretch => format!("scale={w}:{h}"),
+    }
+}
+
+/// Convert a video stream to the canvas frame rate
+pub fn conform_filter(canvas: &Canvas) -> String {
+    match canvas.conversion {
+        FrameRateConversion::Duplicate => format!("fps={}", num(canvas.frame_rate)),
+        FrameRateConversion::Interpolate => format!(
+            "minterpolate=fps={}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1",
+            num(canvas.frame_rate)
+        ),
+    }
+}
+
+/// Audio filter that stretches or pads audio to follow the video timestamps,
+/// so VFR recordings do not drift after conforming
+pub const AUDIO_SYNC_FILTER: &str = "aresample=async=1:first_pts=0";
+
+/// Input options that seek to and limit the clip's trim window
+fn trim_input_options(item: &TimelineItem) -> Vec<String> {
+    vec![
+        "-ss".to_string(), num(item.source_in()),
+        "-t".to_string(), num(item.clip_duration()),
+    ]
+}
+
+/// Video filter chain (without pads) that trims, normalizes, fades and
+/// applies opacity keyframes to a clip
+fn clip_video_filters(item: &TimelineItem, canvas: &Canvas) -> Vec<String> {
+    let duration = item.clip_duration();
+    let mut filters = vec![
+        "setpts=PTS-STARTPTS".to_string(),
+        fit_filter(canvas, item.fit_mode.unwrap_or_default()),
+        "setsar=1".to_string(),
+        conform_filter(canvas),
+        "format=yuv420p".to_string(),
+        // Hold the last frame if the source runs short, then cut to exact length
+        format!("tpad=stop_mode=clone:stop_duration={}", num(duration)),
+        format!("trim=duration={}", num(duration)),
+    ];
+
+    if let Some(fade_in) = item.fade_in_duration() {
+        filters.push(format!("fade=t=in:st=0:d={}", num(fade_in)));
+    }
+    if let Some(fade_out) = item.fade_out_duration() {
+        filters.push(format!("fade=t=out:st={}:d={}", num(duration - fade_out), num(fade_out)));
+    }
+    filters.extend(opacity_to_black_filter(item.opacity_keyframes()));
+    filters
+}
+
+/// Audio filter chain (without pads) that trims, normalizes, fades and
+/// applies volume keyframes to a clip
+fn clip_audio_filters(item: &TimelineItem, info: &MediaInfo) -> Vec<String> {
+    let duration = item.clip_duration();
+    let mut filters = vec!["asetpts=PTS-STARTPTS".to_string()];
+    if info.is_variable_frame_rate() {
+        filters.push(AUDIO_SYNC_FILTER.to_string());
+    }
+    filters.extend([
+        format!(
+            "aformat=sample_fmts=fltp:sample_rates={}:channel_layouts={}",
+            AUDIO_SAMPLE_RATE, AUDIO_LAYOUT
+        ),
+        format!("apad=whole_dur={}", num(duration)),
+        format!("atrim=duration={}", num(duration)),
+    ]);
+
+    if let Some(fade_in) = item.fade_in_duration() {
+        filters.push(format!("afade=t=in:st=0:d={}", num(fade_in)));
+    }
+    if let Some(fade_out) = item.fade_out_duration() {
+        filters.push(format!("afade=t=out:st={}:d={}", num(duration - fade_out), num(fade_out)));
+    }
+    filters.extend(volume_filter(item.volume_keyframes()));
+    filters
+}
+
+/// Trimmed, normalized video and audio for one main track clip
+fn clip_segment(
+    graph: &mut FilterGraph,
+    canvas: &Canvas,
+    item: &TimelineItem,
+    info: &MediaInfo,
+) -> Segment {
+    let input = graph.add_input(trim_input_options(item), &item.path);
+    let duration = item.clip_duration();
+
+    let video = graph.label("v");
+    if info.has_video() {
+        graph.push(format!(
+            "[{}:v:0]{}{}",
+            input, clip_video_filters(item, canvas).join(","), video
+        ));
+    } else {
+        // Audio-only clips render over black
+        graph.push(format!(
+            "color=c=black:s={}:r={}:d={},format=yuv420p,setsar=1{}",
+            canvas.size(), num(canvas.frame_rate), num(duration), video
+        ));
+    }
+
+    let audio = graph.label("a");
+    if info.has_audio() {
+        graph.push(format!("[{}:a:0]{}{}", input, clip_audio_filters(item, info).join(","), audio));
+    } else {
+        graph.push(format!(
+            "anullsrc=r={}:cl={},atrim=duration={}{}",
+            AUDIO_SAMPLE_RATE, AUDIO_LAYOUT, num(duration), audio
+        ));
+    }
+
+    Segment { video, audio }
+}
+
+/// Scaled, faded and time-shifted overlay clip composited onto `base`.
+///
+/// Returns the new base video pad, and the overlay's audio pad if its audio is included.
+fn composite_overlay(
+    graph: &mut FilterGraph,
+    canvas: &Canvas,
+    base: String,
+    item: &TimelineItem,
+    info: &MediaInfo,
+) -> (String, Option<String>) {
+    let props = item.overlay_properties.clone().unwrap_or_default();
+    let input = graph.add_input(trim_input_options(item), &item.path);
+    let duration = item.clip_duration();
+    let start = item.start_time;
+    let end = start + duration;
+
+    let mut overlay_audio = None;
+    if props.include_audio.unwrap_or(false) && info.has_audio() {
+        let audio = graph.label("oa");
+        let delay_ms = (start * 1000.0).round() as i64;
+        graph.push(format!(
+            "[{}:a:0]{},adelay={}:all=1{}",
+            input, clip_audio_filters(item, info).join(","), delay_ms, audio
+        ));
+        overlay_audio = Some(audio);
+    }
+
+    if !info.has_video() {
+        return (base, overlay_audio);
+    }
+
+    let width = even((canvas.width as f64 * props.scale_or_default()).round() as u32);
+    let mut filters = vec![
+        "setpts=PTS-STARTPTS".to_string(),
+        format!("scale={}:-2", width),
+        "setsar=1".to_string(),
+        conform_filter(canvas),
+        "format=yuva420p".to_string(),
+        format!("tpad=stop_mode=clone:stop_duration={}", num(duration)),
+        format!("trim=duration={}", num(duration)),
+    ];
+    // Fades act on the alpha channel so the main track shows through
+    if let Some(fade_in) = item.fade_in_duration() {
+        filters.push(format!("fade=t=in:st=0:d={}:alpha=1", num(fade_in)));
+    }
+    if let Some(fade_out) = item.fade_out_duration() {
+        filters.push(format!(
+            "fade=t=out:st={}:d={}:alpha=1",
+            num(duration - fade_out), num(fade_out)
+        ));
+    }
+    let opacity = props.opacity_or_default();
+    if opacity < 1.0 {
+        filters.push(format!("colorchannelmixer=aa={}", num(opacity)));
+    }
+    filters.extend(opacity_alpha_filter(item.opacity_keyframes()));
+    filters.push(format!("setpts=PTS+{}/TB", num(start)));
+
+    let overlay = graph.label("ov");
+    graph.push(format!("[{}:v:0]{}{}", input, filters.join(","), overlay));
+
+    let position = props.position_or_default();
+    let composited = graph.label("vc");
+    graph.push(format!(
+        "{}{}overlay=x={}:y={}:eof_action=pass:enable='between(t,{},{})'{}",
+        base,
+        overlay,
+        (position.x * canvas.width as f64).round() as i64,
+        (position.y * canvas.height as f64).round() as i64,
+        num(start),
+        num(end),
+        composited
+    ));
+
+    (composited, overlay_audio)
+}
+
+/// Compile a timeline into a single FFmpeg filter graph.
+///
+/// The main track is concatenated into a base layer (gaps become black and
+/// silence), then overlay clips are composited on top in z-order.
+/// `media` must contain probe results for every source path in the timeline.
+pub fn compile_timeline(
+    timeline: &Timeline,
+    media: &HashMap<String, MediaInfo>,
+) -> Result<RenderPlan, String> {
+    timeline.validate()?;
+
+    let lookup = |item: &TimelineItem| {
+        media
+            .get(&item.path)
+            .ok_or_else(|| format!("Missing media info for {}", item.path))
+    };
+
+    let canvas = Canvas::for_timeline(timeline, media);
+    let mut graph = FilterGraph::default();
+    let mut segments = Vec::new();
+    let mut cursor = 0.0;
+
+    for item in timeline.main_track_sorted() {
+        let info = lookup(item)?;
+
+        let gap = item.start_time - cursor;
+        if gap > GAP_EPSILON {
+            segments.push(gap_segment(&mut graph, &canvas, gap));
+            cursor += gap;
+        }
+
+        segments.push(clip_segment(&mut graph, &canvas, item, info));
+        cursor += item.clip_duration();
+    }
+
+    // Extend the base layer with black so overlays past the main track still render
+    let overlay_end = timeline
+        .overlay_track
+        .iter()
+        .map(|item| item.start_time + item.clip_duration())
+        .fold(0.0, f64::max);
+    if overlay_end - cursor > GAP_EPSILON {
+        segments.push(gap_segment(&mut graph, &canvas, overlay_end - cursor));
+        cursor = overlay_end;
+    }
+
+    let pads: String = segments
+        .iter()
+        .map(|s| format!("{}{}", s.video, s.audio))
+        .collect();
+    let main_video = graph.label("vmain");
+    let main_audio = graph.label("amain");
+    graph.push(format!(
+        "{}concat=n={}:v=1:a=1{}{}",
+        pads, segments.len(), main_video, main_audio
+    ));
+
+    let mut video_out = main_video;
+    let mut audio_pads = vec![main_audio];
+    for item in timeline.overlay_track_sorted() {
+        let info = lookup(item)?;
+        let (video, audio) = composite_overlay(&mut graph, &canvas, video_out, item, info);
+        video_out = video;
+        audio_pads.extend(audio);
+    }
+
+    let audio_out = if audio_pads.len() > 1 {
+        let mixed = graph.label("amix");
+        graph.push(format!(
+            "{}amix=inputs={}:duration=first:dropout_transition=0:normalize=0{}",
+            audio_pads.concat(), audio_pads.len(), mixed
+        ));
+        mixed
+    } else {
+        audio_pads.remove(0)
+    };
+
+    Ok(RenderPlan {
+        args: graph.into_args(),
+        video_out,
+        audio_out,
+        duration: cursor,
+    })
+}
+
+/// Probe every distinct source path referenced by the timeline
+pub fn probe_timeline_media(timeline: &Timeline) -> Result<HashMap<String, MediaInfo>, String> {
+    let mut media = HashMap::new();
+    for item in timeline.main_track.iter().chain(&timeline.overlay_track) {
+        if !media.contains_key(&item.path) {
+            let info = probe_media_info(&item.path)
+                .map_err(|e| format!("Failed to probe {}: {}", item.path, e))?;
+            media.insert(item.path.clone(), info);
+        }
+    }
+    Ok(media)
+}
+
+/// Export the whole timeline in one FFmpeg pass
+#[tauri::command]
+pub async fn export_timeline(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    timeline: Timeline,
+    output: String,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<i32, CommandError> {
+    policy.check_write(Path::new(&output))?;
+    let encode = resolve_encode_settings(&app, encode)?;
+
+    let job = start_job(&app, "export", job_id)?;
+    let result = export_timeline_internal(&job, &timeline, &output, &encode).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+async fn export_timeline_internal(
+    job: &Job,
+    timeline: &Timeline,
+    output: &str,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    let media = {
+        let timeline = timeline.clone();
+        tauri::async_runtime::spawn_blocking(move || probe_timeline_media(&timeline))
+            .await
+            .map_err(|e| format!("Failed to probe timeline media: {}", e))??
+    };
+    let plan = compile_timeline(timeline, &media)?;
+
+    let mut args = plan.args;
+    args.extend(vec![
+        "-map".to_string(), plan.video_out,
+        "-map".to_string(), plan.audio_out,
+    ]);
+    args.extend(encode.output_args());
+    args.extend(vec!["-y".to_string(), output.to_string()]);
+
+    job.run_ffmpeg("timeline export", &args, Some(output), Some(plan.duration)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::{StreamInfo, StreamKind};
+    use serde_json::json;
+
+    fn stream(kind: StreamKind) -> StreamInfo {
+        StreamInfo {
+            index: 0,
+            kind,
+            codec: None,
+            profile: None,
+            duration: None,
+            bit_rate: None,
+            timecode: None,
+            width: Some(1920),
+            height: Some(1080),
+            pixel_format: None,
+            frame_rate: None,
+            avg_frame_rate: None,
+            time_base: None,
+            rotation: 0,
+            variable_frame_rate: false,
+            sample_rate: None,
+            channels: None,
+            channel_layout: None,
+        }
+    }
+
+    /// Probe results for every path, each with one video and one audio stream
+    fn media(paths: &[&str]) -> HashMap<String, MediaInfo> {
+        paths
+            .iter()
+            .map(|path| {
+                let info = MediaInfo {
+                    path: path.to_string(),
+                    container: None,
+                    duration: Some(60.0),
+                    start_time: None,
+                    size: None,
+                    bit_rate: None,
+                    timecode: None,
+                    streams: vec![stream(StreamKind::Video), stream(StreamKind::Audio)],
+                };
+                (path.to_string(), info)
+            })
+            .collect()
+    }
+
+    /// A 1280x720, 30 fps timeline
+    fn timeline(main_track: serde_json::Value, overlay_track: serde_json::Value) -> Timeline {
+        serde_json::from_value(json!({
+            "mainTrack": main_track,
+            "overlayTrack": overlay_track,
+            "settings": { "resolution": { "width": 1280, "height": 720 }, "frameRate": 30 },
+        }))
+        .unwrap()
+    }
+
+    fn chains(plan: &RenderPlan) -> Vec<String> {
+        plan.args.last().unwrap().split(';').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn gaps_between_clips_become_black_and_silence() {
+        let timeline = timeline(
+            json!([
+                { "id": "b", "path": "b.mp4", "startTime": 3.0, "durationSec": 1.0 },
+                { "id": "a", "path": "a.mp4", "startTime": 0.0, "durationSec": 5.0, "inSec": 1.0, "outSec": 3.0 },
+            ]),
+            json!([]),
+        );
+        let plan = compile_timeline(&timeline, &media(&["a.mp4", "b.mp4"])).unwrap();
+
+        // Inputs follow timeline order and seek to each trim window
+        assert_eq!(
+            plan.args[..plan.args.len() - 2],
+            ["-ss", "1", "-t", "2", "-i", "a.mp4", "-ss", "0", "-t", "1", "-i", "b.mp4"]
+        );
+        assert_eq!(plan.args[plan.args.len() - 2], "-filter_complex");
+
+        let chains = chains(&plan);
+        assert!(chains[0].starts_with("[0:v:0]setpts=PTS-STARTPTS,scale=1280:720:force_original_aspect_ratio=decrease,"));
+        assert!(chains[0].ends_with(",fps=30,format=yuv420p,tpad=stop_mode=clone:stop_duration=2,trim=duration=2[v1]"));
+        assert_eq!(chains[2], "color=c=black:s=1280x720:r=30:d=1,format=yuv420p,setsar=1[gv3]");
+        assert_eq!(chains[3], "anullsrc=r=48000:cl=stereo,atrim=duration=1[ga4]");
+        assert_eq!(chains.last().unwrap(), "[v1][a2][gv3][ga4][v5][a6]concat=n=3:v=1:a=1[vmain7][amain8]");
+        assert_eq!((plan.video_out.as_str(), plan.audio_out.as_str()), ("[vmain7]", "[amain8]"));
+        assert_eq!(plan.duration, 4.0);
+    }
+
+    #[test]
+    fn fades_apply_to_video_and_audio() {
+        let fades = json!({
+            "fadeIn": { "type": "fade", "duration": 0.5, "enabled": true },
+            "fadeOut": { "type": "fade", "duration": 1.0, "enabled": true },
+        });
+        let timeline = timeline(
+            json!([{ "id": "a", "path": "a.mp4", "durationSec": 4.0, "fadePresets": fades }]),
+            json!([]),
+        );
+        let plan = compile_timeline(&timeline, &media(&["a.mp4"])).unwrap();
+
+        let chains = chains(&plan);
+        assert!(chains[0].ends_with("trim=duration=4,fade=t=in:st=0:d=0.5,fade=t=out:st=3:d=1[v1]"), "{}", chains[0]);
+        assert!(chains[1].ends_with("atrim=duration=4,afade=t=in:st=0:d=0.5,afade=t=out:st=3:d=1[a2]"), "{}", chains[1]);
+    }
+
+    #[test]
+    fn disabled_fades_are_skipped() {
+        let fades = json!({ "fadeIn": { "type": "fade", "duration": 0.5, "enabled": false } });
+        let timeline = timeline(
+            json!([{ "id": "a", "path": "a.mp4", "durationSec": 4.0, "fadePresets": fades }]),
+            json!([]),
+        );
+        let plan = compile_timeline(&timeline, &media(&["a.mp4"])).unwrap();
+        assert!(!plan.args.last().unwrap().contains("fade="));
+    }
+
+    #[test]
+    fn overlays_are_composited_and_extend_the_base() {
+        let timeline = timeline(
+            json!([{ "id": "a", "path": "a.mp4", "durationSec": 2.0 }]),
+            json!([{
+                "id": "o",
+                "path": "o.mp4",
+                "startTime": 1.0,
+                "durationSec": 3.0,
+                "fadePresets": { "fadeIn": { "type": "fade", "duration": 0.25, "enabled": true } },
+                "overlayProperties": {
+                    "position": { "x": 0.5, "y": 0.25 },
+                    "scale": 0.5,
+                    "opacity": 0.8,
+                    "includeAudio": true,
+                },
+            }]),
+        );
+        let plan = compile_timeline(&timeline, &media(&["a.mp4", "o.mp4"])).unwrap();
+
+        assert_eq!(plan.args[6..12], ["-ss", "0", "-t", "3", "-i", "o.mp4"]);
+        // The main track is padded with 2s of black to reach the overlay's end
+        assert_eq!(plan.duration, 4.0);
+
+        let chains = chains(&plan);
+        assert_eq!(chains[2], "color=c=black:s=1280x720:r=30:d=2,format=yuv420p,setsar=1[gv3]");
+        assert_eq!(chains[5], "[1:a:0]asetpts=PTS-STARTPTS,aformat=sample_fmts=fltp:sample_rates=48000:channel_layouts=stereo,\
+apad=whole_dur=3,atrim=duration=3,afade=t=in:st=0:d=0.25,adelay=1000:all=1[oa7]");
+        assert_eq!(chains[6], "[1:v:0]setpts=PTS-STARTPTS,scale=640:-2,setsar=1,fps=30,format=yuva420p,\
+tpad=stop_mode=clone:stop_duration=3,trim=duration=3,fade=t=in:st=0:d=0.25:alpha=1,colorchannelmixer=aa=0.8,\
+setpts=PTS+1/TB[ov8]");
+        assert_eq!(chains[7], "[vmain5][ov8]overlay=x=640:y=180:eof_action=pass:enable='between(t,1,4)'[vc9]");
+        assert_eq!(chains[8], "[amain6][oa7]amix=inputs=2:duration=first:dropout_transition=0:normalize=0[amix10]");
+        assert_eq!((plan.video_out.as_str(), plan.audio_out.as_str()), ("[vc9]", "[amix10]"));
+    }
+
+    #[test]
+    fn overlays_composite_in_z_order() {
+        let timeline = timeline(
+            json!([{ "id": "a", "path": "a.mp4", "durationSec": 5.0 }]),
+            json!([
+                { "id": "top", "path": "top.mp4", "durationSec": 1.0, "overlayProperties": { "zIndex": 2 } },
+                { "id": "bottom", "path": "bottom.mp4", "durationSec": 1.0, "overlayProperties": { "zIndex": 1 } },
+            ]),
+        );
+        let plan = compile_timeline(&timeline, &media(&["a.mp4", "top.mp4", "bottom.mp4"])).unwrap();
+
+        let bottom = plan.args.iter().position(|a| a == "bottom.mp4").unwrap();
+        let top = plan.args.iter().position(|a| a == "top.mp4").unwrap();
+        assert!(bottom < top);
+        // Without included audio the main track audio is the output
+        assert!(plan.audio_out.starts_with("[amain"));
+    }
+
+    #[test]
+    fn overlapping_main_track_clips_are_rejected() {
+        let timeline = timeline(
+            json!([
+                { "id": "a", "path": "a.mp4", "durationSec": 2.0 },
+                { "id": "b", "path": "b.mp4", "startTime": 1.0, "durationSec": 2.0 },
+            ]),
+            json!([]),
+        );
+        assert!(compile_timeline(&timeline, &media(&["a.mp4", "b.mp4"])).is_err());
+    }
+}
@@ -0,0 +1,400 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::jobs::{start_job, Job};
+use crate::media::{probe_media_info, MediaInfo};
+use crate::sandbox::{resolve_existing_prefix, CommandError, PathPolicy};
+
+// Matroska element IDs (with their length markers)
+const EBML_HEADER_ID: u64 = 0x1A45_DFA3;
+const SEGMENT_ID: u64 = 0x1853_8067;
+
+// Enough of the file to cover the EBML header and the Segment element header
+const HEADER_PROBE_BYTES: u64 = 4096;
+
+/// How a recording was rebuilt
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RepairMethod {
+    /// Stream copy into a fresh container (lossless, fast)
+    Remux,
+    /// Decoded and encoded again because the stream copy failed
+    Reencode,
+}
+
+/// What `repair_recording` found and recovered
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairReport {
+    pub input: String,
+    /// The repaired file, or the input itself when nothing needed repairing
+    pub output: String,
+    pub repaired: bool,
+    pub method: Option<RepairMethod>,
+    /// Problems detected in the input
+    pub issues: Vec<String>,
+    /// Problems left after the repair, e.g. a stream that could not be salvaged
+    pub warnings: Vec<String>,
+    /// Duration of the recovered file
+    pub duration: Option<f64>,
+    pub has_video: bool,
+    pub has_audio: bool,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+}
+
+/// Read an EBML variable-length integer. Element IDs keep their length
+/// marker; sizes drop it. Returns the value, its length in bytes and whether
+/// every value bit is set (an unknown size).
+fn read_vint(data: &[u8], keep_marker: bool) -> Option<(u64, usize, bool)> {
+    let first = *data.first()?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let bytes = data.get(..len)?;
+
+    let mut value = if keep_marker {
+        first as u64
+    } else {
+        first as u64 & (0xFF >> len)
+    };
+    for byte in &bytes[1..] {
+        value = (value << 8) | *byte as u64;
+    }
+    let all_ones = !keep_marker && value == (1u64 << (7 * len)) - 1;
+    Some((value, len, all_ones))
+}
+
+/// Whether a file starts with an EBML header, and whether its Segment has an
+/// unknown size, which is how live writers such as MediaRecorder leave files
+/// they never finalized. None if the file is not Matroska/WebM.
+fn segment_size_unknown(path: &Path) -> Result<Option<bool>, String> {
+    let mut header = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(HEADER_PROBE_BYTES).read_to_end(&mut header))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    if read_vint(&header, true).map(|(id, _, _)| id) != Some(EBML_HEADER_ID) {
+        return Ok(None);
+    }
+
+    let parse = || -> Option<bool> {
+        // The EBML header ID is 4 bytes
+        let (size, size_len, _) = read_vint(&header[4..], false)?;
+        let segment_start = usize::try_from(size).ok()?.checked_add(4 + size_len)?;
+        let segment = header.get(segment_start..)?;
+
+        let (id, id_len, _) = read_vint(segment, true)?;
+        if id != SEGMENT_ID {
+            return None;
+        }
+        let (_, _, unknown) = read_vint(&segment[id_len..], false)?;
+        Some(unknown)
+    };
+    parse()
+        .map(Some)
+        .ok_or_else(|| format!("Damaged Matroska header in {}", path.display()))
+}
+
+/// Problems that make a recording need repair; empty if it looks complete
+fn detect_issues(path: &Path) -> Result<(Vec<String>, Option<MediaInfo>), String> {
+    let mut issues = Vec::new();
+
+    match segment_size_unknown(path) {
+        Ok(None) => return Err(format!("Not a Matroska/WebM file: {}", path.display())),
+        Ok(Some(true)) => issues.push("Segment size is unknown (recording was not finalized)".to_string()),
+        Ok(Some(false)) => {}
+        Err(e) => issues.push(e),
+    }
+
+    let info = match probe_media_info(&path.to_string_lossy()) {
+        Ok(info) => {
+            if info.duration.is_none() {
+                issues.push("No duration".to_string());
+            }
+            if !info.has_video() && !info.has_audio() {
+                issues.push("No audio or video streams found".to_string());
+            }
+            Some(info)
+        }
+        Err(e) => {
+            issues.push(format!("Could not be probed: {}", e));
+            None
+        }
+    };
+
+    Ok((issues, info))
+}
+
+/// Encoder arguments for the re-encode fallback, by output container
+fn reencode_args(output: &Path) -> Vec<String> {
+    let webm = output
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("webm"));
+    if webm {
+        vec![
+            "-c:v".to_string(), "libvpx-vp9".to_string(),
+            "-deadline".to_string(), "realtime".to_string(),
+            "-cpu-used".to_string(), "8".to_string(),
+            "-b:v".to_string(), "0".to_string(),
+            "-crf".to_string(), "32".to_string(),
+            "-c:a".to_string(), "libopus".to_string(),
+        ]
+    } else {
+        vec![
+            "-c:v".to_string(), "libx264".to_string(),
+            "-crf".to_string(), "18".to_string(),
+            "-c:a".to_string(), "aac".to_string(),
+        ]
+    }
+}
+
+/// Rebuild a recording into `output`, trying a stream copy before a re-encode.
+///
+/// Corrupt packets are dropped and timestamps regenerated, so everything up to
+/// the point of truncation is kept. Why a stream copy failed goes to `warnings`.
+async fn rebuild(job: &Job, input: &str, output: &str, warnings: &mut Vec<String>) -> Result<RepairMethod, String> {
+    let mut args = vec![
+        "-fflags".to_string(), "+genpts+discardcorrupt".to_string(),
+        "-err_detect".to_string(), "ignore_err".to_string(),
+        "-i".to_string(), input.to_string(),
+        "-map".to_string(), "0:v?".to_string(),
+        "-map".to_string(), "0:a?".to_string(),
+    ];
+    let prefix_len = args.len();
+
+    args.extend([
+        "-c".to_string(), "copy".to_string(),
+        "-y".to_string(), output.to_string(),
+    ]);
+    let remux = job.run_ffmpeg("repair (remux)", &args, Some(output), None).await;
+    match remux {
+        Ok(_) => return Ok(RepairMethod::Remux),
+        Err(_) if job.is_cancelled() => return Err(job.cancelled_error()),
+        Err(e) => warnings.push(format!("Stream copy failed, so the recording was re-encoded: {}", e)),
+    }
+
+    args.truncate(prefix_len);
+    args.extend(reencode_args(Path::new(output)));
+    args.extend(["-y".to_string(), output.to_string()]);
+    job.run_ffmpeg("repair (re-encode)", &args, Some(output), None).await?;
+    Ok(RepairMethod::Reencode)
+}
+
+async fn repair_internal(
+    job: &Job,
+    input: &str,
+    output: &str,
+    force: bool,
+) -> Result<RepairReport, String> {
+    let input_path = Path::new(input);
+    let input_bytes = std::fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read {}: {}", input, e))?
+        .len();
+    let (issues, before) = {
+        let input_path = input_path.to_path_buf();
+        tauri::async_runtime::spawn_blocking(move || detect_issues(&input_path))
+            .await
+            .map_err(|e| format!("Failed to inspect recording: {}", e))??
+    };
+
+    if issues.is_empty() && !force {
+        let info = before.as_ref();
+        return Ok(RepairReport {
+            input: input.to_string(),
+            output: input.to_string(),
+            repaired: false,
+            method: None,
+            issues,
+            warnings: Vec::new(),
+            duration: info.and_then(|i| i.duration),
+            has_video: info.is_some_and(MediaInfo::has_video),
+            has_audio: info.is_some_and(MediaInfo::has_audio),
+            input_bytes,
+            output_bytes: input_bytes,
+        });
+    }
+
+    let mut warnings = Vec::new();
+    let method = rebuild(job, input, output, &mut warnings).await?;
+    let after = {
+        let output = output.to_string();
+        tauri::async_runtime::spawn_blocking(move || probe_media_info(&output))
+            .await
+            .map_err(|e| format!("Failed to probe repaired file: {}", e))??
+    };
+
+    if after.duration.is_none() {
+        warnings.push("Repaired file still has no duration".to_string());
+    }
+    if let Some(before) = &before {
+        if before.has_video() && !after.has_video() {
+            warnings.push("Video stream could not be recovered".to_string());
+        }
+        if before.has_audio() && !after.has_audio() {
+            warnings.push("Audio stream could not be recovered".to_string());
+        }
+    }
+
+    let output_bytes = std::fs::metadata(output)
+        .map_err(|e| format!("Failed to read {}: {}", output, e))?
+        .len();
+    Ok(RepairReport {
+        input: input.to_string(),
+        output: output.to_string(),
+        repaired: true,
+        method: Some(method),
+        issues,
+        warnings,
+        duration: after.duration,
+        has_video: after.has_video(),
+        has_audio: after.has_audio(),
+        input_bytes,
+        output_bytes,
+    })
+}
+
+/// `clip.webm` -> `clip.repaired.webm`
+fn default_output(input: &Path) -> String {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = input
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "webm".to_string());
+    input
+        .with_file_name(format!("{}.repaired.{}", stem, extension))
+        .display()
+        .to_string()
+}
+
+/// Check a Matroska/WebM recording for signs of an interrupted write (no
+/// duration, unfinalized segment) and, if found, remux it so it has a
+/// duration and seek index again.
+///
+/// Writes `output` (default `<name>.repaired.<ext>` next to the input); the
+/// input is never modified. With `force` the file is rebuilt even if it looks
+/// complete.
+#[tauri::command]
+pub async fn repair_recording(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    input: String,
+    output: Option<String>,
+    force: Option<bool>,
+    job_id: Option<String>,
+) -> Result<RepairReport, CommandError> {
+    let output = output.unwrap_or_else(|| default_output(Path::new(&input)));
+    policy.check_write(Path::new(&output))?;
+    // Compare with symlinks resolved, so an alias of the input is caught too
+    let same_file = resolve_existing_prefix(Path::new(&output))
+        .is_some_and(|output| Some(output) == resolve_existing_prefix(Path::new(&input)));
+    if same_file {
+        return Err("Repair output must differ from the input".to_string().into());
+    }
+
+    let job = start_job(&app, "repair", job_id)?;
+    let result = repair_internal(&job, &input, &output, force.unwrap_or(false)).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A file under the system temp dir, removed on drop
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("trimbot_repair_{}_{}", name, uuid::Uuid::new_v4()));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// EBML header (DocType "webm") followed by a Segment with the given size bytes
+    fn matroska(segment_size: &[u8]) -> Vec<u8> {
+        let mut data = vec![0x1A, 0x45, 0xDF, 0xA3, 0x87, 0x42, 0x82, 0x84, b'w', b'e', b'b', b'm'];
+        data.extend([0x18, 0x53, 0x80, 0x67]);
+        data.extend(segment_size);
+        data.extend([0x15, 0x49, 0xA9, 0x66, 0x80]);
+        data
+    }
+
+    #[test]
+    fn reads_sizes_without_the_marker() {
+        assert_eq!(read_vint(&[0x81], false), Some((1, 1, false)));
+        assert_eq!(read_vint(&[0x40, 0x02], false), Some((2, 2, false)));
+        assert_eq!(read_vint(&[0x21, 0x00, 0x00, 0xFF], false), Some((0x01_0000, 3, false)));
+    }
+
+    #[test]
+    fn reads_ids_with_the_marker() {
+        assert_eq!(read_vint(&[0x1A, 0x45, 0xDF, 0xA3], true), Some((EBML_HEADER_ID, 4, false)));
+        assert_eq!(read_vint(&[0x18, 0x53, 0x80, 0x67, 0x01], true), Some((SEGMENT_ID, 4, false)));
+        // All value bits set is only special for sizes
+        assert_eq!(read_vint(&[0xFF], true), Some((0xFF, 1, false)));
+    }
+
+    #[test]
+    fn flags_unknown_sizes() {
+        assert_eq!(read_vint(&[0xFF], false), Some((0x7F, 1, true)));
+        let eight = [0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert_eq!(read_vint(&eight, false), Some(((1u64 << 56) - 1, 8, true)));
+    }
+
+    #[test]
+    fn rejects_invalid_and_truncated_vints() {
+        assert_eq!(read_vint(&[], false), None);
+        // A zero first byte would need more than 8 bytes
+        assert_eq!(read_vint(&[0x00, 0x81], false), None);
+        assert_eq!(read_vint(&[0x40], false), None);
+        assert_eq!(read_vint(&[0x1A, 0x45, 0xDF], true), None);
+    }
+
+    #[test]
+    fn detects_an_unknown_segment_size() {
+        let one_byte = TempFile::new("unknown1", &matroska(&[0xFF]));
+        assert_eq!(segment_size_unknown(&one_byte.0), Ok(Some(true)));
+
+        let eight_bytes = TempFile::new("unknown8", &matroska(&[0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]));
+        assert_eq!(segment_size_unknown(&eight_bytes.0), Ok(Some(true)));
+    }
+
+    #[test]
+    fn accepts_a_known_segment_size() {
+        let file = TempFile::new("known", &matroska(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x12, 0x34]));
+        assert_eq!(segment_size_unknown(&file.0), Ok(Some(false)));
+    }
+
+    #[test]
+    fn ignores_files_that_are_not_matroska() {
+        let mp4 = TempFile::new("mp4", &[0x00, 0x00, 0x00, 0x20, b'f', b't', b'y', b'p', b'i', b's', b'o', b'm']);
+        assert_eq!(segment_size_unknown(&mp4.0), Ok(None));
+
+        let empty = TempFile::new("empty", &[]);
+        assert_eq!(segment_size_unknown(&empty.0), Ok(None));
+    }
+
+    #[test]
+    fn reports_a_truncated_header() {
+        let full = matroska(&[0xFF]);
+        // Cut inside the EBML header, and right after the Segment ID
+        for len in [6, 16] {
+            let file = TempFile::new("truncated", &full[..len]);
+            let result = segment_size_unknown(&file.0);
+            assert!(result.as_ref().is_err_and(|e| e.contains("Damaged")), "{} bytes: {:?}", len, result);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Why a path was refused
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DenyReason {
+    /// Relative paths are ambiguous; commands need absolute ones
+    NotAbsolute,
+    /// The path contains `..`
+    Traversal,
+    /// The path is outside the project and app data directories
+    OutsideSandbox,
+    /// The path is inside the sandbox but a symlink resolves outside it
+    SymlinkEscape,
+    /// A file name that is empty, `.`/`..` or contains a separator
+    InvalidFileName,
+}
+
+/// Error returned by sandboxed commands; serialized with a `kind` tag so the
+/// frontend can tell permission failures apart
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CommandError {
+    #[serde(rename_all = "camelCase")]
+    PermissionDenied {
+        path: String,
+        reason: DenyReason,
+        message: String,
+    },
+    /// An FFmpeg argument outside the `run_ffmpeg` allowlist
+    RejectedArgument {
+        argument: String,
+        message: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl CommandError {
+    fn denied(path: &Path, reason: DenyReason) -> Self {
+        let why = match reason {
+            DenyReason::NotAbsolute => "path is not absolute",
+            DenyReason::Traversal => "path contains '..'",
+            DenyReason::OutsideSandbox => "path is outside the project and app data directories",
+            DenyReason::SymlinkEscape => "path resolves outside the project and app data directories",
+            DenyReason::InvalidFileName => "not a plain file name",
+        };
+        CommandError::PermissionDenied {
+            path: path.display().to_string(),
+            reason,
+            message: format!("Permission denied for {}: {}", path.display(), why),
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::PermissionDenied { message, .. }
+            | CommandError::RejectedArgument { message, .. }
+            | CommandError::Failed { message } => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed { message }
+    }
+}
+
+/// A directory writes are allowed under, as given and with symlinks resolved
+#[derive(Clone, Debug)]
+struct Root {
+    path: PathBuf,
+    canonical: PathBuf,
+}
+
+impl Root {
+    fn new(path: &Path) -> Result<Self, String> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+        Ok(Root {
+            path: path.to_path_buf(),
+            canonical,
+        })
+    }
+}
+
+/// Resolve symlinks in the longest existing prefix of `path` and append the
+/// rest, so paths that do not exist yet can still be checked. None if the
+/// prefix ends in a dangling symlink, whose target cannot be checked.
+pub(crate) fn resolve_existing_prefix(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return Some(rest.iter().rev().fold(canonical, |acc, part| acc.join(part)));
+        }
+        if existing.symlink_metadata().is_ok() {
+            return None;
+        }
+        rest.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+}
+
+/// Check that `path` lies inside one of `roots`, returning it unchanged
+fn check_path(roots: &[Root], path: &Path) -> Result<PathBuf, CommandError> {
+    if !path.is_absolute() {
+        return Err(CommandError::denied(path, DenyReason::NotAbsolute));
+    }
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(CommandError::denied(path, DenyReason::Traversal));
+    }
+
+    let lexically_inside = roots
+        .iter()
+        .any(|root| path.starts_with(&root.path) || path.starts_with(&root.canonical));
+    if !lexically_inside {
+        return Err(CommandError::denied(path, DenyReason::OutsideSandbox));
+    }
+
+    let resolved = resolve_existing_prefix(path);
+    if !resolved.is_some_and(|resolved| roots.iter().any(|root| resolved.starts_with(&root.canonical))) {
+        return Err(CommandError::denied(path, DenyReason::SymlinkEscape));
+    }
+
+    Ok(path.to_path_buf())
+}
+
+/// Check that `name` is a single plain path component
+pub fn check_file_name(name: &str) -> Result<&str, CommandError> {
+    let mut components = Path::new(name).components();
+    let plain = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\']);
+    if plain {
+        Ok(name)
+    } else {
+        Err(CommandError::denied(Path::new(name), DenyReason::InvalidFileName))
+    }
+}
+
+/// Directories the webview may write to: the app data directory and the
+/// active project directory. Both may also be read, as may files the user
+/// picked in a dialog. Managed as Tauri state.
+#[derive(Default)]
+pub struct PathPolicy {
+    app_data: RwLock<Option<Root>>,
+    project: RwLock<Option<Root>>,
+    picked: RwLock<Vec<Root>>,
+}
+
+impl PathPolicy {
+    fn roots(&self) -> Vec<Root> {
+        let app_data = self.app_data.read().unwrap().clone();
+        let project = self.project.read().unwrap().clone();
+        app_data.into_iter().chain(project).collect()
+    }
+
+    /// Allow writes under the app data directory, creating it if needed
+    pub fn set_app_data_dir(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        *self.app_data.write().unwrap() = Some(Root::new(dir)?);
+        Ok(())
+    }
+
+    /// Make `dir` the active project directory, replacing the previous one
+    pub fn set_project_dir(&self, dir: &Path) -> Result<(), String> {
+        if !dir.is_absolute() {
+            return Err(format!("Project directory must be absolute: {}", dir.display()));
+        }
+        *self.project.write().unwrap() = Some(Root::new(dir)?);
+        Ok(())
+    }
+
+    /// Allow reads of a file or directory the user picked
+    pub fn allow_read(&self, path: &Path) -> Result<(), String> {
+        if !path.is_absolute() {
+            return Err(format!("Picked path must be absolute: {}", path.display()));
+        }
+        let root = Root::new(path)?;
+        let mut picked = self.picked.write().unwrap();
+        if !picked.iter().any(|p| p.canonical == root.canonical) {
+            picked.push(root);
+        }
+        Ok(())
+    }
+
+    /// Check that a command may write to `path`
+    pub fn check_write(&self, path: &Path) -> Result<PathBuf, CommandError> {
+        check_path(&self.roots(), path)
+    }
+
+    /// Check that a command may read `path`
+    pub fn check_read(&self, path: &Path) -> Result<PathBuf, CommandError> {
+        let mut roots = self.roots();
+        roots.extend(self.picked.read().unwrap().iter().cloned());
+        check_path(&roots, path)
+    }
+}
+
+/// Make `dir` the active project: writes are allowed under it and the asset
+/// protocol may serve files from it
+pub fn activate_project_dir(app: &AppHandle, dir: &Path) -> Result<(), String> {
+    app.state::<PathPolicy>().set_project_dir(dir)?;
+    app.asset_protocol_scope()
+        .allow_directory(dir, true)
+        .map_err(|e| format!("Failed to allow project directory: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("trimbot_sandbox_{}_{}", name, uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir.canonicalize().unwrap())
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn policy_for(project: &Path) -> PathPolicy {
+        let policy = PathPolicy::default();
+        policy.set_project_dir(project).unwrap();
+        policy
+    }
+
+    fn reason(result: Result<PathBuf, CommandError>) -> DenyReason {
+        match result {
+            Err(CommandError::PermissionDenied { reason, .. }) => reason,
+            other => panic!("expected a permission error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn allows_paths_inside_the_project() {
+        let project = TempDir::new("inside");
+        let policy = policy_for(&project.0);
+
+        assert!(policy.check_write(&project.0.join("media")).is_ok());
+        assert!(policy.check_write(&project.0.join(".temp/files.txt")).is_ok());
+        // Nothing below the project has to exist yet
+        assert!(policy.check_write(&project.0.join("a/b/c.mp4")).is_ok());
+    }
+
+    #[test]
+    fn rejects_parent_dir_traversal() {
+        let project = TempDir::new("traversal");
+        let policy = policy_for(&project.0);
+
+        let escape = project.0.join("media/../../.bashrc");
+        assert_eq!(reason(policy.check_write(&escape)), DenyReason::Traversal);
+        // Even when it would land back inside the project
+        let round_trip = project.0.join("media/../media/clip.mp4");
+        assert_eq!(reason(policy.check_write(&round_trip)), DenyReason::Traversal);
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_sandbox() {
+        let project = TempDir::new("outside");
+        let other = TempDir::new("other");
+        let policy = policy_for(&project.0);
+
+        assert_eq!(reason(policy.check_write(&other.0.join("x"))), DenyReason::OutsideSandbox);
+        assert_eq!(reason(policy.check_write(Path::new("/etc/passwd"))), DenyReason::OutsideSandbox);
+    }
+
+    #[test]
+    fn rejects_sibling_with_shared_prefix() {
+        let project = TempDir::new("prefix");
+        let sibling = PathBuf::from(format!("{}-evil", project.0.display()));
+        let policy = policy_for(&project.0);
+
+        assert_eq!(reason(policy.check_write(&sibling.join("x"))), DenyReason::OutsideSandbox);
+    }
+
+    #[test]
+    fn rejects_relative_paths() {
+        let project = TempDir::new("relative");
+        let policy = policy_for(&project.0);
+
+        assert_eq!(reason(policy.check_write(Path::new("media/clip.mp4"))), DenyReason::NotAbsolute);
+        assert_eq!(reason(policy.check_write(Path::new("../../.bashrc"))), DenyReason::NotAbsolute);
+    }
+
+    #[test]
+    fn denies_everything_without_roots() {
+        let project = TempDir::new("empty");
+        let policy = PathPolicy::default();
+
+        assert_eq!(reason(policy.check_write(&project.0.join("x"))), DenyReason::OutsideSandbox);
+    }
+
+    #[test]
+    fn allows_app_data_and_project_together() {
+        let project = TempDir::new("project");
+        let app_data = TempDir::new("app_data");
+        let policy = policy_for(&project.0);
+        policy.set_app_data_dir(&app_data.0).unwrap();
+
+        assert!(policy.check_write(&app_data.0.join("media/recording.webm")).is_ok());
+        assert!(policy.check_write(&project.0.join("exports/out.mp4")).is_ok());
+    }
+
+    #[test]
+    fn picked_files_are_readable_but_not_writable() {
+        let project = TempDir::new("read_project");
+        let footage = TempDir::new("footage");
+        let picked = footage.0.join("take1.mp4");
+        std::fs::write(&picked, b"").unwrap();
+        std::fs::write(footage.0.join("take2.mp4"), b"").unwrap();
+        let policy = policy_for(&project.0);
+        policy.allow_read(&picked).unwrap();
+
+        assert!(policy.check_read(&picked).is_ok());
+        assert!(policy.check_read(&project.0.join("media/a.mp4")).is_ok());
+        assert_eq!(reason(policy.check_write(&picked)), DenyReason::OutsideSandbox);
+        assert_eq!(reason(policy.check_read(&footage.0.join("take2.mp4"))), DenyReason::OutsideSandbox);
+    }
+
+    #[test]
+    fn switching_projects_revokes_the_old_one() {
+        let first = TempDir::new("first");
+        let second = TempDir::new("second");
+        let policy = policy_for(&first.0);
+        policy.set_project_dir(&second.0).unwrap();
+
+        assert_eq!(reason(policy.check_write(&first.0.join("x"))), DenyReason::OutsideSandbox);
+        assert!(policy.check_write(&second.0.join("x")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_escapes() {
+        let project = TempDir::new("symlink");
+        let outside = TempDir::new("symlink_target");
+        std::os::unix::fs::symlink(&outside.0, project.0.join("media")).unwrap();
+        std::os::unix::fs::symlink(outside.0.join("file.txt"), project.0.join("link.txt")).unwrap();
+        let policy = policy_for(&project.0);
+
+        let through_dir = project.0.join("media/clip.mp4");
+        assert_eq!(reason(policy.check_write(&through_dir)), DenyReason::SymlinkEscape);
+        assert_eq!(reason(policy.check_write(&project.0.join("media"))), DenyReason::SymlinkEscape);
+        // A dangling link to a file outside is caught too
+        assert_eq!(reason(policy.check_write(&project.0.join("link.txt"))), DenyReason::SymlinkEscape);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn allows_symlinks_that_stay_inside() {
+        let project = TempDir::new("symlink_inside");
+        std::fs::create_dir(project.0.join("real")).unwrap();
+        std::os::unix::fs::symlink(project.0.join("real"), project.0.join("alias")).unwrap();
+        let policy = policy_for(&project.0);
+
+        assert!(policy.check_write(&project.0.join("alias/clip.mp4")).is_ok());
+    }
+
+    #[test]
+    fn checks_file_names() {
+        assert!(check_file_name("recording.webm").is_ok());
+        assert!(check_file_name("clip (1).mp4").is_ok());
+        assert!(check_file_name(".hidden").is_ok());
+
+        for name in ["", ".", "..", "../../.bashrc", "media/clip.mp4", "..\\..\\x", "/etc/passwd", "a/"] {
+            match check_file_name(name) {
+                Err(CommandError::PermissionDenied { reason, .. }) => {
+                    assert_eq!(reason, DenyReason::InvalidFileName, "{:?}", name)
+                }
+                other => panic!("{:?} should be rejected, got {:?}", name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn permission_errors_serialize_with_a_kind_tag() {
+        let error = CommandError::denied(Path::new("/etc/passwd"), DenyReason::OutsideSandbox);
+        let json = serde_json::to_value(&error).unwrap();
+
+        assert_eq!(json["kind"], "permissionDenied");
+        assert_eq!(json["reason"], "outsideSandbox");
+        assert_eq!(json["path"], "/etc/passwd");
+        assert!(json["message"].as_str().unwrap().starts_with("Permission denied"));
+    }
+}
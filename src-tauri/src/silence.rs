@@ -0,0 +1,539 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::encode::{resolve_encode_settings, EncodeSettings};
+use crate::jobs::{start_job, Job};
+use crate::media::{probe_keyframes, probe_media_info, MediaInfo};
+use crate::render::num;
+use crate::sandbox::{CommandError, PathPolicy};
+use crate::trim::{plan_trim, run_smart_cut, run_trim, supports_smart_cut, TrimMode, TrimPlan, TrimSegment};
+
+// Defaults for `SilenceOptions`
+const DEFAULT_THRESHOLD_DB: f64 = -30.0;
+const DEFAULT_MIN_SILENCE_SEC: f64 = 0.5;
+const DEFAULT_PADDING_SEC: f64 = 0.1;
+
+// Speech ranges shorter than this after padding are dropped as clicks
+const MIN_SPEECH_SEC: f64 = 0.05;
+
+/// Parameters for `detect_silence` and `auto_trim_silence`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SilenceOptions {
+    /// Audio below this level counts as silence, in dBFS (default -30)
+    pub threshold_db: Option<f64>,
+    /// Shortest pause that is cut, in seconds (default 0.5)
+    pub min_duration: Option<f64>,
+    /// Seconds of silence kept on each side of speech (default 0.1)
+    pub padding: Option<f64>,
+    /// Source range to analyze, e.g. a clip's in and out points (default the whole file)
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl TimeRange {
+    fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Silent and speech ranges of a source, in source seconds
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SilenceAnalysis {
+    pub input: String,
+    /// The analyzed range
+    pub range: TimeRange,
+    /// Pauses as detected, without padding
+    pub silences: Vec<TimeRange>,
+    /// Ranges to keep: everything between the pauses, widened by the padding
+    pub speech: Vec<TimeRange>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JumpCut {
+    pub input: String,
+    /// The rendered file, when an output was requested
+    pub output: Option<String>,
+    /// Kept segments in source seconds, in order. When rendered, these are the
+    /// ranges written, which copy mode widens back to keyframes.
+    pub segments: Vec<TimeRange>,
+    pub original_duration: f64,
+    pub kept_duration: f64,
+}
+
+/// Parse `silence_start`/`silence_end` lines from FFmpeg's `silencedetect`
+/// log. Times are relative to the analyzed input; a pause still open at the
+/// end runs to `duration`.
+fn parse_silence_log(log: &str, duration: f64) -> Vec<TimeRange> {
+    let value = |line: &str, key: &str| -> Option<f64> {
+        let rest = &line[line.find(key)? + key.len()..];
+        rest.split_whitespace().next()?.parse::<f64>().ok()
+    };
+
+    let mut silences = Vec::new();
+    let mut open: Option<f64> = None;
+    for line in log.lines().filter(|line| line.contains("silencedetect")) {
+        if let Some(start) = value(line, "silence_start:") {
+            open = Some(start.max(0.0));
+        } else if let Some(end) = value(line, "silence_end:") {
+            let start = open.take().unwrap_or(0.0);
+            silences.push(TimeRange { start, end: end.min(duration) });
+        }
+    }
+    if let Some(start) = open {
+        silences.push(TimeRange { start, end: duration });
+    }
+    silences.retain(|s| s.duration() > 0.0);
+    silences
+}
+
+/// Ranges of `0..duration` outside `silences`, widened by `padding` on each
+/// side. Ranges that overlap after padding are merged.
+fn speech_ranges(silences: &[TimeRange], duration: f64, padding: f64) -> Vec<TimeRange> {
+    let mut speech: Vec<TimeRange> = Vec::new();
+    let mut cursor = 0.0;
+    let gaps = silences
+        .iter()
+        .map(|s| (s.start, s.end))
+        .chain(std::iter::once((duration, duration)));
+
+    for (silence_start, silence_end) in gaps {
+        if silence_start > cursor {
+            let range = TimeRange {
+                start: (cursor - padding).max(0.0),
+                end: (silence_start + padding).min(duration),
+            };
+            match speech.last_mut() {
+                Some(last) if range.start <= last.end => last.end = range.end,
+                _ => speech.push(range),
+            }
+        }
+        cursor = cursor.max(silence_end);
+    }
+
+    speech.retain(|s| s.duration() >= MIN_SPEECH_SEC);
+    speech
+}
+
+async fn detect_internal(job: &Job, input: &str, options: &SilenceOptions) -> Result<SilenceAnalysis, String> {
+    let info = {
+        let path = input.to_string();
+        tauri::async_runtime::spawn_blocking(move || probe_media_info(&path))
+            .await
+            .map_err(|e| format!("Failed to probe {}: {}", input, e))??
+    };
+    if !info.has_audio() {
+        return Err(format!("{} has no audio stream", input));
+    }
+
+    let file_duration = info.duration_seconds()?;
+    let start = options.start.unwrap_or(0.0).clamp(0.0, file_duration);
+    let end = options.end.unwrap_or(file_duration).clamp(start, file_duration);
+    let duration = end - start;
+    if duration <= 0.0 {
+        return Err("Invalid silence detection range".to_string());
+    }
+
+    let threshold = options.threshold_db.unwrap_or(DEFAULT_THRESHOLD_DB);
+    let min_duration = options.min_duration.unwrap_or(DEFAULT_MIN_SILENCE_SEC).max(0.01);
+    let padding = options.padding.unwrap_or(DEFAULT_PADDING_SEC).max(0.0);
+
+    let args = vec![
+        "-ss".to_string(), num(start),
+        "-i".to_string(), input.to_string(),
+        "-t".to_string(), num(duration),
+        "-map".to_string(), "0:a:0".to_string(),
+        "-vn".to_string(),
+        "-af".to_string(), format!("silencedetect=noise={}dB:d={}", num(threshold), num(min_duration)),
+        "-f".to_string(), "null".to_string(),
+        "-".to_string(),
+    ];
+    let log = job.run_ffmpeg_logged("silence detection", &args, Some(duration)).await?;
+
+    let shift = |r: TimeRange| TimeRange { start: r.start + start, end: r.end + start };
+    let silences = parse_silence_log(&log, duration);
+    let speech = speech_ranges(&silences, duration, padding);
+
+    Ok(SilenceAnalysis {
+        input: input.to_string(),
+        range: TimeRange { start, end },
+        silences: silences.into_iter().map(shift).collect(),
+        speech: speech.into_iter().map(shift).collect(),
+    })
+}
+
+/// Filter graph that cuts `segments` out of input 0 and concatenates them,
+/// producing `[v]` (when `has_video`) and `[a]`
+fn jump_cut_filter(segments: &[TimeRange], has_video: bool) -> String {
+    let mut chains = Vec::new();
+    let mut pads = String::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let (start, end) = (num(segment.start), num(segment.end));
+        if has_video {
+            chains.push(format!("[0:v:0]trim=start={start}:end={end},setpts=PTS-STARTPTS[v{i}]"));
+            pads.push_str(&format!("[v{i}]"));
+        }
+        chains.push(format!("[0:a:0]atrim=start={start}:end={end},asetpts=PTS-STARTPTS[a{i}]"));
+        pads.push_str(&format!("[a{i}]"));
+    }
+
+    let outputs = if has_video { "[v][a]" } else { "[a]" };
+    chains.push(format!(
+        "{}concat=n={}:v={}:a=1{}",
+        pads, segments.len(), u8::from(has_video), outputs
+    ));
+    chains.join(";")
+}
+
+/// Re-encode the kept segments in one pass with trim and concat filters, with
+/// codecs the output's container can hold
+async fn render_reencoded(
+    job: &Job,
+    input: &str,
+    output: &str,
+    segments: &[TimeRange],
+    info: &MediaInfo,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    let encode = encode.for_output(Path::new(output))?;
+    let has_video = info.has_video();
+    let mut args = vec![
+        "-i".to_string(), input.to_string(),
+        "-filter_complex".to_string(), jump_cut_filter(segments, has_video),
+    ];
+    if has_video {
+        args.extend(vec!["-map".to_string(), "[v]".to_string()]);
+    }
+    args.extend(vec!["-map".to_string(), "[a]".to_string()]);
+    args.extend(encode.output_args());
+    args.extend(vec!["-y".to_string(), output.to_string()]);
+
+    let total: f64 = segments.iter().map(TimeRange::duration).sum();
+    job.run_ffmpeg("jump cut", &args, Some(output), Some(total)).await
+}
+
+/// Stream copy each kept segment and join the parts with the concat demuxer.
+/// Used for copy mode on sources the smart cut machinery cannot handle; the
+/// parts all come from the same stream, so they share parameter sets.
+async fn render_copied(
+    job: &Job,
+    input: &str,
+    output: &str,
+    plans: &[TrimPlan],
+    info: &MediaInfo,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    let temp_dir = std::env::temp_dir().join(format!("trimbot_jumpcut_{}", job.id()));
+    std::fs::create_dir_all(&temp_dir)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+
+    let result = async {
+        let extension = Path::new(output)
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_else(|| "mp4".to_string());
+        let mut parts: Vec<PathBuf> = Vec::new();
+        for (i, plan) in plans.iter().enumerate() {
+            let part = temp_dir.join(format!("part_{}.{}", i, extension));
+            let part_str = part.to_string_lossy().to_string();
+            run_trim(job, input, &part_str, plan, Some(info), encode).await?;
+            parts.push(part);
+        }
+
+        let list_path = temp_dir.join("segments.txt");
+        let list: String = parts
+            .iter()
+            .map(|p| format!("file '{}'\n", p.to_string_lossy()))
+            .collect();
+        std::fs::write(&list_path, list)
+            .map_err(|e| format!("Failed to write segment list: {}", e))?;
+
+        let total: f64 = plans.iter().map(|plan| plan.end - plan.start).sum();
+        let args = vec![
+            "-f".to_string(), "concat".to_string(),
+            "-safe".to_string(), "0".to_string(),
+            "-i".to_string(), list_path.to_string_lossy().to_string(),
+            "-c".to_string(), "copy".to_string(),
+            "-movflags".to_string(), "+faststart".to_string(),
+            "-y".to_string(), output.to_string(),
+        ];
+        job.run_ffmpeg("jump cut join", &args, Some(output), Some(total)).await
+    }
+    .await;
+
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    result
+}
+
+/// Plan a trim of each kept segment. Copy mode moves starts back to a
+/// keyframe, which can run a part into the one before it; those are planned
+/// as one part so no footage is repeated.
+fn plan_segments(segments: &[TimeRange], mode: TrimMode, keyframes: &[f64], smart_cut_supported: bool) -> Vec<TrimPlan> {
+    // Each plan with the start that was asked for
+    let mut plans: Vec<(f64, TrimPlan)> = Vec::new();
+    for segment in segments {
+        let plan = plan_trim(segment.start, segment.end, mode, keyframes, smart_cut_supported);
+        match plans.last_mut() {
+            Some((start, last)) if plan.start < last.end => {
+                *last = plan_trim(*start, segment.end, mode, keyframes, smart_cut_supported);
+            }
+            _ => plans.push((segment.start, plan)),
+        }
+    }
+    plans.into_iter().map(|(_, plan)| plan).collect()
+}
+
+/// Render the kept segments to `output` and return the ranges actually
+/// written. Re-encode is a single pass and exact; copy and smart cut produce
+/// their parts through the smart cut path so copied and encoded parts join
+/// cleanly.
+async fn render_jump_cut(
+    job: &Job,
+    input: &str,
+    output: &str,
+    segments: &[TimeRange],
+    mode: TrimMode,
+    encode: &EncodeSettings,
+) -> Result<Vec<TimeRange>, String> {
+    // Probe once for all segments instead of once per trim
+    let path = input.to_string();
+    let (info, keyframes) = tauri::async_runtime::spawn_blocking(move || {
+        let info = probe_media_info(&path)?;
+        let keyframes = if mode == TrimMode::Reencode { Vec::new() } else { probe_keyframes(&path)? };
+        Ok::<_, String>((info, keyframes))
+    })
+    .await
+    .map_err(|e| format!("Failed to probe {}: {}", input, e))??;
+
+    let smart_cut_supported = supports_smart_cut(&info);
+    if mode == TrimMode::Reencode || (mode == TrimMode::SmartCut && !smart_cut_supported) {
+        render_reencoded(job, input, output, segments, &info, encode).await?;
+        return Ok(segments.to_vec());
+    }
+
+    let plans = plan_segments(segments, mode, &keyframes, smart_cut_supported);
+    let kept = plans.iter().map(|plan| TimeRange { start: plan.start, end: plan.end }).collect();
+    if smart_cut_supported {
+        let parts: Vec<TrimSegment> = plans.into_iter().flat_map(|plan| plan.segments).collect();
+        run_smart_cut(job, input, output, &parts, &info).await?;
+    } else {
+        render_copied(job, input, output, &plans, &info, encode).await?;
+    }
+    Ok(kept)
+}
+
+async fn auto_trim_internal(
+    job: &Job,
+    input: &str,
+    output: Option<&str>,
+    options: &SilenceOptions,
+    mode: TrimMode,
+    encode: &EncodeSettings,
+) -> Result<JumpCut, String> {
+    let analysis = detect_internal(job, input, options).await?;
+    if analysis.speech.is_empty() {
+        return Err(format!("No speech found in {}", input));
+    }
+
+    let segments = match output {
+        Some(output) => render_jump_cut(job, input, output, &analysis.speech, mode, encode).await?,
+        None => analysis.speech,
+    };
+
+    Ok(JumpCut {
+        input: input.to_string(),
+        output: output.map(str::to_string),
+        kept_duration: segments.iter().map(TimeRange::duration).sum(),
+        original_duration: analysis.range.duration(),
+        segments,
+    })
+}
+
+/// Find pauses in a file's audio with FFmpeg's `silencedetect` and return
+/// them along with the speech ranges between them
+#[tauri::command]
+pub async fn detect_silence(
+    app: AppHandle,
+    input: String,
+    options: Option<SilenceOptions>,
+    job_id: Option<String>,
+) -> Result<SilenceAnalysis, CommandError> {
+    let options = options.unwrap_or_default();
+    let job = start_job(&app, "silence", job_id)?;
+    let result = detect_internal(&job, &input, &options).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+/// Cut the pauses out of a clip (a jump cut).
+///
+/// Returns the kept segments in source time, which the timeline can turn into
+/// clips. With `output` the jump cut is also rendered with `trim_mode`
+/// (default re-encode, which is frame accurate and done in a single pass);
+/// re-encoded output uses `encode`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn auto_trim_silence(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    input: String,
+    output: Option<String>,
+    options: Option<SilenceOptions>,
+    trim_mode: Option<TrimMode>,
+    encode: Option<EncodeSettings>,
+    job_id: Option<String>,
+) -> Result<JumpCut, CommandError> {
+    if let Some(output) = &output {
+        policy.check_write(Path::new(output))?;
+    }
+
+    let options = options.unwrap_or_default();
+    let encode = resolve_encode_settings(&app, encode)?;
+    let job = start_job(&app, "jump-cut", job_id)?;
+    let mode = trim_mode.unwrap_or_default();
+    let result = auto_trim_internal(&job, &input, output.as_deref(), &options, mode, &encode).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: f64, end: f64) -> TimeRange {
+        TimeRange { start, end }
+    }
+
+    #[test]
+    fn parses_silence_pairs_and_ignores_other_lines() {
+        let log = "\
+Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'talk.mp4':
+[silencedetect @ 0x55d0c8] silence_start: 1.5
+[silencedetect @ 0x55d0c8] silence_end: 2.25 | silence_duration: 0.75
+size=N/A time=00:00:04.00 bitrate=N/A speed= 200x
+[silencedetect @ 0x55d0c8] silence_start: 3
+[silencedetect @ 0x55d0c8] silence_end: 3.5 | silence_duration: 0.5
+";
+        assert_eq!(parse_silence_log(log, 10.0), vec![range(1.5, 2.25), range(3.0, 3.5)]);
+    }
+
+    #[test]
+    fn open_silence_runs_to_the_end() {
+        let log = "\
+[silencedetect @ 0x1] silence_start: 1
+[silencedetect @ 0x1] silence_end: 2 | silence_duration: 1
+[silencedetect @ 0x1] silence_start: 8.5
+";
+        assert_eq!(parse_silence_log(log, 10.0), vec![range(1.0, 2.0), range(8.5, 10.0)]);
+    }
+
+    #[test]
+    fn leading_silence_starts_at_zero() {
+        // A negative start is reported for silence at the very beginning, and
+        // with some builds the start line is missing altogether
+        let log = "\
+[silencedetect @ 0x1] silence_start: -0.0213
+[silencedetect @ 0x1] silence_end: 1.2 | silence_duration: 1.2213
+";
+        assert_eq!(parse_silence_log(log, 10.0), vec![range(0.0, 1.2)]);
+
+        let log = "[silencedetect @ 0x1] silence_end: 0.8 | silence_duration: 0.8\n";
+        assert_eq!(parse_silence_log(log, 10.0), vec![range(0.0, 0.8)]);
+    }
+
+    #[test]
+    fn silence_end_is_clamped_to_the_duration() {
+        let log = "\
+[silencedetect @ 0x1] silence_start: 4
+[silencedetect @ 0x1] silence_end: 5.02 | silence_duration: 1.02
+";
+        assert_eq!(parse_silence_log(log, 5.0), vec![range(4.0, 5.0)]);
+    }
+
+    #[test]
+    fn speech_is_the_padded_complement_of_silence() {
+        let speech = speech_ranges(&[range(2.0, 4.0), range(6.0, 7.0)], 10.0, 0.1);
+        assert_eq!(speech.len(), 3);
+        assert_eq!(speech[0], range(0.0, 2.1));
+        assert!((speech[1].start - 3.9).abs() < 1e-9 && (speech[1].end - 6.1).abs() < 1e-9);
+        assert!((speech[2].start - 6.9).abs() < 1e-9 && speech[2].end == 10.0);
+    }
+
+    #[test]
+    fn speech_ranges_merge_when_padding_overlaps() {
+        // The 0.15s pause is shorter than the padding on both sides
+        let speech = speech_ranges(&[range(1.0, 1.15)], 5.0, 0.1);
+        assert_eq!(speech, vec![range(0.0, 5.0)]);
+    }
+
+    #[test]
+    fn speech_ranges_skip_leading_and_trailing_silence() {
+        let speech = speech_ranges(&[range(0.0, 2.0), range(8.0, 10.0)], 10.0, 0.25);
+        assert_eq!(speech, vec![range(1.75, 8.25)]);
+    }
+
+    #[test]
+    fn speech_ranges_drop_clicks_and_all_silence() {
+        let speech = speech_ranges(&[range(0.0, 5.0), range(5.02, 10.0)], 10.0, 0.0);
+        assert!(speech.is_empty());
+
+        let speech = speech_ranges(&[range(0.0, 10.0)], 10.0, 0.5);
+        assert!(speech.is_empty());
+    }
+
+    #[test]
+    fn speech_ranges_without_silence_cover_everything() {
+        assert_eq!(speech_ranges(&[], 3.0, 0.1), vec![range(0.0, 3.0)]);
+    }
+
+    #[test]
+    fn jump_cut_filter_concatenates_segments() {
+        let filter = jump_cut_filter(&[range(0.5, 2.0), range(3.0, 4.25)], true);
+        assert_eq!(
+            filter,
+            "[0:v:0]trim=start=0.5:end=2,setpts=PTS-STARTPTS[v0];\
+[0:a:0]atrim=start=0.5:end=2,asetpts=PTS-STARTPTS[a0];\
+[0:v:0]trim=start=3:end=4.25,setpts=PTS-STARTPTS[v1];\
+[0:a:0]atrim=start=3:end=4.25,asetpts=PTS-STARTPTS[a1];\
+[v0][a0][v1][a1]concat=n=2:v=1:a=1[v][a]"
+        );
+
+        let filter = jump_cut_filter(&[range(0.0, 1.0)], false);
+        assert_eq!(filter, "[0:a:0]atrim=start=0:end=1,asetpts=PTS-STARTPTS[a0];[a0]concat=n=1:v=0:a=1[a]");
+    }
+
+    fn planned(plans: &[TrimPlan]) -> Vec<(f64, f64)> {
+        plans.iter().map(|plan| (plan.start, plan.end)).collect()
+    }
+
+    #[test]
+    fn copied_segments_snapped_into_each_other_are_merged() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0, 8.0];
+        let segments = [range(0.5, 2.5), range(3.0, 3.5), range(4.5, 5.0), range(6.0, 7.0)];
+
+        let plans = plan_segments(&segments, TrimMode::Copy, &keyframes, false);
+        // 3.0 snaps back to 2.0, inside the first part; 6.0 is already a keyframe
+        assert_eq!(planned(&plans), [(0.0, 3.5), (4.0, 5.0), (6.0, 7.0)]);
+        for pair in plans.windows(2) {
+            assert!(pair[1].start >= pair[0].end);
+        }
+    }
+
+    #[test]
+    fn frame_accurate_segments_are_planned_as_detected() {
+        let keyframes = [0.0, 2.0, 4.0];
+        let segments = [range(0.5, 2.5), range(3.0, 3.5)];
+        for mode in [TrimMode::SmartCut, TrimMode::Reencode] {
+            let plans = plan_segments(&segments, mode, &keyframes, true);
+            assert_eq!(planned(&plans), [(0.5, 2.5), (3.0, 3.5)], "{:?}", mode);
+        }
+    }
+}
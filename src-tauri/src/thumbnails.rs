@@ -0,0 +1,442 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::fingerprint::partial_hash;
+use crate::fs::write_file_atomic;
+use crate::jobs::{start_job, Job};
+use crate::media::{probe_media_info, MediaInfo};
+use crate::render::num;
+use crate::sandbox::{CommandError, PathPolicy};
+
+// Defaults for `ThumbnailOptions`
+const DEFAULT_HEIGHT: u32 = 90;
+const DEFAULT_FILMSTRIP_COUNT: u32 = 10;
+const DEFAULT_SPRITE_COLUMNS: u32 = 10;
+const DEFAULT_SPRITE_INTERVAL_SEC: f64 = 1.0;
+
+const MIN_HEIGHT: u32 = 16;
+const MAX_HEIGHT: u32 = 720;
+const MAX_FILMSTRIP_COUNT: u32 = 100;
+
+// The sprite interval is widened so long media stays under this many tiles
+const MAX_SPRITE_FRAMES: u32 = 400;
+
+// Largest sprite sheet width or height; JPEG stores 16-bit dimensions and
+// libjpeg stops a little short of 65535
+const JPEG_MAX_DIMENSION: u32 = 65500;
+
+// Poster frame position as a fraction of the duration, skipping fade-ins
+const POSTER_POSITION: f64 = 0.1;
+const MAX_POSTER_TIME_SEC: f64 = 5.0;
+
+// JPEG quality for `-q:v` (2 is best, 31 worst)
+const JPEG_QUALITY: &str = "4";
+
+/// What `generate_thumbnails` produces
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailOptions {
+    /// Height of every image in pixels; width follows the display aspect (default 90)
+    pub height: Option<u32>,
+    /// Number of evenly spaced filmstrip frames (default 10, 0 for none)
+    pub filmstrip_count: Option<u32>,
+    /// Also build a sprite sheet for scrubbing previews
+    #[serde(default)]
+    pub sprite: bool,
+    /// Seconds between sprite frames (default 1, widened for long media)
+    pub sprite_interval: Option<f64>,
+    /// Tiles per sprite sheet row (default 10)
+    pub sprite_columns: Option<u32>,
+}
+
+/// One extracted frame
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thumbnail {
+    pub time: f64,
+    pub path: String,
+}
+
+/// Position of one frame in a sprite sheet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteFrame {
+    pub time: f64,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// A grid of frames in a single image, with the JSON index written next to it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteSheet {
+    pub image: String,
+    pub index: String,
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub interval: f64,
+    pub frames: Vec<SpriteFrame>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailSet {
+    pub source: String,
+    /// Partial content hash the cache is keyed by
+    pub hash: String,
+    pub cache_dir: String,
+    pub width: u32,
+    pub height: u32,
+    pub duration: Option<f64>,
+    pub poster: Thumbnail,
+    pub filmstrip: Vec<Thumbnail>,
+    pub sprite: Option<SpriteSheet>,
+    /// Every image was already in the cache
+    pub cached: bool,
+}
+
+/// Cache folder for one source: `<project>/.temp/thumbnails/<hash>`
+fn cache_dir(project_dir: &Path, hash: &str) -> PathBuf {
+    project_dir.join(".temp").join("thumbnails").join(hash)
+}
+
+/// Width matching the display aspect at `height`, rounded to an even number
+fn scaled_width(info: &MediaInfo, height: u32) -> Result<u32, String> {
+    let (w, h) = info
+        .video_stream()
+        .and_then(|v| v.display_size())
+        .filter(|(w, h)| *w > 0 && *h > 0)
+        .ok_or_else(|| format!("{} has no video stream with a known size", info.path))?;
+    let width = (height as f64 * w as f64 / h as f64 / 2.0).round() as u32 * 2;
+    Ok(width.max(2))
+}
+
+/// Times of `count` evenly spaced frames, each centered in its slice
+fn filmstrip_times(duration: f64, count: u32) -> Vec<f64> {
+    (0..count)
+        .map(|i| duration * (i as f64 + 0.5) / count as f64)
+        .collect()
+}
+
+/// Seconds between sprite frames, widened so `duration` fits in
+/// `MAX_SPRITE_FRAMES` tiles
+fn sprite_interval(duration: f64, requested: Option<f64>) -> f64 {
+    let requested = requested
+        .filter(|i| i.is_finite() && *i > 0.0)
+        .unwrap_or(DEFAULT_SPRITE_INTERVAL_SEC);
+    requested.max(duration / MAX_SPRITE_FRAMES as f64)
+}
+
+/// Lay out `count` frames `interval` seconds apart in rows of up to `columns`
+/// tiles, adjusting the columns so the sheet stays within
+/// `JPEG_MAX_DIMENSION` both ways. Returns the grid size and each frame's
+/// position.
+fn sprite_layout(
+    count: u32,
+    columns: u32,
+    interval: f64,
+    tile_width: u32,
+    tile_height: u32,
+) -> (u32, u32, Vec<SpriteFrame>) {
+    let max_columns = (JPEG_MAX_DIMENSION / tile_width.max(1)).max(1);
+    let max_rows = (JPEG_MAX_DIMENSION / tile_height.max(1)).max(1);
+    // Frames that fit in neither direction are dropped from the end
+    let count = count.min(max_columns * max_rows);
+    let columns = columns
+        .max(count.div_ceil(max_rows))
+        .min(max_columns)
+        .min(count)
+        .max(1);
+    let rows = count.div_ceil(columns).max(1);
+    let frames = (0..count)
+        .map(|i| SpriteFrame {
+            time: i as f64 * interval,
+            x: (i % columns) * tile_width,
+            y: (i / columns) * tile_height,
+        })
+        .collect();
+    (columns, rows, frames)
+}
+
+/// Extract the frame at `time` into `path`, unless it is already cached.
+/// Returns whether a new image was written.
+async fn extract_frame(
+    job: &Job,
+    input: &str,
+    time: f64,
+    scale: &str,
+    path: &Path,
+) -> Result<bool, String> {
+    if path.exists() {
+        return Ok(false);
+    }
+
+    // Written under a temporary name so an interrupted run never leaves a
+    // partial image that later looks cached
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.{}.jpg", file_name, job.id()));
+    let temp = temp_path.display().to_string();
+
+    let args = vec![
+        "-ss".to_string(), num(time),
+        "-i".to_string(), input.to_string(),
+        "-map".to_string(), "0:v:0".to_string(),
+        "-frames:v".to_string(), "1".to_string(),
+        "-vf".to_string(), scale.to_string(),
+        "-q:v".to_string(), JPEG_QUALITY.to_string(),
+        "-y".to_string(), temp.clone(),
+    ];
+    job.run_ffmpeg("thumbnail", &args, Some(&temp), None).await?;
+
+    if !temp_path.exists() {
+        return Err(format!("No frame found at {}s in {}", num(time), input));
+    }
+    std::fs::rename(&temp_path, path)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+/// Build the sprite sheet and its JSON index, unless the index is already
+/// cached. Returns the sheet and whether it was newly written.
+#[allow(clippy::too_many_arguments)]
+async fn build_sprite(
+    job: &Job,
+    input: &str,
+    dir: &Path,
+    duration: f64,
+    tile_width: u32,
+    tile_height: u32,
+    interval: f64,
+    columns: u32,
+) -> Result<(SpriteSheet, bool), String> {
+    let key = format!("sprite-h{}-i{}-c{}", tile_height, num(interval), columns);
+    let image_path = dir.join(format!("{}.jpg", key));
+    let index_path = dir.join(format!("{}.json", key));
+
+    // The index is written last, so it marks a complete sheet
+    if let Ok(contents) = std::fs::read_to_string(&index_path) {
+        if let Ok(mut sheet) = serde_json::from_str::<SpriteSheet>(&contents) {
+            if image_path.exists() {
+                // The project may have moved since the index was written
+                sheet.image = image_path.display().to_string();
+                sheet.index = index_path.display().to_string();
+                return Ok((sheet, false));
+            }
+        }
+    }
+
+    let count = ((duration / interval).ceil() as u32).max(1);
+    let (columns, rows, frames) = sprite_layout(count, columns, interval, tile_width, tile_height);
+
+    let temp_path = dir.join(format!(".{}.{}.jpg", key, job.id()));
+    let temp = temp_path.display().to_string();
+    let filter = format!(
+        "fps=1/{},scale={}:{},setsar=1,tile={}x{}",
+        num(interval), tile_width, tile_height, columns, rows
+    );
+    let args = vec![
+        "-i".to_string(), input.to_string(),
+        "-map".to_string(), "0:v:0".to_string(),
+        "-vf".to_string(), filter,
+        "-frames:v".to_string(), "1".to_string(),
+        "-q:v".to_string(), JPEG_QUALITY.to_string(),
+        "-y".to_string(), temp.clone(),
+    ];
+    job.run_ffmpeg("sprite sheet", &args, Some(&temp), Some(duration)).await?;
+    std::fs::rename(&temp_path, &image_path)
+        .map_err(|e| format!("Failed to write {}: {}", image_path.display(), e))?;
+
+    let sheet = SpriteSheet {
+        image: image_path.display().to_string(),
+        index: index_path.display().to_string(),
+        columns,
+        rows,
+        tile_width,
+        tile_height,
+        interval,
+        frames,
+    };
+    let json = serde_json::to_string_pretty(&sheet)
+        .map_err(|e| format!("Failed to serialize sprite index: {}", e))?;
+    write_file_atomic(&index_path, json.as_bytes())?;
+    Ok((sheet, true))
+}
+
+async fn generate_internal(
+    job: &Job,
+    project_dir: &Path,
+    input: &str,
+    options: &ThumbnailOptions,
+) -> Result<ThumbnailSet, String> {
+    let (hash, info) = {
+        let path = input.to_string();
+        tauri::async_runtime::spawn_blocking(move || {
+            Ok::<_, String>((partial_hash(Path::new(&path))?, probe_media_info(&path)?))
+        })
+        .await
+        .map_err(|e| format!("Failed to inspect {}: {}", input, e))??
+    };
+
+    let height = options.height.unwrap_or(DEFAULT_HEIGHT).clamp(MIN_HEIGHT, MAX_HEIGHT);
+    let width = scaled_width(&info, height)?;
+    let scale = format!("scale={}:{},setsar=1", width, height);
+
+    let dir = cache_dir(project_dir, &hash);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create directory {}: {}", dir.display(), e))?;
+
+    // Still images and broken files have no duration; they get a poster only
+    let duration = info.duration.filter(|d| d.is_finite() && *d > 0.0);
+    let mut cached = true;
+
+    let poster_time = duration
+        .map(|d| (d * POSTER_POSITION).min(MAX_POSTER_TIME_SEC))
+        .unwrap_or(0.0);
+    let poster_path = dir.join(format!("poster-h{}.jpg", height));
+    cached &= !extract_frame(job, input, poster_time, &scale, &poster_path).await?;
+    let poster = Thumbnail {
+        time: poster_time,
+        path: poster_path.display().to_string(),
+    };
+
+    let mut filmstrip = Vec::new();
+    if let Some(duration) = duration {
+        let count = options
+            .filmstrip_count
+            .unwrap_or(DEFAULT_FILMSTRIP_COUNT)
+            .min(MAX_FILMSTRIP_COUNT);
+        let strip_dir = dir.join(format!("filmstrip-h{}-n{}", height, count));
+        if count > 0 {
+            std::fs::create_dir_all(&strip_dir)
+                .map_err(|e| format!("Failed to create directory {}: {}", strip_dir.display(), e))?;
+        }
+        for (i, time) in filmstrip_times(duration, count).into_iter().enumerate() {
+            let path = strip_dir.join(format!("{:03}.jpg", i));
+            cached &= !extract_frame(job, input, time, &scale, &path).await?;
+            filmstrip.push(Thumbnail {
+                time,
+                path: path.display().to_string(),
+            });
+        }
+    }
+
+    let sprite = match duration {
+        Some(duration) if options.sprite => {
+            let interval = sprite_interval(duration, options.sprite_interval);
+            let columns = options.sprite_columns.unwrap_or(DEFAULT_SPRITE_COLUMNS).max(1);
+            let (sheet, written) =
+                build_sprite(job, input, &dir, duration, width, height, interval, columns).await?;
+            cached &= !written;
+            Some(sheet)
+        }
+        _ => None,
+    };
+
+    Ok(ThumbnailSet {
+        source: input.to_string(),
+        hash,
+        cache_dir: dir.display().to_string(),
+        width,
+        height,
+        duration,
+        poster,
+        filmstrip,
+        sprite,
+        cached,
+    })
+}
+
+/// Extract a poster frame and an evenly spaced filmstrip for a media file,
+/// plus a sprite sheet with a JSON index when `options.sprite` is set.
+///
+/// Images are cached under `<project>/.temp/thumbnails/<hash>`, keyed by the
+/// file's partial hash and the options, so each is only generated once (also
+/// after the file is moved or renamed).
+#[tauri::command]
+pub async fn generate_thumbnails(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    project_dir: String,
+    path: String,
+    options: Option<ThumbnailOptions>,
+    job_id: Option<String>,
+) -> Result<ThumbnailSet, CommandError> {
+    let project_dir = Path::new(&project_dir);
+    policy.check_write(&project_dir.join(".temp").join("thumbnails"))?;
+
+    let options = options.unwrap_or_default();
+    let job = start_job(&app, "thumbnails", job_id)?;
+    let result = generate_internal(&job, project_dir, &path, &options).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filmstrip_frames_are_centered_in_their_slices() {
+        assert_eq!(filmstrip_times(8.0, 4), vec![1.0, 3.0, 5.0, 7.0]);
+        assert_eq!(filmstrip_times(3.0, 1), vec![1.5]);
+        assert!(filmstrip_times(10.0, 0).is_empty());
+    }
+
+    #[test]
+    fn sprite_interval_defaults_and_ignores_invalid_values() {
+        assert_eq!(sprite_interval(60.0, None), DEFAULT_SPRITE_INTERVAL_SEC);
+        assert_eq!(sprite_interval(60.0, Some(2.5)), 2.5);
+        assert_eq!(sprite_interval(60.0, Some(0.0)), DEFAULT_SPRITE_INTERVAL_SEC);
+        assert_eq!(sprite_interval(60.0, Some(-1.0)), DEFAULT_SPRITE_INTERVAL_SEC);
+        assert_eq!(sprite_interval(60.0, Some(f64::NAN)), DEFAULT_SPRITE_INTERVAL_SEC);
+    }
+
+    #[test]
+    fn sprite_interval_widens_for_long_media() {
+        // Two hours at one frame a second would be 7200 tiles
+        let interval = sprite_interval(7200.0, Some(1.0));
+        assert_eq!(interval, 7200.0 / MAX_SPRITE_FRAMES as f64);
+        assert!((7200.0 / interval).ceil() as u32 <= MAX_SPRITE_FRAMES);
+    }
+
+    #[test]
+    fn sprite_layout_fills_rows() {
+        let (columns, rows, frames) = sprite_layout(7, 3, 2.0, 160, 90);
+        assert_eq!((columns, rows), (3, 3));
+        assert_eq!(frames.len(), 7);
+        let last = &frames[6];
+        assert_eq!((last.time, last.x, last.y), (12.0, 0, 180));
+        let fifth = &frames[4];
+        assert_eq!((fifth.x, fifth.y), (160, 90));
+    }
+
+    #[test]
+    fn sprite_layout_uses_one_row_for_few_frames() {
+        let (columns, rows, frames) = sprite_layout(4, 10, 1.0, 160, 90);
+        assert_eq!((columns, rows), (4, 1));
+        assert_eq!(frames.iter().map(|f| f.x).collect::<Vec<_>>(), vec![0, 160, 320, 480]);
+    }
+
+    #[test]
+    fn sprite_layout_keeps_the_sheet_within_jpeg_limits() {
+        // 100 columns of 1280px tiles would be 128000px wide
+        let (columns, rows, _) = sprite_layout(400, 100, 1.0, 1280, 720);
+        assert!(columns * 1280 <= JPEG_MAX_DIMENSION, "{} columns", columns);
+        assert!(rows * 720 <= JPEG_MAX_DIMENSION, "{} rows", rows);
+
+        // A single column of 400 tall tiles would be 288000px high
+        let (columns, rows, frames) = sprite_layout(400, 1, 1.0, 160, 720);
+        assert!(rows * 720 <= JPEG_MAX_DIMENSION, "{} rows", rows);
+        assert_eq!(frames.len(), 400);
+        assert!(columns * rows >= 400);
+    }
+
+    #[test]
+    fn sprite_layout_drops_frames_that_cannot_fit() {
+        let (columns, rows, frames) = sprite_layout(400, 10, 1.0, 16000, 16000);
+        assert_eq!((columns, rows), (4, 4));
+        assert_eq!(frames.len(), 16);
+    }
+}
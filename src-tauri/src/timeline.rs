@@ -0,0 +1,394 @@
+use serde::{Deserialize, Serialize};
+
+// Minimum clip length the renderer will accept; shorter than the 0.2s split
+// minimum because a trimmed clip may legitimately be a few frames long
+pub const MIN_CLIP_SECONDS: f64 = 0.05;
+
+// Main track clips may overlap by this much (rounding in the frontend)
+const OVERLAP_EPSILON: f64 = 0.001;
+
+/// Keyframe on a clip property; `time_sec` is relative to the clip start
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipKeyframe {
+    #[serde(default)]
+    pub id: String,
+    pub time_sec: f64,
+    #[serde(default)]
+    pub property: String,
+    pub value: f64,
+    #[serde(default)]
+    pub interpolation: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyframeSet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<Vec<ClipKeyframe>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<Vec<ClipKeyframe>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FadePreset {
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    pub duration: f64,
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FadePresets {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_in: Option<FadePreset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_out: Option<FadePreset>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayProperties {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_audio: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z_index: Option<i32>,
+}
+
+// Defaults used by the overlay inspector when a property is unset
+pub const DEFAULT_OVERLAY_POSITION: Position = Position { x: 0.7, y: 0.05 };
+pub const DEFAULT_OVERLAY_SCALE: f64 = 0.3;
+
+impl OverlayProperties {
+    /// Top-left corner as a fraction of the canvas size
+    pub fn position_or_default(&self) -> Position {
+        self.position.unwrap_or(DEFAULT_OVERLAY_POSITION)
+    }
+
+    /// Overlay width as a fraction of the canvas width
+    pub fn scale_or_default(&self) -> f64 {
+        self.scale
+            .filter(|s| s.is_finite() && *s > 0.0)
+            .unwrap_or(DEFAULT_OVERLAY_SCALE)
+    }
+
+    pub fn opacity_or_default(&self) -> f64 {
+        self.opacity
+            .filter(|o| o.is_finite())
+            .map(|o| o.clamp(0.0, 1.0))
+            .unwrap_or(1.0)
+    }
+}
+
+/// How a clip whose aspect ratio differs from the canvas is fitted onto it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FitMode {
+    /// Scale to fit inside the canvas and pad with black bars
+    #[default]
+    Letterbox,
+    /// Scale to cover the canvas and crop the overflow
+    Crop,
+    /// Scale to the canvas size, ignoring the aspect ratio
+    Stretch,
+}
+
+/// How clips are converted to the project frame rate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FrameRateConversion {
+    /// Duplicate or drop frames (fast)
+    #[default]
+    Duplicate,
+    /// Synthesize in-between frames with motion interpolation (slow)
+    Interpolate,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackType {
+    #[default]
+    Main,
+    Overlay,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemType {
+    #[default]
+    Video,
+    Audio,
+}
+
+/// A clip placed on the timeline, mirroring the frontend `TimelineItem`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineItem {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub duration_sec: f64,
+    #[serde(default)]
+    pub start_time: f64,
+    #[serde(default)]
+    pub x_px: f64,
+    #[serde(default)]
+    pub w_px: f64,
+    #[serde(rename = "type", default)]
+    pub item_type: ItemType,
+    pub path: String,
+    #[serde(default)]
+    pub selected: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_sec: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub out_sec: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyframes: Option<KeyframeSet>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit_mode: Option<FitMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_presets: Option<FadePresets>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_type: Option<TrackType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlay_properties: Option<OverlayProperties>,
+}
+
+impl TimelineItem {
+    /// Start of the trim window within the source media
+    pub fn source_in(&self) -> f64 {
+        self.in_sec.unwrap_or(0.0).max(0.0)
+    }
+
+    /// End of the trim window within the source media
+    pub fn source_out(&self) -> f64 {
+        self.out_sec
+            .unwrap_or_else(|| self.source_in() + self.duration_sec)
+    }
+
+    /// Length of the clip on the timeline
+    pub fn clip_duration(&self) -> f64 {
+        (self.source_out() - self.source_in()).max(0.0)
+    }
+
+    /// Opacity keyframes, empty when the clip has none
+    pub fn opacity_keyframes(&self) -> &[ClipKeyframe] {
+        self.keyframes
+            .as_ref()
+            .and_then(|k| k.opacity.as_deref())
+            .unwrap_or(&[])
+    }
+
+    /// Volume keyframes, empty when the clip has none
+    pub fn volume_keyframes(&self) -> &[ClipKeyframe] {
+        self.keyframes
+            .as_ref()
+            .and_then(|k| k.volume.as_deref())
+            .unwrap_or(&[])
+    }
+
+    /// Enabled fade-in duration, clamped to the clip length
+    pub fn fade_in_duration(&self) -> Option<f64> {
+        let preset = self.fade_presets.as_ref()?.fade_in.as_ref()?;
+        enabled_fade(preset, self.clip_duration())
+    }
+
+    /// Enabled fade-out duration, clamped to the clip length
+    pub fn fade_out_duration(&self) -> Option<f64> {
+        let preset = self.fade_presets.as_ref()?.fade_out.as_ref()?;
+        enabled_fade(preset, self.clip_duration())
+    }
+}
+
+fn enabled_fade(preset: &FadePreset, clip_duration: f64) -> Option<f64> {
+    if preset.enabled && preset.duration > 0.0 {
+        Some(preset.duration.min(clip_duration))
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Project-wide output settings, mirroring the frontend `ProjectSettings`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSettings {
+    pub resolution: Resolution,
+    pub frame_rate: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_rate_conversion: Option<FrameRateConversion>,
+    #[serde(default)]
+    pub output_format: String,
+}
+
+/// The full timeline as serialized by the frontend
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeline {
+    #[serde(default, alias = "timeline")]
+    pub main_track: Vec<TimelineItem>,
+    #[serde(default)]
+    pub overlay_track: Vec<TimelineItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<ProjectSettings>,
+}
+
+impl ProjectSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.resolution.width == 0 || self.resolution.height == 0 {
+            return Err("Project resolution must be non-zero".to_string());
+        }
+        if !self.frame_rate.is_finite() || self.frame_rate <= 0.0 {
+            return Err("Project frame rate must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Timeline {
+    /// Check trim windows, durations and the main track layout before rendering
+    pub fn validate(&self) -> Result<(), String> {
+        if self.main_track.is_empty() && self.overlay_track.is_empty() {
+            return Err("Timeline is empty".to_string());
+        }
+        self.validate_clips()?;
+
+        // The main track is concatenated, so its clips cannot overlap
+        for pair in self.main_track_sorted().windows(2) {
+            let end = pair[0].start_time + pair[0].clip_duration();
+            if pair[1].start_time < end - OVERLAP_EPSILON {
+                return Err(format!(
+                    "Clips {} and {} overlap on the main track",
+                    pair[0].id, pair[1].id
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check every clip and the settings; an empty timeline is accepted
+    pub fn validate_clips(&self) -> Result<(), String> {
+        for item in self.main_track.iter().chain(&self.overlay_track) {
+            if item.path.trim().is_empty() {
+                return Err(format!("Clip {} has no source path", item.id));
+            }
+            if !item.source_in().is_finite() || !item.source_out().is_finite() {
+                return Err(format!("Clip {} has an invalid trim window", item.id));
+            }
+            if item.clip_duration() < MIN_CLIP_SECONDS {
+                return Err(format!("Clip {} is too short to render", item.id));
+            }
+            if !item.start_time.is_finite() || item.start_time < 0.0 {
+                return Err(format!("Clip {} has an invalid start time", item.id));
+            }
+        }
+
+        if let Some(settings) = &self.settings {
+            settings.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Overlay clips in compositing order: lowest z-index first, then by position
+    pub fn overlay_track_sorted(&self) -> Vec<&TimelineItem> {
+        let mut items: Vec<&TimelineItem> = self.overlay_track.iter().collect();
+        items.sort_by(|a, b| {
+            let za = a.overlay_properties.as_ref().and_then(|p| p.z_index).unwrap_or(0);
+            let zb = b.overlay_properties.as_ref().and_then(|p| p.z_index).unwrap_or(0);
+            za.cmp(&zb).then(a.start_time.total_cmp(&b.start_time))
+        });
+        items
+    }
+
+    /// Main track clips ordered by timeline position
+    pub fn main_track_sorted(&self) -> Vec<&TimelineItem> {
+        let mut items: Vec<&TimelineItem> = self.main_track.iter().collect();
+        items.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn clip(id: &str, start_time: f64, duration_sec: f64) -> serde_json::Value {
+        json!({ "id": id, "path": format!("/media/{}.mp4", id), "startTime": start_time, "durationSec": duration_sec })
+    }
+
+    fn timeline(main_track: Vec<serde_json::Value>) -> Timeline {
+        serde_json::from_value(json!({ "mainTrack": main_track })).unwrap()
+    }
+
+    #[test]
+    fn accepts_adjacent_and_spaced_clips() {
+        let adjacent = timeline(vec![clip("a", 0.0, 2.0), clip("b", 2.0, 1.0)]);
+        assert!(adjacent.validate().is_ok());
+
+        // Out of order in the list, with a gap
+        let spaced = timeline(vec![clip("b", 5.0, 1.0), clip("a", 0.0, 2.0)]);
+        assert!(spaced.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_overlapping_main_track_clips() {
+        let overlapping = timeline(vec![clip("a", 0.0, 2.0), clip("b", 1.5, 1.0)]);
+        let error = overlapping.validate().unwrap_err();
+        assert!(error.contains("overlap"), "{}", error);
+
+        // The trim window decides the length, not `durationSec`
+        let mut trimmed = clip("c", 0.0, 10.0);
+        trimmed["inSec"] = json!(1.0);
+        trimmed["outSec"] = json!(4.0);
+        assert!(timeline(vec![trimmed.clone(), clip("d", 3.0, 1.0)]).validate().is_ok());
+        assert!(timeline(vec![trimmed, clip("d", 2.5, 1.0)]).validate().is_err());
+    }
+
+    #[test]
+    fn tolerates_rounding_at_clip_boundaries() {
+        let rounded = timeline(vec![clip("a", 0.0, 2.0), clip("b", 1.9995, 1.0)]);
+        assert!(rounded.validate().is_ok());
+    }
+
+    #[test]
+    fn overlays_may_overlap_the_main_track() {
+        let mut timeline = timeline(vec![clip("a", 0.0, 2.0)]);
+        timeline.overlay_track = vec![serde_json::from_value(clip("o", 1.0, 2.0)).unwrap()];
+        assert!(timeline.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_an_empty_timeline() {
+        assert!(Timeline::default().validate().is_err());
+        assert!(Timeline::default().validate_clips().is_ok());
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+/// Video transition between two adjacent clips
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transition {
+    /// Hard cut, no overlap
+    Cut,
+    #[default]
+    Fade,
+    Dissolve,
+    FadeToBlack,
+    FadeToWhite,
+    FadeGrays,
+    WipeLeft,
+    WipeRight,
+    WipeUp,
+    WipeDown,
+    SlideLeft,
+    SlideRight,
+    SlideUp,
+    SlideDown,
+    SmoothLeft,
+    SmoothRight,
+    SmoothUp,
+    SmoothDown,
+    CircleOpen,
+    CircleClose,
+    CircleCrop,
+    RectCrop,
+    Radial,
+    Pixelize,
+    Distance,
+    HorizontalBlur,
+}
+
+impl Transition {
+    /// Name of the matching FFmpeg `xfade` transition; None for a hard cut
+    pub fn xfade_name(&self) -> Option<&'static str> {
+        Some(match self {
+            Transition::Cut => return None,
+            Transition::Fade => "fade",
+            Transition::Dissolve => "dissolve",
+            Transition::FadeToBlack => "fadeblack",
+            Transition::FadeToWhite => "fadewhite",
+            Transition::FadeGrays => "fadegrays",
+            Transition::WipeLeft => "wipeleft",
+            Transition::WipeRight => "wiperight",
+            Transition::WipeUp => "wipeup",
+            Transition::WipeDown => "wipedown",
+            Transition::SlideLeft => "slideleft",
+            Transition::SlideRight => "slideright",
+            Transition::SlideUp => "slideup",
+            Transition::SlideDown => "slidedown",
+            Transition::SmoothLeft => "smoothleft",
+            Transition::SmoothRight => "smoothright",
+            Transition::SmoothUp => "smoothup",
+            Transition::SmoothDown => "smoothdown",
+            Transition::CircleOpen => "circleopen",
+            Transition::CircleClose => "circleclose",
+            Transition::CircleCrop => "circlecrop",
+            Transition::RectCrop => "rectcrop",
+            Transition::Radial => "radial",
+            Transition::Pixelize => "pixelize",
+            Transition::Distance => "distance",
+            Transition::HorizontalBlur => "hblur",
+        })
+    }
+}
+
+/// Gain curve used by `acrossfade` for the outgoing and incoming audio
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AudioCurve {
+    #[default]
+    Linear,
+    /// Quarter sine; keeps perceived loudness constant across the fade
+    EqualPower,
+    HalfSine,
+    ExponentialSine,
+    Logarithmic,
+    Exponential,
+    Quadratic,
+    Cubic,
+    SquareRoot,
+    CubeRoot,
+}
+
+impl AudioCurve {
+    /// Name of the matching FFmpeg `acrossfade` curve
+    pub fn ffmpeg_name(&self) -> &'static str {
+        match self {
+            AudioCurve::Linear => "tri",
+            AudioCurve::EqualPower => "qsin",
+            AudioCurve::HalfSine => "hsin",
+            AudioCurve::ExponentialSine => "esin",
+            AudioCurve::Logarithmic => "log",
+            AudioCurve::Exponential => "exp",
+            AudioCurve::Quadratic => "qua",
+            AudioCurve::Cubic => "cub",
+            AudioCurve::SquareRoot => "squ",
+            AudioCurve::CubeRoot => "cbr",
+        }
+    }
+}
+
+/// Transition settings for one boundary between adjacent clips
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionSpec {
+    #[serde(default)]
+    pub transition: Transition,
+    /// Transition length in seconds; falls back to the export default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub audio_curve: AudioCurve,
+}
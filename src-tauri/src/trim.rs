@@ -0,0 +1,423 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::encode::EncodeSettings;
+use crate::jobs::Job;
+use crate::media::{probe_keyframes, probe_media_info, MediaInfo};
+use crate::render::num;
+
+// Cut points closer than this to a keyframe are treated as on the keyframe
+const KEYFRAME_EPSILON: f64 = 0.001;
+
+/// How a trim is performed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrimMode {
+    /// Re-encode the whole range (frame accurate, slow)
+    #[default]
+    Reencode,
+    /// Stream copy from the last keyframe at or before the start (instant,
+    /// lossless; may include a little footage before the start)
+    Copy,
+    /// Re-encode only the partial GOPs at the cut points and stream copy the rest
+    SmartCut,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SegmentMethod {
+    Copy,
+    Encode,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimSegment {
+    pub start: f64,
+    pub end: f64,
+    pub method: SegmentMethod,
+}
+
+/// Resolved cut: the actual range written and how each part is produced
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimPlan {
+    pub mode: TrimMode,
+    pub start: f64,
+    pub end: f64,
+    pub segments: Vec<TrimSegment>,
+}
+
+impl TrimPlan {
+    fn encode_all(mode: TrimMode, start: f64, end: f64) -> Self {
+        TrimPlan {
+            mode,
+            start,
+            end,
+            segments: vec![TrimSegment { start, end, method: SegmentMethod::Encode }],
+        }
+    }
+}
+
+/// Plan a trim of `start..end` given the source's sorted keyframe times.
+///
+/// `smart_cut_supported` says whether re-encoded parts can be joined losslessly
+/// with copied parts; when false, smart cut falls back to a full re-encode.
+pub fn plan_trim(
+    start: f64,
+    end: f64,
+    mode: TrimMode,
+    keyframes: &[f64],
+    smart_cut_supported: bool,
+) -> TrimPlan {
+    match mode {
+        TrimMode::Reencode => TrimPlan::encode_all(mode, start, end),
+        TrimMode::Copy => {
+            // Snapping forward would drop requested footage, so always snap back
+            let snapped = keyframes
+                .iter()
+                .copied()
+                .rfind(|k| *k <= start + KEYFRAME_EPSILON)
+                .map_or(start, |k| k.min(start));
+            TrimPlan {
+                mode,
+                start: snapped,
+                end,
+                segments: vec![TrimSegment { start: snapped, end, method: SegmentMethod::Copy }],
+            }
+        }
+        TrimMode::SmartCut => {
+            if !smart_cut_supported {
+                return TrimPlan::encode_all(mode, start, end);
+            }
+
+            let first_key = keyframes.iter().copied().find(|k| *k >= start - KEYFRAME_EPSILON);
+            let last_key = keyframes.iter().copied().rev().find(|k| *k <= end + KEYFRAME_EPSILON);
+
+            let (first_key, last_key) = match (first_key, last_key) {
+                (Some(first), Some(last)) if last > first + KEYFRAME_EPSILON => (first, last.min(end)),
+                // The range sits inside a single GOP; nothing can be copied
+                _ => return TrimPlan::encode_all(mode, start, end),
+            };
+
+            let mut segments = Vec::new();
+            if first_key - start > KEYFRAME_EPSILON {
+                segments.push(TrimSegment { start, end: first_key, method: SegmentMethod::Encode });
+            }
+            segments.push(TrimSegment { start: first_key.max(start), end: last_key, method: SegmentMethod::Copy });
+            if end - last_key > KEYFRAME_EPSILON {
+                segments.push(TrimSegment { start: last_key, end, method: SegmentMethod::Encode });
+            }
+
+            TrimPlan { mode, start, end, segments }
+        }
+    }
+}
+
+/// Whether re-encoded segments can be concatenated with stream-copied ones
+pub fn supports_smart_cut(info: &MediaInfo) -> bool {
+    let video_ok = info
+        .video_stream()
+        .and_then(|v| v.codec.as_deref())
+        .is_some_and(|codec| matches!(codec, "h264" | "hevc"));
+    let audio_ok = info
+        .audio_stream()
+        .map(|a| a.codec.as_deref() == Some("aac"))
+        .unwrap_or(true);
+    video_ok && audio_ok
+}
+
+/// Plan a trim, probing the source and its keyframes only when the mode needs them
+pub fn plan_trim_for_file(
+    input: &str,
+    start: f64,
+    end: f64,
+    mode: TrimMode,
+) -> Result<(TrimPlan, Option<MediaInfo>), String> {
+    if end - start <= 0.0 {
+        return Err("Invalid trim duration".to_string());
+    }
+
+    if mode == TrimMode::Reencode {
+        return Ok((plan_trim(start, end, mode, &[], false), None));
+    }
+
+    let info = probe_media_info(input)?;
+    let keyframes = probe_keyframes(input)?;
+    let plan = plan_trim(start, end, mode, &keyframes, supports_smart_cut(&info));
+    Ok((plan, Some(info)))
+}
+
+/// Encoder arguments that reproduce the source's video/audio parameters closely
+/// enough for the concat demuxer to join encoded and copied segments.
+/// Container options go on the final join (see `smart_cut_join_args`).
+fn matching_encode_args(info: &MediaInfo) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if let Some(video) = info.video_stream() {
+        let encoder = if video.codec.as_deref() == Some("hevc") { "libx265" } else { "libx264" };
+        args.extend(["-c:v".to_string(), encoder.to_string(), "-crf".to_string(), "18".to_string()]);
+        if let Some(pix_fmt) = &video.pixel_format {
+            args.extend(["-pix_fmt".to_string(), pix_fmt.clone()]);
+        }
+        if encoder == "libx264" {
+            let profile = match video.profile.as_deref().map(str::to_ascii_lowercase).as_deref() {
+                Some("constrained baseline") | Some("baseline") => Some("baseline"),
+                Some("main") => Some("main"),
+                Some("high") => Some("high"),
+                _ => None,
+            };
+            if let Some(profile) = profile {
+                args.extend(["-profile:v".to_string(), profile.to_string()]);
+            }
+        }
+    }
+
+    if let Some(audio) = info.audio_stream() {
+        args.extend(["-c:a".to_string(), "aac".to_string()]);
+        if let Some(rate) = audio.sample_rate {
+            args.extend(["-ar".to_string(), rate.to_string()]);
+        }
+        if let Some(channels) = audio.channels {
+            args.extend(["-ac".to_string(), channels.to_string()]);
+        }
+    }
+
+    args
+}
+
+/// Bitstream filter that moves parameter sets in-band, so each smart cut part
+/// carries its own SPS/PPS instead of relying on the container's
+fn annexb_filter(info: &MediaInfo) -> &'static str {
+    match info.video_stream().and_then(|v| v.codec.as_deref()) {
+        Some("hevc") => "hevc_mp4toannexb",
+        _ => "h264_mp4toannexb",
+    }
+}
+
+/// Output arguments for joining the MPEG-TS parts back into the final file
+fn smart_cut_join_args(info: &MediaInfo) -> Vec<String> {
+    let mut args = vec![
+        "-c".to_string(), "copy".to_string(),
+        "-bsf:a".to_string(), "aac_adtstoasc".to_string(),
+    ];
+    if let Some(video) = info.video_stream() {
+        // Parts encoded by us and copied from the source have different
+        // parameter sets; `hev1` allows them in-band, `hvc1` does not
+        if video.codec.as_deref() == Some("hevc") {
+            args.extend(["-tag:v".to_string(), "hev1".to_string()]);
+        }
+        if let Some(time_base) = video.time_base.filter(|tb| tb.num == 1) {
+            args.extend(["-video_track_timescale".to_string(), time_base.den.to_string()]);
+        }
+    }
+    args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    args
+}
+
+/// Execute a trim plan as part of a job, writing `output`. A fully re-encoded
+/// trim uses `encode`; smart cut encodes to match the copied source instead.
+pub async fn run_trim(
+    job: &Job,
+    input: &str,
+    output: &str,
+    plan: &TrimPlan,
+    info: Option<&MediaInfo>,
+    encode: &EncodeSettings,
+) -> Result<i32, String> {
+    let duration = plan.end - plan.start;
+
+    match plan.segments.as_slice() {
+        [segment] if segment.method == SegmentMethod::Copy => {
+            let args = vec![
+                "-ss".to_string(), num(segment.start),
+                "-i".to_string(), input.to_string(),
+                "-t".to_string(), num(segment.end - segment.start),
+                "-map".to_string(), "0".to_string(),
+                "-c".to_string(), "copy".to_string(),
+                "-avoid_negative_ts".to_string(), "make_zero".to_string(),
+                "-movflags".to_string(), "+faststart".to_string(),
+                "-y".to_string(), output.to_string(),
+            ];
+            job.run_ffmpeg("trim (copy)", &args, Some(output), Some(duration)).await
+        }
+        [segment] if segment.method == SegmentMethod::Encode => {
+            let mut args = vec![
+                "-ss".to_string(), num(segment.start),
+                "-i".to_string(), input.to_string(),
+                "-t".to_string(), num(segment.end - segment.start),
+            ];
+            args.extend(encode.output_args());
+            args.extend(["-y".to_string(), output.to_string()]);
+            job.run_ffmpeg("trim", &args, Some(output), Some(duration)).await
+        }
+        segments => {
+            let info = info.ok_or("Smart cut requires probed media info")?;
+            run_smart_cut(job, input, output, segments, info).await
+        }
+    }
+}
+
+/// Produce each segment separately, then join them with the concat demuxer.
+/// The segments may come from several plans, e.g. the ranges of a jump cut.
+///
+/// Parts are written as MPEG-TS with in-band parameter sets: the encoded parts
+/// and the copied middle come from different encoders, and a joined MP4 only
+/// keeps the first part's out-of-band SPS/PPS.
+pub async fn run_smart_cut(
+    job: &Job,
+    input: &str,
+    output: &str,
+    segments: &[TrimSegment],
+    info: &MediaInfo,
+) -> Result<i32, String> {
+    let temp_dir = std::env::temp_dir().join(format!("trimbot_smartcut_{}", job.id()));
+    std::fs::create_dir_all(&temp_dir)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+
+    let result = async {
+        let encode_args = matching_encode_args(info);
+        let mut parts: Vec<PathBuf> = Vec::new();
+
+        for (i, segment) in segments.iter().enumerate() {
+            let part = temp_dir.join(format!("part_{}.ts", i));
+            let part_str = part.to_string_lossy().to_string();
+            let length = segment.end - segment.start;
+
+            let mut args = vec![
+                "-ss".to_string(), num(segment.start),
+                "-i".to_string(), input.to_string(),
+                "-t".to_string(), num(length),
+                "-map".to_string(), "0:v:0".to_string(),
+                "-map".to_string(), "0:a:0?".to_string(),
+            ];
+            match segment.method {
+                SegmentMethod::Copy => {
+                    args.extend([
+                        "-c".to_string(), "copy".to_string(),
+                        "-bsf:v".to_string(), annexb_filter(info).to_string(),
+                    ]);
+                }
+                SegmentMethod::Encode => args.extend(encode_args.iter().cloned()),
+            }
+            args.extend([
+                "-avoid_negative_ts".to_string(), "make_zero".to_string(),
+                "-f".to_string(), "mpegts".to_string(),
+                "-y".to_string(), part_str.clone(),
+            ]);
+
+            job.run_ffmpeg("smart cut segment", &args, Some(&part_str), Some(length)).await?;
+            parts.push(part);
+        }
+
+        let list_path = temp_dir.join("segments.txt");
+        let list: String = parts
+            .iter()
+            .map(|p| format!("file '{}'\n", p.to_string_lossy()))
+            .collect();
+        std::fs::write(&list_path, list)
+            .map_err(|e| format!("Failed to write segment list: {}", e))?;
+
+        let total: f64 = segments.iter().map(|s| s.end - s.start).sum();
+        let mut args = vec![
+            "-f".to_string(), "concat".to_string(),
+            "-safe".to_string(), "0".to_string(),
+            "-i".to_string(), list_path.to_string_lossy().to_string(),
+        ];
+        args.extend(smart_cut_join_args(info));
+        args.extend(["-y".to_string(), output.to_string()]);
+        job.run_ffmpeg("smart cut join", &args, Some(output), Some(total)).await
+    }
+    .await;
+
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    result
+}
+
+/// Preview how a trim would be cut (actual start after keyframe snapping, and
+/// which parts are copied vs re-encoded) without writing anything
+#[tauri::command]
+pub async fn plan_trim_cut(input: String, start: f64, end: f64, mode: Option<TrimMode>) -> Result<TrimPlan, String> {
+    let mode = mode.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        plan_trim_for_file(&input, start, end, mode).map(|(plan, _)| plan)
+    })
+    .await
+    .map_err(|e| format!("Failed to plan trim: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keyframes every 2 seconds
+    const KEYFRAMES: &[f64] = &[0.0, 2.0, 4.0, 6.0, 8.0, 10.0];
+
+    fn methods(plan: &TrimPlan) -> Vec<(f64, f64, SegmentMethod)> {
+        plan.segments.iter().map(|s| (s.start, s.end, s.method)).collect()
+    }
+
+    #[test]
+    fn reencode_cuts_exactly() {
+        let plan = plan_trim(3.3, 7.1, TrimMode::Reencode, KEYFRAMES, true);
+        assert_eq!((plan.start, plan.end), (3.3, 7.1));
+        assert_eq!(methods(&plan), [(3.3, 7.1, SegmentMethod::Encode)]);
+    }
+
+    #[test]
+    fn copy_snaps_back_to_the_previous_keyframe() {
+        // 3.9 is closer to 4.0, but starting there would drop 3.9..4.0
+        let plan = plan_trim(3.9, 7.0, TrimMode::Copy, KEYFRAMES, true);
+        assert_eq!(plan.start, 2.0);
+        assert_eq!(methods(&plan), [(2.0, 7.0, SegmentMethod::Copy)]);
+
+        let plan = plan_trim(4.0, 7.0, TrimMode::Copy, KEYFRAMES, true);
+        assert_eq!(plan.start, 4.0);
+        // Within the epsilon of a keyframe counts as on it
+        let plan = plan_trim(3.9995, 7.0, TrimMode::Copy, KEYFRAMES, true);
+        assert_eq!(plan.start, 3.9995);
+    }
+
+    #[test]
+    fn copy_without_keyframes_keeps_the_start() {
+        let plan = plan_trim(3.3, 7.0, TrimMode::Copy, &[], true);
+        assert_eq!(methods(&plan), [(3.3, 7.0, SegmentMethod::Copy)]);
+    }
+
+    #[test]
+    fn smart_cut_encodes_only_the_partial_gops() {
+        let plan = plan_trim(3.3, 7.1, TrimMode::SmartCut, KEYFRAMES, true);
+        assert_eq!((plan.start, plan.end), (3.3, 7.1));
+        assert_eq!(
+            methods(&plan),
+            [
+                (3.3, 4.0, SegmentMethod::Encode),
+                (4.0, 6.0, SegmentMethod::Copy),
+                (6.0, 7.1, SegmentMethod::Encode),
+            ]
+        );
+    }
+
+    #[test]
+    fn smart_cut_on_keyframes_copies_everything() {
+        let plan = plan_trim(2.0, 6.0, TrimMode::SmartCut, KEYFRAMES, true);
+        assert_eq!(methods(&plan), [(2.0, 6.0, SegmentMethod::Copy)]);
+    }
+
+    #[test]
+    fn smart_cut_inside_one_gop_falls_back_to_encoding() {
+        let plan = plan_trim(4.2, 5.8, TrimMode::SmartCut, KEYFRAMES, true);
+        assert_eq!(plan.mode, TrimMode::SmartCut);
+        assert_eq!(methods(&plan), [(4.2, 5.8, SegmentMethod::Encode)]);
+
+        // Only one keyframe inside the range leaves nothing to copy either
+        let plan = plan_trim(3.5, 4.5, TrimMode::SmartCut, KEYFRAMES, true);
+        assert_eq!(methods(&plan), [(3.5, 4.5, SegmentMethod::Encode)]);
+    }
+
+    #[test]
+    fn smart_cut_without_support_falls_back_to_encoding() {
+        let plan = plan_trim(3.3, 7.1, TrimMode::SmartCut, KEYFRAMES, false);
+        assert_eq!(methods(&plan), [(3.3, 7.1, SegmentMethod::Encode)]);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use tauri::ipc::{InvokeBody, Request};
+use tauri::{AppHandle, Manager};
+
+use crate::fs::unique_media_path;
+use crate::sandbox::{check_file_name, CommandError, PathPolicy};
+
+// Request headers carried by `append_blob_chunk`
+const UPLOAD_ID_HEADER: &str = "x-upload-id";
+const UPLOAD_OFFSET_HEADER: &str = "x-upload-offset";
+
+/// A file being written chunk by chunk
+struct BlobUpload {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+/// Open blob uploads by ID, managed as Tauri state
+#[derive(Default)]
+pub struct UploadManager {
+    uploads: Mutex<HashMap<String, Arc<Mutex<BlobUpload>>>>,
+}
+
+impl UploadManager {
+    fn get(&self, upload_id: &str) -> Result<Arc<Mutex<BlobUpload>>, String> {
+        self.uploads
+            .lock()
+            .unwrap()
+            .get(upload_id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown upload: {}", upload_id))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobUploadStarted {
+    pub upload_id: String,
+    /// Where the file is being written; it grows as chunks arrive
+    pub path: String,
+}
+
+fn header<'a>(request: &'a Request<'_>, name: &str) -> Option<&'a str> {
+    request.headers().get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Start streaming a file (e.g. a recording) into the app's media directory.
+///
+/// Chunks are written to disk as they arrive, so a crash mid-recording leaves
+/// everything received so far. An existing file with the same name is kept
+/// and the upload gets a numbered name instead.
+#[tauri::command]
+pub async fn begin_blob_upload(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    uploads: tauri::State<'_, UploadManager>,
+    filename: String,
+) -> Result<BlobUploadStarted, CommandError> {
+    check_file_name(&filename)?;
+    let app_dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    let media_dir = app_dir.join("media");
+    std::fs::create_dir_all(&media_dir)
+        .map_err(|e| format!("Failed to create media directory: {}", e))?;
+
+    let path = policy.check_write(&unique_media_path(&media_dir, &filename))?;
+    let file = File::create(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    let upload_id = uuid::Uuid::new_v4().to_string();
+    let started = BlobUploadStarted {
+        upload_id: upload_id.clone(),
+        path: path.display().to_string(),
+    };
+    uploads.uploads.lock().unwrap().insert(
+        upload_id,
+        Arc::new(Mutex::new(BlobUpload { path, file, written: 0 })),
+    );
+    Ok(started)
+}
+
+/// Append one chunk to an upload. The chunk is the raw binary request body;
+/// the `X-Upload-Id` header names the upload and the optional
+/// `X-Upload-Offset` header (bytes sent before this chunk) guards against
+/// lost or repeated chunks. Returns the total bytes written.
+#[tauri::command]
+pub async fn append_blob_chunk(
+    uploads: tauri::State<'_, UploadManager>,
+    request: Request<'_>,
+) -> Result<u64, CommandError> {
+    let InvokeBody::Raw(bytes) = request.body() else {
+        return Err("append_blob_chunk expects a raw binary body".to_string().into());
+    };
+    let upload_id = header(&request, UPLOAD_ID_HEADER)
+        .ok_or_else(|| format!("Missing {} header", UPLOAD_ID_HEADER))?;
+    let offset = header(&request, UPLOAD_OFFSET_HEADER)
+        .map(|value| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid {} header: {}", UPLOAD_OFFSET_HEADER, value))
+        })
+        .transpose()?;
+
+    let upload = uploads.get(upload_id)?;
+    let bytes = bytes.clone();
+    let written = tauri::async_runtime::spawn_blocking(move || {
+        let mut upload = upload.lock().unwrap();
+        if let Some(offset) = offset.filter(|offset| *offset != upload.written) {
+            return Err(format!(
+                "Chunk offset {} does not match the {} bytes written to {}",
+                offset,
+                upload.written,
+                upload.path.display()
+            ));
+        }
+
+        // Flush each chunk to disk so a crash loses at most the chunk in flight
+        upload
+            .file
+            .write_all(&bytes)
+            .and_then(|_| upload.file.sync_data())
+            .map_err(|e| format!("Failed to write {}: {}", upload.path.display(), e))?;
+        upload.written += bytes.len() as u64;
+        Ok(upload.written)
+    })
+    .await
+    .map_err(|e| format!("Failed to write chunk: {}", e))??;
+
+    Ok(written)
+}
+
+/// Close an upload and return the path of the finished file
+#[tauri::command]
+pub async fn finish_blob_upload(
+    uploads: tauri::State<'_, UploadManager>,
+    upload_id: String,
+) -> Result<String, CommandError> {
+    let upload = uploads
+        .uploads
+        .lock()
+        .unwrap()
+        .remove(&upload_id)
+        .ok_or_else(|| format!("Unknown upload: {}", upload_id))?;
+
+    let path = tauri::async_runtime::spawn_blocking(move || {
+        let upload = upload.lock().unwrap();
+        upload
+            .file
+            .sync_all()
+            .map_err(|e| format!("Failed to finish {}: {}", upload.path.display(), e))?;
+        Ok::<_, String>(upload.path.display().to_string())
+    })
+    .await
+    .map_err(|e| format!("Failed to finish upload: {}", e))??;
+
+    Ok(path)
+}
+
+/// Discard an upload after a failed recording: close the file and delete
+/// what was written. Unknown IDs are ignored, so this is safe to call after
+/// `finish_blob_upload` already closed the upload.
+#[tauri::command]
+pub async fn abort_blob_upload(
+    uploads: tauri::State<'_, UploadManager>,
+    upload_id: String,
+) -> Result<(), CommandError> {
+    let Some(upload) = uploads.uploads.lock().unwrap().remove(&upload_id) else {
+        return Ok(());
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // Wait for a chunk still being written, then close the file before
+        // removing it (Windows cannot delete open files)
+        let path = upload.lock().unwrap().path.clone();
+        drop(upload);
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to abort upload: {}", e))??;
+
+    Ok(())
+}
@@ -0,0 +1,504 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::fingerprint::partial_hash;
+use crate::fs::write_file_atomic;
+use crate::jobs::{start_job, Job};
+use crate::media::probe_media_info;
+use crate::sandbox::{CommandError, PathPolicy};
+
+// Audio is mixed down to mono 16-bit PCM at this rate before peak detection
+const WAVEFORM_SAMPLE_RATE: u32 = 22050;
+
+// Finest cached zoom level; each further level halves the resolution
+const BASE_SAMPLES_PER_PIXEL: u32 = 32;
+const LEVEL_COUNT: u32 = 12;
+
+// audiowaveform .dat version 1 header: version, flags, sample rate,
+// samples per pixel and length, each 32-bit little-endian
+const DAT_VERSION: i32 = 1;
+const DAT_FLAGS_16_BIT: u32 = 0;
+const DAT_HEADER_BYTES: u64 = 20;
+
+// Bytes per min/max pair of 16-bit values
+const PEAK_BYTES: u64 = 4;
+
+// Read buffer for the decoded PCM
+const PCM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// What a cached waveform contains, written once every level is on disk
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WaveformIndex {
+    sample_rate: u32,
+    /// Decoded samples (per channel after the mono mixdown)
+    samples: u64,
+    /// Samples per pixel of each cached level, finest first
+    levels: Vec<u32>,
+}
+
+/// Peaks for a range of a media file at one zoom level
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformData {
+    pub source: String,
+    /// Partial content hash the cache is keyed by
+    pub hash: String,
+    pub sample_rate: u32,
+    pub samples_per_pixel: u32,
+    /// Index of the first returned pixel; it starts at
+    /// `start_pixel * samples_per_pixel / sample_rate` seconds
+    pub start_pixel: u64,
+    /// Pixels in the whole file at this zoom level
+    pub total_pixels: u64,
+    pub duration: f64,
+    /// Interleaved min/max pairs of 16-bit samples, one pair per pixel
+    pub data: Vec<i16>,
+    /// The cached level the peaks were derived from
+    pub level: u32,
+    /// The audio was already decoded before this call
+    pub cached: bool,
+}
+
+/// Cache folder for one source: `<project>/.temp/waveforms/<hash>`
+fn cache_dir(project_dir: &Path, hash: &str) -> PathBuf {
+    project_dir.join(".temp").join("waveforms").join(hash)
+}
+
+fn level_path(dir: &Path, samples_per_pixel: u32) -> PathBuf {
+    dir.join(format!("peaks-{}.dat", samples_per_pixel))
+}
+
+/// Min/max pairs of every `samples_per_pixel` samples of mono s16le PCM.
+/// Returns the peaks and the number of samples read.
+fn peaks_from_pcm(reader: &mut impl Read, samples_per_pixel: u32) -> std::io::Result<(Vec<i16>, u64)> {
+    let mut peaks = Vec::new();
+    let mut buffer = vec![0u8; PCM_BUFFER_SIZE];
+    // A sample split across two reads
+    let mut carry: Option<u8> = None;
+    let (mut min, mut max, mut in_pixel) = (i16::MAX, i16::MIN, 0u32);
+    let mut samples = 0u64;
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        let mut bytes = &buffer[..read];
+        let mut push = |sample: i16| {
+            min = min.min(sample);
+            max = max.max(sample);
+            in_pixel += 1;
+            samples += 1;
+            if in_pixel == samples_per_pixel {
+                peaks.extend([min, max]);
+                (min, max, in_pixel) = (i16::MAX, i16::MIN, 0);
+            }
+        };
+
+        if let Some(low) = carry.take() {
+            push(i16::from_le_bytes([low, bytes[0]]));
+            bytes = &bytes[1..];
+        }
+        let mut pairs = bytes.chunks_exact(2);
+        for pair in &mut pairs {
+            push(i16::from_le_bytes([pair[0], pair[1]]));
+        }
+        carry = pairs.remainder().first().copied();
+    }
+
+    if in_pixel > 0 {
+        peaks.extend([min, max]);
+    }
+    Ok((peaks, samples))
+}
+
+/// Halve the resolution of interleaved min/max peaks
+fn downsample(peaks: &[i16]) -> Vec<i16> {
+    peaks
+        .chunks(4)
+        .flat_map(|group| {
+            let min = group.iter().step_by(2).copied().min().unwrap_or(0);
+            let max = group.iter().skip(1).step_by(2).copied().max().unwrap_or(0);
+            [min, max]
+        })
+        .collect()
+}
+
+/// Encode peaks as an audiowaveform `.dat` (version 1, 16-bit) file
+fn encode_dat(sample_rate: u32, samples_per_pixel: u32, peaks: &[i16]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(DAT_HEADER_BYTES as usize + peaks.len() * 2);
+    bytes.extend(DAT_VERSION.to_le_bytes());
+    bytes.extend(DAT_FLAGS_16_BIT.to_le_bytes());
+    bytes.extend((sample_rate as i32).to_le_bytes());
+    bytes.extend((samples_per_pixel as i32).to_le_bytes());
+    bytes.extend(((peaks.len() / 2) as u32).to_le_bytes());
+    for value in peaks {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes
+}
+
+/// Read `count` pixels starting at `first` from a cached `.dat` level
+fn read_dat_range(path: &Path, first: u64, count: u64) -> Result<Vec<i16>, String> {
+    let read_error = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+    let mut file = File::open(path).map_err(read_error)?;
+
+    let mut header = [0u8; DAT_HEADER_BYTES as usize];
+    file.read_exact(&mut header).map_err(read_error)?;
+    let field = |i: usize| u32::from_le_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+    if field(0) != DAT_VERSION as u32 || field(1) != DAT_FLAGS_16_BIT {
+        return Err(format!("Unsupported waveform file: {}", path.display()));
+    }
+
+    let length = field(4) as u64;
+    let first = first.min(length);
+    let count = count.min(length - first);
+
+    let mut bytes = vec![0u8; (count * PEAK_BYTES) as usize];
+    file.seek(SeekFrom::Start(DAT_HEADER_BYTES + first * PEAK_BYTES))
+        .and_then(|_| file.read_exact(&mut bytes))
+        .map_err(read_error)?;
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect())
+}
+
+/// Resample peaks of a cached level to `target` samples per pixel.
+///
+/// `peaks` holds the level's pixels from index `offset`; the result covers
+/// target pixels `first..first + count`. Each target pixel takes the extremes
+/// of every level pixel it overlaps.
+fn resample(peaks: &[i16], level: u32, offset: u64, target: u32, first: u64, count: u64) -> Vec<i16> {
+    let (level, target) = (level as u64, target as u64);
+    let available = (peaks.len() / 2) as u64;
+    let mut out = Vec::with_capacity(count as usize * 2);
+
+    for pixel in first..first + count {
+        let start = (pixel * target / level).saturating_sub(offset).min(available);
+        let end = ((pixel + 1) * target).div_ceil(level).saturating_sub(offset).min(available);
+        let group = &peaks[start as usize * 2..end.max(start) as usize * 2];
+        let min = group.iter().step_by(2).copied().min().unwrap_or(0);
+        let max = group.iter().skip(1).step_by(2).copied().max().unwrap_or(0);
+        out.extend([min, max]);
+    }
+    out
+}
+
+/// Decode the audio and write every zoom level, unless the cache is complete.
+/// Returns the index and whether it was already cached.
+async fn ensure_waveform_cache(job: &Job, input: &str, dir: &Path) -> Result<(WaveformIndex, bool), String> {
+    // The index is written last, so it marks a complete cache
+    let index_path = dir.join("index.json");
+    if let Ok(contents) = std::fs::read_to_string(&index_path) {
+        if let Ok(index) = serde_json::from_str::<WaveformIndex>(&contents) {
+            if index.levels.iter().all(|spp| level_path(dir, *spp).exists()) {
+                return Ok((index, true));
+            }
+        }
+    }
+
+    let info = {
+        let path = input.to_string();
+        tauri::async_runtime::spawn_blocking(move || probe_media_info(&path))
+            .await
+            .map_err(|e| format!("Failed to probe {}: {}", input, e))??
+    };
+    if !info.has_audio() {
+        return Err(format!("{} has no audio stream", input));
+    }
+
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create directory {}: {}", dir.display(), e))?;
+    let pcm_path = dir.join(format!(".{}.pcm", job.id()));
+    let pcm = pcm_path.display().to_string();
+
+    let args = vec![
+        "-i".to_string(), input.to_string(),
+        "-map".to_string(), "0:a:0".to_string(),
+        "-vn".to_string(),
+        "-ac".to_string(), "1".to_string(),
+        "-ar".to_string(), WAVEFORM_SAMPLE_RATE.to_string(),
+        "-c:a".to_string(), "pcm_s16le".to_string(),
+        "-f".to_string(), "s16le".to_string(),
+        "-y".to_string(), pcm.clone(),
+    ];
+    let decoded = job.run_ffmpeg("waveform decode", &args, Some(&pcm), info.duration).await;
+
+    let result = match decoded {
+        Ok(_) => {
+            let dir = dir.to_path_buf();
+            let pcm_path = pcm_path.clone();
+            tauri::async_runtime::spawn_blocking(move || write_levels(&pcm_path, &dir))
+                .await
+                .map_err(|e| format!("Failed to build waveform: {}", e))
+                .and_then(|result| result)
+        }
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_file(&pcm_path);
+    let index = result?;
+
+    let json = serde_json::to_string_pretty(&index)
+        .map_err(|e| format!("Failed to serialize waveform index: {}", e))?;
+    write_file_atomic(&index_path, json.as_bytes())?;
+    Ok((index, false))
+}
+
+/// Compute the base peaks from decoded PCM and write each level as a `.dat`
+fn write_levels(pcm_path: &Path, dir: &Path) -> Result<WaveformIndex, String> {
+    let file = File::open(pcm_path)
+        .map_err(|e| format!("Failed to open {}: {}", pcm_path.display(), e))?;
+    let (mut peaks, samples) = peaks_from_pcm(&mut BufReader::new(file), BASE_SAMPLES_PER_PIXEL)
+        .map_err(|e| format!("Failed to read {}: {}", pcm_path.display(), e))?;
+
+    let mut levels = Vec::new();
+    let mut samples_per_pixel = BASE_SAMPLES_PER_PIXEL;
+    for _ in 0..LEVEL_COUNT {
+        let bytes = encode_dat(WAVEFORM_SAMPLE_RATE, samples_per_pixel, &peaks);
+        write_file_atomic(&level_path(dir, samples_per_pixel), &bytes)?;
+        levels.push(samples_per_pixel);
+
+        peaks = downsample(&peaks);
+        samples_per_pixel *= 2;
+    }
+
+    Ok(WaveformIndex {
+        sample_rate: WAVEFORM_SAMPLE_RATE,
+        samples,
+        levels,
+    })
+}
+
+async fn compute_internal(
+    job: &Job,
+    project_dir: &Path,
+    input: &str,
+    samples_per_pixel: Option<u32>,
+    start: Option<f64>,
+    end: Option<f64>,
+) -> Result<WaveformData, String> {
+    let hash = {
+        let path = input.to_string();
+        tauri::async_runtime::spawn_blocking(move || partial_hash(Path::new(&path)))
+            .await
+            .map_err(|e| format!("Failed to hash {}: {}", input, e))??
+    };
+    let dir = cache_dir(project_dir, &hash);
+    let (index, cached) = ensure_waveform_cache(job, input, &dir).await?;
+
+    // Zooming in past the finest level would only repeat its pixels
+    let target = samples_per_pixel.unwrap_or(BASE_SAMPLES_PER_PIXEL).max(BASE_SAMPLES_PER_PIXEL);
+    let level = index
+        .levels
+        .iter()
+        .copied()
+        .filter(|spp| *spp <= target)
+        .max()
+        .unwrap_or(BASE_SAMPLES_PER_PIXEL);
+
+    let sample_rate = index.sample_rate as f64;
+    let total_pixels = index.samples.div_ceil(target as u64);
+    let to_pixel = |time: f64| ((time.max(0.0) * sample_rate) / target as f64) as u64;
+    let first = start.map(to_pixel).unwrap_or(0).min(total_pixels);
+    let last = end
+        .map(|time| (time.max(0.0) * sample_rate / target as f64).ceil() as u64)
+        .unwrap_or(total_pixels)
+        .clamp(first, total_pixels);
+    let count = last - first;
+
+    let offset = first * target as u64 / level as u64;
+    let level_end = (last * target as u64).div_ceil(level as u64);
+    let path = level_path(&dir, level);
+    let peaks = {
+        let level_path = path.clone();
+        tauri::async_runtime::spawn_blocking(move || read_dat_range(&level_path, offset, level_end - offset))
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))??
+    };
+    let data = if level == target {
+        peaks
+    } else {
+        resample(&peaks, level, offset, target, first, count)
+    };
+
+    Ok(WaveformData {
+        source: input.to_string(),
+        hash,
+        sample_rate: index.sample_rate,
+        samples_per_pixel: target,
+        start_pixel: first,
+        total_pixels,
+        duration: index.samples as f64 / sample_rate,
+        data,
+        level,
+        cached,
+    })
+}
+
+/// Min/max audio peaks of a media file for drawing waveforms.
+///
+/// The first call decodes the audio and caches peaks at every power-of-two
+/// zoom level from 32 samples per pixel (mono, 22050 Hz) in audiowaveform
+/// `.dat` files under `<project>/.temp/waveforms/<hash>`. Any
+/// `samples_per_pixel` is then served from the nearest finer level without
+/// decoding again. `start`/`end` (seconds) limit the returned range.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn compute_waveform(
+    app: AppHandle,
+    policy: tauri::State<'_, PathPolicy>,
+    project_dir: String,
+    path: String,
+    samples_per_pixel: Option<u32>,
+    start: Option<f64>,
+    end: Option<f64>,
+    job_id: Option<String>,
+) -> Result<WaveformData, CommandError> {
+    let project_dir = Path::new(&project_dir);
+    policy.check_write(&project_dir.join(".temp").join("waveforms"))?;
+
+    let job = start_job(&app, "waveform", job_id)?;
+    let result = compute_internal(&job, project_dir, &path, samples_per_pixel, start, end).await;
+    job.finish(result).map_err(CommandError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out at most `step` bytes per read, to split samples across reads
+    struct Chunked<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    /// A path under the system temp dir, removed on drop
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new() -> Self {
+            TempFile(std::env::temp_dir().join(format!("trimbot_waveform_{}.dat", uuid::Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn pcm(samples: &[i16]) -> Vec<u8> {
+        samples.iter().flat_map(|s| s.to_le_bytes()).collect()
+    }
+
+    /// Level pixels where pixel `i` spans `-i..=i`
+    fn ramp(pixels: i16) -> Vec<i16> {
+        (0..pixels).flat_map(|i| [-i, i]).collect()
+    }
+
+    #[test]
+    fn peaks_group_samples_per_pixel() {
+        let data = pcm(&[-5, 10, 3, -20, 7]);
+        let (peaks, samples) = peaks_from_pcm(&mut data.as_slice(), 2).unwrap();
+        // The last, partial pixel is kept
+        assert_eq!(peaks, vec![-5, 10, -20, 3, 7, 7]);
+        assert_eq!(samples, 5);
+    }
+
+    #[test]
+    fn peaks_carry_samples_split_across_reads() {
+        let samples = [-300, 1200, -32768, 32767, 5, -6, 400];
+        let data = pcm(&samples);
+        let expected = peaks_from_pcm(&mut data.as_slice(), 3).unwrap();
+        for step in [1, 3, 5] {
+            let mut reader = Chunked { data: &data, step };
+            assert_eq!(peaks_from_pcm(&mut reader, 3).unwrap(), expected, "{} bytes per read", step);
+        }
+        assert_eq!(expected.0, vec![-32768, 1200, -6, 32767, 400, 400]);
+    }
+
+    #[test]
+    fn peaks_drop_a_trailing_odd_byte() {
+        let mut data = pcm(&[1, 2]);
+        data.push(0x7F);
+        let (peaks, samples) = peaks_from_pcm(&mut data.as_slice(), 4).unwrap();
+        assert_eq!(peaks, vec![1, 2]);
+        assert_eq!(samples, 2);
+    }
+
+    #[test]
+    fn downsample_merges_pixel_pairs() {
+        assert_eq!(downsample(&[1, 2, -3, 4, 5, 6]), vec![-3, 4, 5, 6]);
+        assert_eq!(downsample(&ramp(4)), vec![-1, 1, -3, 3]);
+        assert!(downsample(&[]).is_empty());
+    }
+
+    #[test]
+    fn dat_ranges_round_trip() {
+        let file = TempFile::new();
+        let path = &file.0;
+        std::fs::write(path, encode_dat(WAVEFORM_SAMPLE_RATE, 64, &ramp(5))).unwrap();
+
+        assert_eq!(read_dat_range(path, 0, 5).unwrap(), ramp(5));
+        assert_eq!(read_dat_range(path, 1, 2).unwrap(), vec![-1, 1, -2, 2]);
+        // Ranges past the end are cut short
+        assert_eq!(read_dat_range(path, 3, 10).unwrap(), vec![-3, 3, -4, 4]);
+        assert!(read_dat_range(path, 9, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn dat_rejects_other_formats() {
+        let file = TempFile::new();
+        let mut bytes = encode_dat(WAVEFORM_SAMPLE_RATE, 64, &ramp(2));
+        // 8-bit flag
+        bytes[4] = 1;
+        std::fs::write(&file.0, bytes).unwrap();
+
+        assert!(read_dat_range(&file.0, 0, 2).is_err());
+    }
+
+    #[test]
+    fn resample_at_a_power_of_two() {
+        let level = ramp(10);
+        // Target pixels 1..3 at 64 spp cover level pixels 2..6 at 32 spp
+        let offset = 64 / 32;
+        let peaks = &level[offset * 2..];
+        assert_eq!(resample(peaks, 32, offset as u64, 64, 1, 2), vec![-3, 3, -5, 5]);
+    }
+
+    #[test]
+    fn resample_from_an_unaligned_first_pixel() {
+        let level = ramp(10);
+        // Target pixel 3 at 48 spp starts at sample 144, halfway into level
+        // pixel 4, so the read starts at that pixel
+        let (target, level_spp, first, count) = (48u64, 32u64, 3u64, 2u64);
+        let offset = first * target / level_spp;
+        assert_eq!(offset, 4);
+        let level_end = ((first + count) * target).div_ceil(level_spp);
+        let peaks = &level[offset as usize * 2..level_end as usize * 2];
+
+        // Pixel 3 spans samples 144..192 (level pixels 4 and 5), pixel 4
+        // spans 192..240 (level pixels 6 and 7)
+        assert_eq!(resample(peaks, 32, offset, 48, first, count), vec![-5, 5, -7, 7]);
+    }
+
+    #[test]
+    fn resample_pads_past_the_cached_peaks() {
+        let level = ramp(3);
+        assert_eq!(resample(&level, 32, 0, 64, 1, 2), vec![-2, 2, 0, 0]);
+    }
+}
@@ -26,6 +26,14 @@ const invoke = window.__TAURI__?.core?.invoke || (async (command, args) => {
   }
 });
 
+// Output canvas for each resolution option; 'source' uses the first clip's size
+const RESOLUTIONS = {
+  '1080p': { width: 1920, height: 1080 },
+  '720p': { width: 1280, height: 720 },
+  'vertical': { width: 1080, height: 1920 },
+  'square': { width: 1080, height: 1080 },
+};
+
 function ExportDialog({ isOpen, onClose, timelineClips, projectDir }) {
   const [filename, setFilename] = useState('final-export');
   const [resolution, setResolution] = useState('source');
@@ -77,7 +85,7 @@ function ExportDialog({ isOpen, onClose, timelineClips, projectDir }) {
     const outputPath = `${projectDir}/exports/${outputFilename}`;
     
     // Export with or without transitions
-    const resolutionParam = resolution === 'source' ? null : resolution;
+    const resolutionParam = RESOLUTIONS[resolution] ?? null;
     const inputs = timelineClips.map(clip => clip.path.replace(/\\/g, '/'));
 
     try {
@@ -87,8 +95,7 @@ function ExportDialog({ isOpen, onClose, timelineClips, projectDir }) {
           inputs,
           output: outputPath,
           duration: parseFloat(String(crossfadeDuration)) || 1.0,
-          resolution: resolutionParam,
-          temp_dir: `${projectDir}/.temp`
+          resolution: resolutionParam
         });
       } else {
         // Original concat path with optional fade effects
@@ -277,6 +284,8 @@ function ExportDialog({ isOpen, onClose, timelineClips, projectDir }) {
               <option value="source">Source (Original)</option>
               <option value="1080p">1080p (Full HD)</option>
               <option value="720p">720p (HD)</option>
+              <option value="vertical">1080x1920 (Vertical 9:16)</option>
+              <option value="square">1080x1080 (Square 1:1)</option>
             </select>
           </div>
 
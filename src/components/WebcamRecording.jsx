@@ -3,6 +3,7 @@ import { Camera, Square, Play, Pause, Video, VideoOff } from 'lucide-react';
 import { toast } from 'sonner';
 import { generateWebcamRecordingName } from '../utils/fileNaming';
 import { recordingToasts } from '../utils/toastMessages';
+import { createBlobUpload } from '../utils/blobUpload';
 
 // Use global Tauri API with browser fallback for development
 const invoke = window.__TAURI__?.core?.invoke || (async (command, args) => {
@@ -23,6 +24,7 @@ export function WebcamRecording({ onRecordingComplete, onError, onStatusChange }
   const streamRef = useRef(null);
   const previewRef = useRef(null);
   const chunksRef = useRef([]);
+  const uploadRef = useRef(null);
   const timerRef = useRef(null);
 
   const formatTime = (seconds) => {
@@ -146,6 +148,18 @@ export function WebcamRecording({ onRecordingComplete, onError, onStatusChange }
 
       chunksRef.current = [];
 
+      // On desktop, write chunks to disk as they are recorded
+      const filename = generateWebcamRecordingName();
+      uploadRef.current = window.__TAURI__
+        ? await createBlobUpload(invoke, filename, {
+            // Stop at the first failed chunk; onstop reports it and discards the file
+            onError: (error) => {
+              console.error('Failed to write recording chunk:', error);
+              stopRecording();
+            },
+          })
+        : null;
+
       // Create MediaRecorder
       const mediaRecorder = new MediaRecorder(stream, {
         mimeType: 'video/webm;codecs=vp9'
@@ -155,31 +169,28 @@ export function WebcamRecording({ onRecordingComplete, onError, onStatusChange }
 
       mediaRecorder.ondataavailable = (event) => {
         if (event.data.size > 0) {
-          chunksRef.current.push(event.data);
+          if (uploadRef.current) {
+            uploadRef.current.append(event.data);
+          } else {
+            chunksRef.current.push(event.data);
+          }
         }
       };
 
       mediaRecorder.onstop = async () => {
+        const upload = uploadRef.current;
+        uploadRef.current = null;
          try {
-           const blob = new Blob(chunksRef.current, { type: 'video/webm' });
-           const filename = generateWebcamRecordingName();
-          
-          // Convert blob to array buffer for Tauri
-          const arrayBuffer = await blob.arrayBuffer();
-          const uint8Array = new Uint8Array(arrayBuffer);
-          
           recordingToasts.webcamSaving();
           onStatusChange?.('Saving recording...');
-          
-          // Save the webm file
-          const savedPath = await invoke('save_blob', {
-            filename,
-            data: Array.from(uint8Array)
-          });
+
+          // Chunks are already on disk; close the file
+          const savedPath = upload ? await upload.finish() : null;
           
           // Check if we're in a browser environment (savedPath is null)
           if (!savedPath) {
             // Browser environment - create a download link for the user
+            const blob = new Blob(chunksRef.current, { type: 'video/webm' });
             const url = URL.createObjectURL(blob);
             const a = document.createElement('a');
             a.href = url;
@@ -198,12 +209,14 @@ export function WebcamRecording({ onRecordingComplete, onError, onStatusChange }
           recordingToasts.webcamTranscoding();
           onStatusChange?.('Transcoding to MP4...');
           
+          // MediaRecorder never finalizes its WebM; rebuild duration and seek index first
+          const repair = await invoke('repair_recording', { input: savedPath });
           // Transcode to MP4 (only in desktop environment)
-          const mp4Filename = filename.replace('.webm', '.mp4');
           const mp4OutputPath = savedPath.replace('.webm', '.mp4');
+          const mp4Filename = mp4OutputPath.split(/[\\/]/).pop();
           
           await invoke('transcode_to_mp4', {
-            input: savedPath,
+            input: repair.output,
             output: mp4OutputPath
           });
           
@@ -213,6 +226,8 @@ export function WebcamRecording({ onRecordingComplete, onError, onStatusChange }
           
         } catch (error) {
           console.error('Error processing recording:', error);
+          // Delete the partial file unless it was already finished
+          upload?.abort().catch(err => console.error('Failed to discard recording:', err));
           recordingToasts.webcamSaveFailed(error.message);
           onError?.(`Failed to save recording: ${error}`);
         }
@@ -231,6 +246,9 @@ export function WebcamRecording({ onRecordingComplete, onError, onStatusChange }
       
     } catch (error) {
       console.error('Failed to start webcam recording:', error);
+      // The upload may already be open if the recorder failed to start
+      uploadRef.current?.abort().catch(err => console.error('Failed to discard recording:', err));
+      uploadRef.current = null;
       onError?.(`Failed to start webcam recording: ${error.message}`);
     }
   }, [onRecordingComplete, onError, onStatusChange]);
@@ -1,7 +1,7 @@
 import React, { createContext, useContext, useReducer, useCallback, useMemo, ReactNode } from 'react';
 import { v4 as uuidv4 } from 'uuid';
 import { generateTrimmedClipName, generateImportedFileName } from '../utils/fileNaming';
-import { projectToasts, importToasts, editingToasts } from '../utils/toastMessages';
+import { projectToasts, importToasts, editingToasts, describe } from '../utils/toastMessages';
 // Import new types from types/index.js for PR-07
 import type { OverlayProperties, TrackSettings, TimelineItem, ClipKeyframe, KeyframeSet } from '../types';
 
@@ -18,7 +18,11 @@ const invoke = window.__TAURI__?.core?.invoke || (async (command, args) => {
       return true;
     case 'copy_file_to_media':
       console.log('Mock: Copied file from', args?.sourcePath, 'to media directory');
-      return `${args?.projectDir}/media/${args?.sourcePath.split(/[\\/]/).pop()}`;
+      return {
+        id: `mock-${args?.sourcePath}`,
+        path: `${args?.projectDir}/media/${args?.sourcePath.split(/[\\/]/).pop()}`,
+        reused: false,
+      };
     case 'get_file_info':
       return { size: 1024000, modified: Date.now() };
     case 'trim_clip':
@@ -366,7 +370,7 @@ export const ProjectProvider: React.FC<ProjectProviderProps> = React.memo(({ chi
       dispatch({ type: 'SET_PROJECT', payload: newProject });
       return newProject;
     } catch (err) {
-      const errorMsg = String(err);
+      const errorMsg = describe(err);
       dispatch({ type: 'SET_ERROR', payload: errorMsg });
       projectToasts.createFailed(errorMsg);
       throw new Error(errorMsg);
@@ -396,14 +400,17 @@ export const ProjectProvider: React.FC<ProjectProviderProps> = React.memo(({ chi
         const clipId = uuidv4();
         
         const improvedFilename = generateImportedFileName(filename, clipId);
-        const copiedPath = await invoke('copy_file_to_media', {
+        const imported = await invoke('copy_file_to_media', {
           sourcePath: filePath,
           projectDir: state.project.projectDir,
           filename: improvedFilename,
         });
+        // The backend may reuse an identical file or rename on a name clash
+        const copiedPath = imported.path;
+        const copiedFilename = copiedPath.split(/[\\/]/).pop() || improvedFilename;
 
         let finalPath = copiedPath;
-        let finalFilename = improvedFilename;
+        let finalFilename = copiedFilename;
 
         // Check if file needs to be converted to MP4 for normalization
         const fileExtension = filename.toLowerCase().split('.').pop();
@@ -413,7 +420,7 @@ export const ProjectProvider: React.FC<ProjectProviderProps> = React.memo(({ chi
           importToasts.converting(filename);
           
           try {
-            const mp4Filename = improvedFilename.replace(/\.[^.]+$/, '.mp4');
+            const mp4Filename = copiedFilename.replace(/\.[^.]+$/, '.mp4');
             const mp4Path = copiedPath.replace(/\.[^.]+$/, '.mp4');
             
             await invoke('transcode_to_mp4', {
@@ -457,7 +464,7 @@ export const ProjectProvider: React.FC<ProjectProviderProps> = React.memo(({ chi
 
       return newClips;
     } catch (err) {
-      const errorMsg = String(err);
+      const errorMsg = describe(err);
       dispatch({ type: 'SET_ERROR', payload: errorMsg });
       throw new Error(errorMsg);
     } finally {
@@ -548,7 +555,7 @@ export const ProjectProvider: React.FC<ProjectProviderProps> = React.memo(({ chi
       editingToasts.trimComplete(originalClip.filename || 'clip');
       return trimmedClip;
     } catch (err) {
-      const errorMsg = String(err);
+      const errorMsg = describe(err);
       editingToasts.trimFailed(originalClip.filename || 'clip', errorMsg);
       throw new Error(errorMsg);
     } finally {
@@ -1,7 +1,7 @@
 import { useState, useCallback } from 'react';
 import { v4 as uuidv4 } from 'uuid';
 import { generateTrimmedClipName, generateImportedFileName } from '../utils/fileNaming';
-import { projectToasts, importToasts, editingToasts } from '../utils/toastMessages';
+import { projectToasts, importToasts, editingToasts, describe } from '../utils/toastMessages';
 
 // Use global Tauri API with browser fallback for development
 const invoke = window.__TAURI__?.core?.invoke || (async (command, args) => {
@@ -21,7 +21,11 @@ const invoke = window.__TAURI__?.core?.invoke || (async (command, args) => {
     case 'copy_file_to_media':
       // Mock file copy operation
       console.log('Mock: Copied file from', args?.sourcePath, 'to media directory');
-      return `${args?.projectDir}/media/${args?.sourcePath.split(/[\\/]/).pop()}`;
+      return {
+        id: `mock-${args?.sourcePath}`,
+        path: `${args?.projectDir}/media/${args?.sourcePath.split(/[\\/]/).pop()}`,
+        reused: false,
+      };
     
     case 'get_file_info':
       // Mock file info
@@ -81,7 +85,7 @@ export const useProject = () => {
       setProject(newProject);
       return newProject;
     } catch (err) {
-      const errorMsg = err;
+      const errorMsg = describe(err);
       setError(errorMsg);
       projectToasts.createFailed(errorMsg);
       throw new Error(errorMsg);
@@ -112,14 +116,17 @@ export const useProject = () => {
         
         // Copy file to media directory with improved naming
         const improvedFilename = generateImportedFileName(filename, clipId);
-        const copiedPath = await invoke('copy_file_to_media', {
+        const imported = await invoke('copy_file_to_media', {
           sourcePath: filePath,
           projectDir: project.projectDir,
           filename: improvedFilename,
         });
+        // The backend may reuse an identical file or rename on a name clash
+        const copiedPath = imported.path;
+        const copiedFilename = copiedPath.split(/[\\/]/).pop() || improvedFilename;
 
         let finalPath = copiedPath;
-        let finalFilename = improvedFilename;
+        let finalFilename = copiedFilename;
 
         // Check if file needs to be converted to MP4 for normalization
         const fileExtension = filename.toLowerCase().split('.').pop();
@@ -131,7 +138,7 @@ export const useProject = () => {
           
           try {
             // Generate MP4 filename
-            const mp4Filename = improvedFilename.replace(/\.[^.]+$/, '.mp4');
+            const mp4Filename = copiedFilename.replace(/\.[^.]+$/, '.mp4');
             const mp4Path = copiedPath.replace(/\.[^.]+$/, '.mp4');
             
             // Transcode to MP4 for normalization
@@ -183,7 +190,7 @@ export const useProject = () => {
 
       return newClips;
     } catch (err) {
-      const errorMsg = err;
+      const errorMsg = describe(err);
       setError(errorMsg);
       throw new Error(errorMsg);
     } finally {
@@ -269,7 +276,7 @@ export const useProject = () => {
 
       return trimmedClip;
     } catch (err) {
-      const errorMsg = err.toString();
+      const errorMsg = describe(err);
       setError(errorMsg);
       throw new Error(errorMsg);
     } finally {
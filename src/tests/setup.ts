@@ -28,7 +28,7 @@ beforeEach(() => {
             case 'ensure_dir':
               return true;
             case 'copy_file_to_media':
-              return '/mock/media/file.mp4';
+              return { id: 'mock-media-id', path: '/mock/media/file.mp4', reused: false };
             case 'get_file_info':
               return { size: 1024000, modified: Date.now() };
             case 'trim_clip':
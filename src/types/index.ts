@@ -43,6 +43,7 @@ export interface TimelineItem {
   inSec?: number;   // default 0
   outSec?: number;  // default == source duration
   keyframes?: KeyframeSet;
+  fitMode?: 'letterbox' | 'crop' | 'stretch';
   fadePresets?: {
     fadeIn?: FadePreset;
     fadeOut?: FadePreset;
@@ -67,9 +68,160 @@ export interface ProjectSettings {
     height: number;
   };
   frameRate: number;
+  frameRateConversion?: 'duplicate' | 'interpolate';
   outputFormat: string;
 }
 
+// On-disk project.trimbot.json (clip paths are absolute once loaded)
+export interface ProjectFile {
+  version: number;
+  id: string;
+  name: string;
+  createdAt: string;
+  updatedAt: string;
+  settings: ProjectSettings;
+  trackSettings?: TrackSettings;
+  mainTrack: TimelineItem[];
+  overlayTrack: TimelineItem[];
+  media?: MediaFingerprint[];
+}
+
+export interface ImportedMedia {
+  id: string;
+  path: string;
+  reused: boolean;
+}
+
+export interface MediaFingerprint {
+  path: string;
+  size: number;
+  duration?: number;
+  partialHash: string;
+}
+
+export interface MigrationReport {
+  fromVersion: number;
+  toVersion: number;
+  applied: string[];
+  backupPath?: string | null;
+}
+
+export interface LoadedProject {
+  project: ProjectFile;
+  migration?: MigrationReport | null;
+}
+
+export interface ConsolidateOptions {
+  zip?: boolean;
+  trim?: boolean;
+  handleSec?: number;
+  trimMode?: 'reencode' | 'copy' | 'smartCut';
+}
+
+export interface ConsolidatedMedia {
+  source: string;
+  path: string;
+  trimmedRange?: [number, number] | null;
+}
+
+export interface ConsolidateResult {
+  path: string;
+  media: ConsolidatedMedia[];
+}
+
+export interface RepairReport {
+  input: string;
+  output: string;
+  repaired: boolean;
+  method?: 'remux' | 'reencode' | null;
+  issues: string[];
+  warnings: string[];
+  duration?: number | null;
+  hasVideo: boolean;
+  hasAudio: boolean;
+  inputBytes: number;
+  outputBytes: number;
+}
+
+export interface ThumbnailOptions {
+  height?: number;
+  filmstripCount?: number;
+  sprite?: boolean;
+  spriteInterval?: number;
+  spriteColumns?: number;
+}
+
+export interface Thumbnail {
+  time: number;
+  path: string;
+}
+
+export interface SpriteSheet {
+  image: string;
+  index: string;
+  columns: number;
+  rows: number;
+  tileWidth: number;
+  tileHeight: number;
+  interval: number;
+  frames: { time: number; x: number; y: number }[];
+}
+
+export interface ThumbnailSet {
+  source: string;
+  hash: string;
+  cacheDir: string;
+  width: number;
+  height: number;
+  duration?: number | null;
+  poster: Thumbnail;
+  filmstrip: Thumbnail[];
+  sprite?: SpriteSheet | null;
+  cached: boolean;
+}
+
+export interface WaveformData {
+  source: string;
+  hash: string;
+  sampleRate: number;
+  samplesPerPixel: number;
+  startPixel: number;
+  totalPixels: number;
+  duration: number;
+  /** Interleaved min/max pairs, one pair per pixel */
+  data: number[];
+  level: number;
+  cached: boolean;
+}
+
+export interface SilenceOptions {
+  thresholdDb?: number;
+  minDuration?: number;
+  padding?: number;
+  start?: number;
+  end?: number;
+}
+
+export interface TimeRange {
+  start: number;
+  end: number;
+}
+
+export interface SilenceAnalysis {
+  input: string;
+  range: TimeRange;
+  silences: TimeRange[];
+  speech: TimeRange[];
+}
+
+export interface JumpCut {
+  input: string;
+  output?: string | null;
+  segments: TimeRange[];
+  originalDuration: number;
+  keptDuration: number;
+}
+
 export interface TrackSettings {
   showOverlayTrack?: boolean;
   overlayTrackHeight?: number;
@@ -85,3 +237,13 @@ export interface Clip {
   width?: number;
   height?: number;
 }
+
+export type CommandError =
+  | {
+      kind: 'permissionDenied';
+      path: string;
+      reason: 'notAbsolute' | 'traversal' | 'outsideSandbox' | 'symlinkEscape' | 'invalidFileName';
+      message: string;
+    }
+  | { kind: 'rejectedArgument'; argument: string; message: string }
+  | { kind: 'failed'; message: string };
@@ -0,0 +1,44 @@
+/**
+ * Stream a recording to disk chunk by chunk as MediaRecorder produces it,
+ * instead of holding the whole file in memory and sending it at the end.
+ * Chunks are sent in order as raw binary IPC bodies. The first failed chunk
+ * is reported to `onError` right away; later chunks are not sent, `finish`
+ * rejects with that failure and `abort` deletes the partial file.
+ */
+export async function createBlobUpload(invoke, filename, { onError } = {}) {
+  const { uploadId, path } = await invoke('begin_blob_upload', { filename });
+  let offset = 0;
+  let failure = null;
+  let queue = Promise.resolve();
+
+  const append = (blob) => {
+    queue = queue.then(async () => {
+      if (failure) return;
+      try {
+        const bytes = new Uint8Array(await blob.arrayBuffer());
+        await invoke('append_blob_chunk', bytes, {
+          headers: { 'X-Upload-Id': uploadId, 'X-Upload-Offset': String(offset) },
+        });
+        offset += bytes.byteLength;
+      } catch (error) {
+        failure = error;
+        onError?.(error);
+      }
+    });
+    return queue;
+  };
+
+  const finish = async () => {
+    await queue;
+    if (failure) throw failure;
+    return invoke('finish_blob_upload', { uploadId });
+  };
+
+  // Safe to call after `finish`; a finished upload is left alone
+  const abort = async () => {
+    await queue;
+    return invoke('abort_blob_upload', { uploadId });
+  };
+
+  return { path, append, finish, abort };
+}
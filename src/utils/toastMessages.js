@@ -3,30 +3,36 @@
  */
 import { toast } from 'sonner';
 
+/**
+ * Readable text for an error; sandboxed backend commands reject with
+ * `{ kind, message }` objects rather than strings
+ */
+export const describe = (error) => error?.message ?? String(error);
+
 // Project-related messages
 export const projectToasts = {
   creating: () => toast.loading('Creating project...', { id: 'project-create' }),
   created: () => toast.success('Project created successfully!', { id: 'project-create' }),
-  createFailed: (error) => toast.error(`Failed to create project: ${error}`, { id: 'project-create' }),
+  createFailed: (error) => toast.error(`Failed to create project: ${describe(error)}`, { id: 'project-create' }),
   
   loading: () => toast.loading('Loading project...', { id: 'project-load' }),
   loaded: () => toast.success('Project loaded successfully!', { id: 'project-load' }),
-  loadFailed: (error) => toast.error(`Failed to load project: ${error}`, { id: 'project-load' }),
+  loadFailed: (error) => toast.error(`Failed to load project: ${describe(error)}`, { id: 'project-load' }),
 };
 
 // Import-related messages
 export const importToasts = {
   importing: (count) => toast.loading(`Importing ${count} file${count > 1 ? 's' : ''}...`, { id: 'import' }),
   imported: (count) => toast.success(`Successfully imported ${count} file${count > 1 ? 's' : ''}!`, { id: 'import' }),
-  importFailed: (error) => toast.error(`Import failed: ${error}`, { id: 'import' }),
+  importFailed: (error) => toast.error(`Import failed: ${describe(error)}`, { id: 'import' }),
   
   probing: (filename) => toast.loading(`Analyzing ${filename}...`, { id: 'probe' }),
   probed: (filename) => toast.success(`${filename} ready for editing`, { id: 'probe' }),
-  probeFailed: (filename, error) => toast.error(`Failed to analyze ${filename}: ${error}`, { id: 'probe' }),
+  probeFailed: (filename, error) => toast.error(`Failed to analyze ${filename}: ${describe(error)}`, { id: 'probe' }),
   
   converting: (filename) => toast.loading(`Converting ${filename} to MP4...`, { id: 'convert' }),
   conversionComplete: (filename) => toast.success(`${filename} converted to MP4 successfully`, { id: 'convert' }),
-  conversionFailed: (filename, error) => toast.error(`Failed to convert ${filename}: ${error}`, { id: 'convert' }),
+  conversionFailed: (filename, error) => toast.error(`Failed to convert ${filename}: ${describe(error)}`, { id: 'convert' }),
   
   unsupportedFormat: (filename) => toast.error(`Unsupported file format: ${filename}`, { duration: 5000 }),
   fileTooLarge: (filename, size) => toast.error(`File too large: ${filename} (${size})`, { duration: 5000 }),
@@ -37,11 +43,11 @@ export const recordingToasts = {
   // Screen recording
   screenStarting: () => toast.loading('Starting screen recording...', { id: 'screen-recording' }),
   screenStarted: () => toast.success('Screen recording started', { id: 'screen-recording' }),
-  screenStartFailed: (error) => toast.error(`Failed to start screen recording: ${error}`, { id: 'screen-recording' }),
+  screenStartFailed: (error) => toast.error(`Failed to start screen recording: ${describe(error)}`, { id: 'screen-recording' }),
   
   screenSaving: () => toast.loading('Saving screen recording...', { id: 'screen-recording' }),
   screenSaved: () => toast.success('Screen recording saved successfully!', { id: 'screen-recording' }),
-  screenSaveFailed: (error) => toast.error(`Failed to save screen recording: ${error}`, { id: 'screen-recording' }),
+  screenSaveFailed: (error) => toast.error(`Failed to save screen recording: ${describe(error)}`, { id: 'screen-recording' }),
   
   screenTranscoding: () => toast.loading('Converting to MP4...', { id: 'screen-recording' }),
   screenCompleted: () => toast.success('Screen recording completed successfully!', { id: 'screen-recording' }),
@@ -49,11 +55,11 @@ export const recordingToasts = {
   // Webcam recording
   webcamStarting: () => toast.loading('Starting webcam recording...', { id: 'webcam-recording' }),
   webcamStarted: () => toast.success('Webcam recording started', { id: 'webcam-recording' }),
-  webcamStartFailed: (error) => toast.error(`Failed to start webcam recording: ${error}`, { id: 'webcam-recording' }),
+  webcamStartFailed: (error) => toast.error(`Failed to start webcam recording: ${describe(error)}`, { id: 'webcam-recording' }),
   
   webcamSaving: () => toast.loading('Saving webcam recording...', { id: 'webcam-recording' }),
   webcamSaved: () => toast.success('Webcam recording saved successfully!', { id: 'webcam-recording' }),
-  webcamSaveFailed: (error) => toast.error(`Failed to save webcam recording: ${error}`, { id: 'webcam-recording' }),
+  webcamSaveFailed: (error) => toast.error(`Failed to save webcam recording: ${describe(error)}`, { id: 'webcam-recording' }),
   
   webcamTranscoding: () => toast.loading('Converting to MP4...', { id: 'webcam-recording' }),
   webcamCompleted: () => toast.success('Webcam recording completed successfully!', { id: 'webcam-recording' }),
@@ -67,7 +73,7 @@ export const recordingToasts = {
 export const editingToasts = {
   trimming: (filename) => toast.loading(`Trimming ${filename}...`, { id: 'trim' }),
   trimmed: (filename) => toast.success(`${filename} trimmed successfully!`, { id: 'trim' }),
-  trimFailed: (filename, error) => toast.error(`Failed to trim ${filename}: ${error}`, { id: 'trim' }),
+  trimFailed: (filename, error) => toast.error(`Failed to trim ${filename}: ${describe(error)}`, { id: 'trim' }),
   
   addedToTimeline: (filename) => toast.success(`Added ${filename} to timeline`, { duration: 2000 }),
   removedFromTimeline: (filename) => toast.success(`Removed ${filename} from timeline`, { duration: 2000 }),
@@ -82,7 +88,7 @@ export const exportToasts = {
   starting: () => toast.loading('Starting export...', { id: 'export' }),
   processing: () => toast.loading('Processing video...', { id: 'export' }),
   completed: (filename) => toast.success(`Export completed: ${filename}`, { id: 'export' }),
-  failed: (error) => toast.error(`Export failed: ${error}`, { id: 'export' }),
+  failed: (error) => toast.error(`Export failed: ${describe(error)}`, { id: 'export' }),
   
   retryingWithFallback: () => toast.loading('Retrying with alternative method...', { id: 'export' }),
   fallbackSuccess: (filename) => toast.success(`Export completed using fallback method: ${filename}`, { id: 'export' }),
@@ -107,11 +113,11 @@ export const systemToasts = {
 export const fileToasts = {
   copying: (filename) => toast.loading(`Copying ${filename}...`, { id: 'file-copy' }),
   copied: (filename) => toast.success(`${filename} copied successfully`, { id: 'file-copy' }),
-  copyFailed: (filename, error) => toast.error(`Failed to copy ${filename}: ${error}`, { id: 'file-copy' }),
+  copyFailed: (filename, error) => toast.error(`Failed to copy ${filename}: ${describe(error)}`, { id: 'file-copy' }),
   
   deleting: (filename) => toast.loading(`Deleting ${filename}...`, { id: 'file-delete' }),
   deleted: (filename) => toast.success(`${filename} deleted`, { id: 'file-delete' }),
-  deleteFailed: (filename, error) => toast.error(`Failed to delete ${filename}: ${error}`, { id: 'file-delete' }),
+  deleteFailed: (filename, error) => toast.error(`Failed to delete ${filename}: ${describe(error)}`, { id: 'file-delete' }),
   
   accessDenied: (filename) => toast.error(`Access denied: ${filename}`, { duration: 4000 }),
   fileNotFound: (filename) => toast.error(`File not found: ${filename}`, { duration: 4000 }),